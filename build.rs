@@ -1,4 +1,12 @@
+use std::{env, path::PathBuf};
+
 fn main() {
-    tonic_prost_build::compile_protos("proto/service.proto").unwrap();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    // The descriptor set backs the gRPC server reflection service
+    tonic_prost_build::configure()
+        .file_descriptor_set_path(out_dir.join("service_descriptor.bin"))
+        .compile_protos(&["proto/service.proto"], &["proto"])
+        .unwrap();
     println!("cargo:rerun-if-changed=proto/service.proto");
 }
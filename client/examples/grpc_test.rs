@@ -1,5 +1,4 @@
 use tonic::Request;
-use std::time::Duration;
 
 pub mod pb {
     tonic::include_proto!("service");
@@ -11,7 +10,7 @@ use pb::*;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 LinkWithMentor gRPC Client - Comprehensive Testing\n");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
 
     // Connect to gRPC server
     println!("\n📡 Connecting to gRPC server at http://127.0.0.1:3001...");
@@ -19,56 +18,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("✅ Connected!\n");
 
     // Test 1: Ping
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     println!("Test 1: Ping Service");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     test_ping(&mut client).await?;
 
     // Test 2: Create User
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("Test 2: Create User");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     let user_id = test_create_user(&mut client).await?;
 
     // Test 3: Get User
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("Test 3: Get User");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     test_get_user(&mut client, user_id).await?;
 
     // Test 4: Create Session
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("Test 4: Create Session");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     test_create_session(&mut client, user_id).await?;
 
     // Test 5: Get User Sessions
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("Test 5: Get User Sessions");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     test_get_sessions(&mut client, user_id).await?;
 
     // Test 6: Register Device Token
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("Test 6: Register Device Token");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     test_register_device_token(&mut client, user_id).await?;
 
     // Test 7: Send Notification
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("Test 7: Send Notification");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     test_send_notification(&mut client, user_id).await?;
 
     // Test 8: Get Unread Notifications
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("Test 8: Get Unread Notifications");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
     test_get_notifications(&mut client, user_id).await?;
 
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("✅ All gRPC tests completed successfully!");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
 
     Ok(())
 }
@@ -94,6 +93,7 @@ async fn test_create_user(client: &mut LinkWithMentorClient<tonic::transport::Ch
         display_name: Some("Test User".to_string()),
         photo_url: Some("https://example.com/photo.jpg".to_string()),
         role: Some("user".to_string()),
+        time_zone: Some("Europe/Berlin".to_string()),
     });
 
     let response = client.create_user(request).await?;
@@ -130,9 +130,11 @@ async fn test_create_session(client: &mut LinkWithMentorClient<tonic::transport:
         mentor_id: user_id, // Using same user as mentor for testing
         title: "Test Mentorship Session".to_string(),
         description: Some("This is a test session".to_string()),
-        scheduled_at: "2025-12-01 10:00:00".to_string(),
+        scheduled_at: "2025-12-01T10:00:00Z".to_string(),
         duration_minutes: Some(60),
         meeting_link: Some("https://meet.example.com/test".to_string()),
+        scheduled_time: None,
+        recurrence: None,
     });
 
     let response = client.create_session(request).await?;
@@ -141,7 +143,7 @@ async fn test_create_session(client: &mut LinkWithMentorClient<tonic::transport:
     println!("✅ Session created:");
     println!("   ID: {}", session.id);
     println!("   Title: {}", session.title);
-    println!("   Scheduled: {}", session.scheduled_at);
+    println!("   Scheduled: {} ({} {})", session.scheduled_at, session.scheduled_at_local, session.time_zone);
     println!("   Duration: {} minutes", session.duration_minutes);
 
     Ok(())
@@ -183,6 +185,7 @@ async fn test_send_notification(client: &mut LinkWithMentorClient<tonic::transpo
         body: "This is a test notification from the gRPC client".to_string(),
         notification_type: "standard".to_string(),
         data: None,
+        payload: None,
     });
 
     let response = client.send_notification(request).await?;
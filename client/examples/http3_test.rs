@@ -1,3 +1,4 @@
+use bytes::{Buf, Bytes};
 use quinn::{ClientConfig, Endpoint};
 use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
 use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
@@ -46,10 +47,12 @@ impl ServerCertVerifier for SkipServerVerification {
     }
 }
 
+type SendRequest = h3::client::SendRequest<h3_quinn::OpenStreams, Bytes>;
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 LinkWithMentor HTTP/3 Client - Comprehensive Testing\n");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
 
     // Create TLS config
     let mut crypto = rustls::ClientConfig::builder()
@@ -66,104 +69,126 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut endpoint = Endpoint::client("0.0.0.0:0".parse()?)?;
     endpoint.set_default_client_config(client_config);
 
-    // Test 1: Basic connection
-    println!("\nTest 1: Basic Connection");
-    println!("=" .repeat(60));
-    test_basic_connection(&endpoint).await?;
+    // Test 1: Basic request
+    println!("\nTest 1: Basic Request");
+    println!("{}", "=".repeat(60));
+    test_basic_request(&endpoint).await?;
 
-    // Test 2: Multiple streams
-    println!("\n" .repeat(1) + &"=".repeat(60));
-    println!("Test 2: Multiple Bidirectional Streams");
-    println!("=" .repeat(60));
-    test_multiple_streams(&endpoint).await?;
+    // Test 2: Multiple concurrent requests
+    println!("\n{}", "=".repeat(60));
+    println!("Test 2: Multiple Concurrent Requests");
+    println!("{}", "=".repeat(60));
+    test_multiple_requests(&endpoint).await?;
 
-    // Test 3: Large message
-    println!("\n" .repeat(1) + &"=".repeat(60));
-    println!("Test 3: Large Message Transfer");
-    println!("=" .repeat(60));
-    test_large_message(&endpoint).await?;
+    // Test 3: Error statuses
+    println!("\n{}", "=".repeat(60));
+    println!("Test 3: Error Statuses");
+    println!("{}", "=".repeat(60));
+    test_error_statuses(&endpoint).await?;
 
-    println!("\n" .repeat(1) + &"=".repeat(60));
+    println!("\n{}", "=".repeat(60));
     println!("✅ All HTTP/3 tests completed successfully!");
-    println!("=" .repeat(60));
+    println!("{}", "=".repeat(60));
 
     endpoint.wait_idle().await;
     Ok(())
 }
 
-async fn test_basic_connection(endpoint: &Endpoint) -> Result<(), Box<dyn std::error::Error>> {
+/// Open an HTTP/3 connection and spawn its driver
+async fn connect(endpoint: &Endpoint) -> Result<SendRequest, Box<dyn std::error::Error>> {
     println!("  → Connecting to 127.0.0.1:3000...");
     let connection = endpoint.connect("127.0.0.1:3000".parse()?, "localhost")?.await?;
-    println!("  → Connected! Opening stream...");
+    let (mut driver, send_request) = h3::client::new(h3_quinn::Connection::new(connection)).await?;
 
-    let (mut send, mut recv) = connection.open_bi().await?;
+    tokio::spawn(async move {
+        let e = std::future::poll_fn(|cx| driver.poll_close(cx)).await;
+        if !e.is_h3_no_error() {
+            println!("  → Connection closed: {}", e);
+        }
+    });
 
-    let message = b"Hello from HTTP/3 test client!";
-    send.write_all(message).await?;
-    send.finish()?;
-    println!("  → Sent: {}", String::from_utf8_lossy(message));
+    Ok(send_request)
+}
 
-    let response = recv.read_to_end(1024).await?;
-    println!("  → Received: {}", String::from_utf8_lossy(&response));
+/// Send one request and return the status code and body
+async fn send(
+    send_request: &mut SendRequest,
+    method: http::Method,
+    path: &str,
+    body: Option<Bytes>,
+) -> Result<(http::StatusCode, String), Box<dyn std::error::Error + Send + Sync>> {
+    let request = http::Request::builder()
+        .method(method)
+        .uri(format!("https://localhost:3000{}", path))
+        .body(())?;
+
+    let mut stream = send_request.send_request(request).await?;
+    if let Some(body) = body {
+        stream.send_data(body).await?;
+    }
+    stream.finish().await?;
 
-    connection.close(0u32.into(), b"test complete");
-    println!("✅ Basic connection test passed");
+    let response = stream.recv_response().await?;
+    let mut body = Vec::new();
+    while let Some(mut chunk) = stream.recv_data().await? {
+        body.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
 
+    Ok((response.status(), String::from_utf8_lossy(&body).into_owned()))
+}
+
+async fn test_basic_request(endpoint: &Endpoint) -> Result<(), Box<dyn std::error::Error>> {
+    let mut send_request = connect(endpoint).await?;
+    println!("  → Connected! Sending GET /health...");
+
+    let (status, body) = send(&mut send_request, http::Method::GET, "/health", None).await
+        .map_err(|e| e.to_string())?;
+    println!("  → Status: {}", status);
+    println!("  → Body: {}", body);
+
+    println!("✅ Basic request test passed");
     Ok(())
 }
 
-async fn test_multiple_streams(endpoint: &Endpoint) -> Result<(), Box<dyn std::error::Error>> {
-    println!("  → Connecting to 127.0.0.1:3000...");
-    let connection = endpoint.connect("127.0.0.1:3000".parse()?, "localhost")?.await?;
-    println!("  → Opening 5 concurrent streams...");
+async fn test_multiple_requests(endpoint: &Endpoint) -> Result<(), Box<dyn std::error::Error>> {
+    let send_request = connect(endpoint).await?;
+    println!("  → Sending 5 concurrent requests...");
 
     let mut handles = vec![];
 
     for i in 1..=5 {
-        let conn = connection.clone();
+        let mut send_request = send_request.clone();
         let handle = tokio::spawn(async move {
-            let (mut send, mut recv) = conn.open_bi().await?;
-            
-            let message = format!("Stream {} message", i);
-            send.write_all(message.as_bytes()).await?;
-            send.finish()?;
-
-            let response = recv.read_to_end(1024).await?;
-            println!("  → Stream {}: {}", i, String::from_utf8_lossy(&response));
-
+            let (status, body) = send(&mut send_request, http::Method::GET, "/", None).await?;
+            println!("  → Request {}: {} {}", i, status, body);
             Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
         });
         handles.push(handle);
     }
 
     for handle in handles {
-        handle.await??;
+        handle.await?.map_err(|e| e.to_string())?;
     }
 
-    connection.close(0u32.into(), b"test complete");
-    println!("✅ Multiple streams test passed");
-
+    println!("✅ Multiple requests test passed");
     Ok(())
 }
 
-async fn test_large_message(endpoint: &Endpoint) -> Result<(), Box<dyn std::error::Error>> {
-    println!("  → Connecting to 127.0.0.1:3000...");
-    let connection = endpoint.connect("127.0.0.1:3000".parse()?, "localhost")?.await?;
-    println!("  → Opening stream for large message...");
-
-    let (mut send, mut recv) = connection.open_bi().await?;
-
-    // Send 10KB message
-    let large_message = vec![b'A'; 10 * 1024];
-    send.write_all(&large_message).await?;
-    send.finish()?;
-    println!("  → Sent: {} bytes", large_message.len());
+async fn test_error_statuses(endpoint: &Endpoint) -> Result<(), Box<dyn std::error::Error>> {
+    let mut send_request = connect(endpoint).await?;
 
-    let response = recv.read_to_end(100 * 1024).await?;
-    println!("  → Received: {} bytes", response.len());
+    let (status, _) = send(&mut send_request, http::Method::GET, "/does-not-exist", None).await
+        .map_err(|e| e.to_string())?;
+    println!("  → GET /does-not-exist: {}", status);
+    assert_eq!(status, http::StatusCode::NOT_FOUND);
 
-    connection.close(0u32.into(), b"test complete");
-    println!("✅ Large message test passed");
+    // 10KB body to a read-only route
+    let large_body = Bytes::from(vec![b'A'; 10 * 1024]);
+    let (status, _) = send(&mut send_request, http::Method::POST, "/health", Some(large_body)).await
+        .map_err(|e| e.to_string())?;
+    println!("  → POST /health: {}", status);
+    assert_eq!(status, http::StatusCode::METHOD_NOT_ALLOWED);
 
+    println!("✅ Error status test passed");
     Ok(())
 }
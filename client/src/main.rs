@@ -1,4 +1,4 @@
-use tonic::transport::Channel;
+use bytes::Buf;
 use quinn::{ClientConfig, Endpoint};
 use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
 use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
@@ -15,7 +15,7 @@ use pb::{PingRequest};
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 LinkWithMentor Client - Testing Tool\n");
-    println!("=" .repeat(50));
+    println!("{}", "=".repeat(50));
 
     // Test HTTP/3 (QUIC) connection
     println!("\n📡 Testing HTTP/3 (QUIC) connection...");
@@ -31,7 +31,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Err(e) => println!("❌ gRPC test failed: {}", e),
     }
 
-    println!("\n" .repeat(1) + &"=".repeat(50));
+    println!("\n{}", "=".repeat(50));
     println!("✅ All tests completed!");
     Ok(())
 }
@@ -97,21 +97,29 @@ async fn test_http3() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("  → Connecting to 127.0.0.1:3000...");
     let connection = endpoint.connect("127.0.0.1:3000".parse()?, "localhost")?.await?;
-    println!("  → Connected! Opening bidirectional stream...");
+    println!("  → Connected! Sending GET /health...");
 
-    let (mut send, mut recv) = connection.open_bi().await?;
+    let (mut driver, mut send_request) = h3::client::new(h3_quinn::Connection::new(connection)).await?;
+    let drive = tokio::spawn(async move {
+        std::future::poll_fn(|cx| driver.poll_close(cx)).await
+    });
 
-    // Send a simple message
-    let message = b"Hello from HTTP/3 client!";
-    send.write_all(message).await?;
-    send.finish()?;
-    println!("  → Sent: {}", String::from_utf8_lossy(message));
+    let request = http::Request::get("https://localhost:3000/health").body(())?;
+    let mut stream = send_request.send_request(request).await?;
+    stream.finish().await?;
 
     // Receive response
-    let response = recv.read_to_end(1024).await?;
-    println!("  → Received: {}", String::from_utf8_lossy(&response));
+    let response = stream.recv_response().await?;
+    let mut body = Vec::new();
+    while let Some(mut chunk) = stream.recv_data().await? {
+        body.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
+    println!("  → Status: {}", response.status());
+    println!("  → Received: {}", String::from_utf8_lossy(&body));
 
-    connection.close(0u32.into(), b"done");
+    // Dropping the last request handle lets the connection close gracefully
+    drop(send_request);
+    drive.await?;
     endpoint.wait_idle().await;
 
     Ok(())
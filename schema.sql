@@ -8,12 +8,105 @@ CREATE TABLE IF NOT EXISTS users (
     display_name VARCHAR(255),
     photo_url TEXT,
     role ENUM('user', 'mentor', 'admin') DEFAULT 'user',
+    time_zone VARCHAR(64) NOT NULL DEFAULT 'UTC',
     created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
     INDEX idx_firebase_uid (firebase_uid),
     INDEX idx_email (email)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
 
+-- Mentors' weekly recurring availability, in the mentor's time zone
+CREATE TABLE IF NOT EXISTS availability_rules (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    mentor_id BIGINT UNSIGNED NOT NULL,
+    weekday TINYINT UNSIGNED NOT NULL, -- ISO weekday, 1 = Monday
+    start_time TIME NOT NULL,
+    end_time TIME NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (mentor_id) REFERENCES users(id) ON DELETE CASCADE,
+    INDEX idx_mentor_id (mentor_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Date-specific availability: override windows replace the weekly ones, a blackout clears the day
+CREATE TABLE IF NOT EXISTS availability_exceptions (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    mentor_id BIGINT UNSIGNED NOT NULL,
+    date DATE NOT NULL,
+    is_blackout BOOLEAN NOT NULL DEFAULT FALSE,
+    start_time TIME,
+    end_time TIME,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (mentor_id) REFERENCES users(id) ON DELETE CASCADE,
+    INDEX idx_mentor_date (mentor_id, date)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Mentor profiles, one per mentor
+CREATE TABLE IF NOT EXISTS mentor_profiles (
+    user_id BIGINT UNSIGNED PRIMARY KEY,
+    headline VARCHAR(160) NOT NULL,
+    bio TEXT,
+    years_experience SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    hourly_rate_cents INT UNSIGNED, -- NULL when the mentor doesn't publish a rate
+    currency CHAR(3), -- ISO 4217, set together with hourly_rate_cents
+    verification_status ENUM('unverified', 'pending', 'verified', 'rejected') NOT NULL DEFAULT 'unverified',
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    INDEX idx_verification_status (verification_status)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Skill tags shared between mentors, stored lowercase
+CREATE TABLE IF NOT EXISTS skills (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(64) UNIQUE NOT NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS mentor_skills (
+    mentor_id BIGINT UNSIGNED NOT NULL,
+    skill_id BIGINT UNSIGNED NOT NULL,
+    PRIMARY KEY (mentor_id, skill_id),
+    FOREIGN KEY (mentor_id) REFERENCES mentor_profiles(user_id) ON DELETE CASCADE,
+    FOREIGN KEY (skill_id) REFERENCES skills(id) ON DELETE CASCADE,
+    INDEX idx_skill_id (skill_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Spoken languages as ISO 639 codes
+CREATE TABLE IF NOT EXISTS mentor_languages (
+    mentor_id BIGINT UNSIGNED NOT NULL,
+    language VARCHAR(8) NOT NULL,
+    PRIMARY KEY (mentor_id, language),
+    FOREIGN KEY (mentor_id) REFERENCES mentor_profiles(user_id) ON DELETE CASCADE,
+    INDEX idx_language (language)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Requests from users to become mentors, reviewed by an admin
+CREATE TABLE IF NOT EXISTS mentor_applications (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    user_id BIGINT UNSIGNED NOT NULL,
+    motivation TEXT NOT NULL,
+    status ENUM('pending', 'approved', 'rejected') NOT NULL DEFAULT 'pending',
+    reviewed_by BIGINT UNSIGNED,
+    review_note TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    reviewed_at TIMESTAMP NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (reviewed_by) REFERENCES users(id) ON DELETE SET NULL,
+    INDEX idx_user_id (user_id),
+    INDEX idx_status (status)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Recurring session series; each occurrence is materialized as a row in sessions
+CREATE TABLE IF NOT EXISTS session_series (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    user_id BIGINT UNSIGNED NOT NULL,
+    mentor_id BIGINT UNSIGNED NOT NULL,
+    recurrence_rule VARCHAR(255) NOT NULL, -- RFC 5545 RRULE value, e.g. FREQ=WEEKLY;COUNT=10
+    time_zone VARCHAR(64) NOT NULL, -- zone whose wall-clock time the occurrences keep
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (mentor_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
 -- Sessions table
 CREATE TABLE IF NOT EXISTS sessions (
     id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
@@ -25,16 +118,88 @@ CREATE TABLE IF NOT EXISTS sessions (
     duration_minutes INT DEFAULT 60,
     status ENUM('scheduled', 'ongoing', 'completed', 'cancelled') DEFAULT 'scheduled',
     meeting_link TEXT,
+    cancelled_by BIGINT UNSIGNED,
+    cancellation_reason TEXT,
+    series_id BIGINT UNSIGNED,
+    original_scheduled_at TIMESTAMP NULL, -- start given by the series rule, kept when the occurrence moves
+    sequence INT UNSIGNED NOT NULL DEFAULT 0, -- iCalendar SEQUENCE, bumped on reschedule and cancellation
     created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
     FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
     FOREIGN KEY (mentor_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (cancelled_by) REFERENCES users(id) ON DELETE SET NULL,
+    FOREIGN KEY (series_id) REFERENCES session_series(id) ON DELETE SET NULL,
+    INDEX idx_series (series_id, original_scheduled_at),
     INDEX idx_user_id (user_id),
     INDEX idx_mentor_id (mentor_id),
     INDEX idx_status (status),
     INDEX idx_scheduled_at (scheduled_at)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
 
+-- Session status changes, one row per transition
+CREATE TABLE IF NOT EXISTS session_history (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    session_id BIGINT UNSIGNED NOT NULL,
+    changed_by BIGINT UNSIGNED,
+    from_status ENUM('scheduled', 'ongoing', 'completed', 'cancelled') NOT NULL,
+    to_status ENUM('scheduled', 'ongoing', 'completed', 'cancelled') NOT NULL,
+    reason TEXT,
+    previous_scheduled_at TIMESTAMP NULL,
+    new_scheduled_at TIMESTAMP NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+    FOREIGN KEY (changed_by) REFERENCES users(id) ON DELETE SET NULL,
+    INDEX idx_session_id (session_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Reminders claimed per session start, so restarts and other replicas don't send them again
+CREATE TABLE IF NOT EXISTS session_reminders (
+    session_id BIGINT UNSIGNED NOT NULL,
+    scheduled_at TIMESTAMP NOT NULL, -- the start the reminder was for; a reschedule gets fresh reminders
+    offset_minutes INT UNSIGNED NOT NULL, -- 0 is the "starting now" ping
+    skipped BOOLEAN NOT NULL DEFAULT FALSE, -- superseded by a reminder closer to the start
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (session_id, scheduled_at, offset_minutes),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Reviews of completed sessions, at most one per participant per session
+CREATE TABLE IF NOT EXISTS session_reviews (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    session_id BIGINT UNSIGNED NOT NULL,
+    reviewer_id BIGINT UNSIGNED NOT NULL,
+    reviewee_id BIGINT UNSIGNED NOT NULL,
+    reviewer_role ENUM('mentee', 'mentor') NOT NULL,
+    rating TINYINT UNSIGNED NOT NULL,
+    comment TEXT,
+    is_hidden BOOLEAN NOT NULL DEFAULT FALSE,
+    hidden_by BIGINT UNSIGNED,
+    hidden_reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_session_reviewer (session_id, reviewer_id),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+    FOREIGN KEY (reviewer_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (reviewee_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (hidden_by) REFERENCES users(id) ON DELETE SET NULL,
+    INDEX idx_reviewee (reviewee_id, reviewer_role, id),
+    CHECK (rating BETWEEN 1 AND 5)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Mentor rating aggregates over visible mentee reviews, updated together with session_reviews
+CREATE TABLE IF NOT EXISTS mentor_ratings (
+    mentor_id BIGINT UNSIGNED PRIMARY KEY,
+    review_count INT NOT NULL DEFAULT 0,
+    rating_sum INT NOT NULL DEFAULT 0,
+    rating_1_count INT NOT NULL DEFAULT 0,
+    rating_2_count INT NOT NULL DEFAULT 0,
+    rating_3_count INT NOT NULL DEFAULT 0,
+    rating_4_count INT NOT NULL DEFAULT 0,
+    rating_5_count INT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (mentor_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
 -- Notifications table
 CREATE TABLE IF NOT EXISTS notifications (
     id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
@@ -47,19 +212,76 @@ CREATE TABLE IF NOT EXISTS notifications (
     created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
     INDEX idx_user_id (user_id),
+    INDEX idx_user_is_read (user_id, is_read), -- unread counts
     INDEX idx_is_read (is_read),
-    INDEX idx_created_at (created_at)
+    INDEX idx_created_at (created_at) -- read notifications past NOTIFICATION_RETENTION_DAYS are purged
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Channel per user and notification type; types without a row are pushed
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id BIGINT UNSIGNED NOT NULL,
+    notification_type ENUM('standard', 'link', 'image', 'chat', 'call') NOT NULL,
+    channel ENUM('push', 'in_app') NOT NULL DEFAULT 'push',
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, notification_type),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Quiet hours as wall-clock times in users.time_zone; they wrap past midnight when start > end
+CREATE TABLE IF NOT EXISTS notification_settings (
+    user_id BIGINT UNSIGNED PRIMARY KEY,
+    quiet_hours_start TIME,
+    quiet_hours_end TIME,
+    calls_bypass_quiet_hours BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Push deliveries of a notification, one per device the recipient had when it was created.
+-- Written in the same transaction as the notification and worked off by the outbox worker
+CREATE TABLE IF NOT EXISTS notification_deliveries (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    notification_id BIGINT UNSIGNED NOT NULL,
+    device_token VARCHAR(512) NOT NULL,
+    device_type ENUM('ios', 'android', 'web') NOT NULL,
+    provider ENUM('fcm', 'web_push') NOT NULL DEFAULT 'fcm',
+    status ENUM('pending', 'sent', 'failed') NOT NULL DEFAULT 'pending',
+    attempts INT UNSIGNED NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error TEXT,
+    sent_at TIMESTAMP NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (notification_id) REFERENCES notifications(id) ON DELETE CASCADE,
+    INDEX idx_notification_id (notification_id),
+    INDEX idx_status_next_attempt_at (status, next_attempt_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Secret iCalendar feed of a user's sessions, one per user. Only the SHA-256 of the token is kept
+CREATE TABLE IF NOT EXISTS calendar_feeds (
+    user_id BIGINT UNSIGNED PRIMARY KEY,
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
 
--- Device tokens for FCM
+-- Device tokens for FCM. Tokens FCM reports as invalid are deleted
 CREATE TABLE IF NOT EXISTS device_tokens (
     id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
     user_id BIGINT UNSIGNED NOT NULL,
-    token VARCHAR(255) UNIQUE NOT NULL,
+    -- FCM registration token, or the endpoint of a Web Push subscription
+    token VARCHAR(512) UNIQUE NOT NULL,
     device_type ENUM('ios', 'android', 'web') NOT NULL,
+    provider ENUM('fcm', 'web_push') NOT NULL DEFAULT 'fcm',
+    -- Base64url keys of a Web Push subscription; NULL for FCM tokens
+    web_push_p256dh VARCHAR(128),
+    web_push_auth VARCHAR(32),
+    -- Role whose FCM topic the token is subscribed to, besides "all"; NULL until the first sync
+    topic_role ENUM('user', 'mentor', 'admin'),
     created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
     FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
     INDEX idx_user_id (user_id),
-    INDEX idx_token (token)
+    INDEX idx_token (token),
+    INDEX idx_updated_at (updated_at) -- refreshed by RegisterDeviceToken; old tokens are expired
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
@@ -0,0 +1,2482 @@
+//! Transport-independent LinkWithMentor operations.
+//!
+//! Both the gRPC service (`grpc.rs`) and the REST routes (`rest.rs`) call into
+//! this module, so validation, authorization and database access live in one place.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tonic::Status;
+use tonic_types::{ErrorDetails, StatusExt};
+
+use crate::auth::CallerContext;
+use crate::availability::Schedule;
+use crate::models::{
+    AvailabilityException, AvailabilityRule, MentorApplication, MentorProfile, Notification, Session, SessionReview,
+    User,
+};
+use crate::search::MentorDocument;
+use crate::session_lifecycle::{OccurrenceScope, SessionAction};
+use crate::AppState;
+
+/// Values allowed by the `notifications.notification_type` ENUM
+pub const NOTIFICATION_TYPES: &[&str] = &["standard", "link", "image", "chat", "call"];
+
+/// Values allowed by the `device_tokens.device_type` ENUM
+pub const DEVICE_TYPES: &[&str] = &["ios", "android", "web"];
+
+/// Values allowed by the `users.role` ENUM
+pub const ROLES: &[&str] = &["user", "mentor", "admin"];
+
+/// Values allowed by the `mentor_applications.status` ENUM
+pub const APPLICATION_STATUSES: &[&str] = &["pending", "approved", "rejected"];
+
+/// Accepted values of `MentorSearch::sort`
+pub const SEARCH_SORT_ORDERS: &[&str] = &["relevance", "rating", "price"];
+
+/// How often an idle notification stream sends a heartbeat
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How far ahead `get_availability` lists date-specific availability
+const UPCOMING_AVAILABILITY_DAYS: u64 = 365;
+
+/// Notifications fetched per query when replaying a subscriber's backlog
+const REPLAY_PAGE_SIZE: u32 = 100;
+
+/// Page size of `list_notifications` when the request doesn't give one, and the largest allowed
+const DEFAULT_NOTIFICATION_PAGE_SIZE: u32 = 20;
+const MAX_NOTIFICATION_PAGE_SIZE: u32 = 100;
+
+/// Most notification IDs one `mark_notifications_read` call accepts
+const MAX_BULK_NOTIFICATION_IDS: usize = 100;
+
+/// Length of `device_tokens.token`, which also holds Web Push endpoints
+const MAX_DEVICE_TOKEN_LEN: usize = 512;
+
+/// Most users one broadcast may list; larger audiences should use a topic
+const MAX_BROADCAST_USERS: usize = 1000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewUser {
+    /// May be left empty when the ID token carries an email, and must match it otherwise
+    #[serde(default)]
+    pub email: String,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    /// Only `user` is accepted: mentors are approved through `ApplyForMentor`, and admins are set directly
+    pub role: Option<String>,
+    /// IANA time zone; defaults to UTC
+    pub time_zone: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeZoneUpdate {
+    pub time_zone: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum UserLookup {
+    Id(u64),
+    FirebaseUid(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MentorProfileInput {
+    pub headline: String,
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub years_experience: u32,
+    pub hourly_rate_cents: Option<u32>,
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub skills: Vec<String>,
+}
+
+/// A mentor profile with its languages and skills
+#[derive(Debug, Clone, Serialize)]
+pub struct MentorProfileDetails {
+    #[serde(flatten)]
+    pub profile: MentorProfile,
+    pub languages: Vec<String>,
+    pub skills: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VerificationUpdate {
+    pub verification_status: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewMentorApplication {
+    pub motivation: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MentorApplicationReview {
+    pub approve: bool,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSession {
+    pub mentor_id: u64,
+    pub title: String,
+    pub description: Option<String>,
+    /// RFC 3339; see `crate::timestamp::parse`
+    pub scheduled_at: String,
+    pub duration_minutes: Option<i32>,
+    pub meeting_link: Option<String>,
+    /// Weekly RRULE subset, expanded in the caller's time zone; see `crate::recurrence`
+    pub recurrence: Option<String>,
+}
+
+/// A session as seen by the caller, with its start also given in the caller's time zone
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionView {
+    #[serde(flatten)]
+    pub session: Session,
+    pub scheduled_at_local: String,
+    /// The caller's time zone, used for `scheduled_at_local`
+    pub time_zone: String,
+}
+
+impl SessionView {
+    pub fn new(caller: &CallerContext, session: Session) -> Self {
+        let time_zone = viewer_time_zone(caller);
+        Self {
+            scheduled_at_local: crate::timestamp::format_local(session.scheduled_at, time_zone),
+            time_zone: time_zone.name().to_string(),
+            session,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeWindowInput {
+    pub start_time: String,
+    pub end_time: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeeklyWindowInput {
+    /// ISO weekday, 1 = Monday
+    pub weekday: u32,
+    pub start_time: String,
+    pub end_time: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeeklyAvailability {
+    /// IANA time zone; keeps the current one when unset
+    pub time_zone: Option<String>,
+    pub windows: Vec<WeeklyWindowInput>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DateAvailability {
+    pub date: String,
+    #[serde(default)]
+    pub blackout: bool,
+    #[serde(default)]
+    pub windows: Vec<TimeWindowInput>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlotQuery {
+    pub mentor_id: u64,
+    pub from_date: String,
+    pub to_date: String,
+    pub slot_minutes: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MentorAvailability {
+    pub mentor_id: u64,
+    pub time_zone: String,
+    pub weekly: Vec<AvailabilityRule>,
+    /// Date-specific overrides and blackouts in the coming year
+    pub dates: Vec<AvailabilityException>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Slot {
+    #[serde(with = "crate::timestamp::utc")]
+    pub start_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
+    pub end_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BookableSlots {
+    pub time_zone: String,
+    pub slots: Vec<Slot>,
+}
+
+/// Mentor search filters; empty fields don't filter
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MentorSearch {
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub skills: Vec<String>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    pub min_price_cents: Option<u32>,
+    pub max_price_cents: Option<u32>,
+    pub currency: Option<String>,
+    pub min_rating: Option<f64>,
+    pub available_within_days: Option<u32>,
+    /// One of [`SEARCH_SORT_ORDERS`]; relevance when empty
+    #[serde(default)]
+    pub sort: String,
+    #[serde(default)]
+    pub page_size: u32,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MentorSearchResults {
+    pub mentors: Vec<MentorDocument>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReviewInput {
+    /// 1 to 5 stars
+    pub rating: u32,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReviewVisibilityUpdate {
+    pub hidden: bool,
+    pub reason: Option<String>,
+}
+
+/// A page of a mentor's reviews with their rating aggregates
+#[derive(Debug, Clone, Serialize)]
+pub struct MentorReviews {
+    pub mentor_id: u64,
+    pub summary: crate::review::RatingSummary,
+    pub reviews: Vec<SessionReview>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SessionCancellation {
+    pub reason: Option<String>,
+    /// `this` (default) or `following` for sessions in a series
+    #[serde(default)]
+    pub scope: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionReschedule {
+    pub scheduled_at: String,
+    pub reason: Option<String>,
+    /// `this` (default) or `following` for sessions in a series
+    #[serde(default)]
+    pub scope: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarFeed {
+    /// Secret URL of the caller's `.ics` feed; anyone holding it can read their sessions
+    pub feed_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewNotification {
+    pub user_id: u64,
+    pub title: String,
+    pub body: String,
+    #[serde(default = "default_notification_type")]
+    pub notification_type: String,
+    pub data: Option<String>,
+    /// Typed payload, e.g. `{"link": {"url": "..."}}`
+    #[serde(default)]
+    pub payload: Option<crate::firebase::NotificationType>,
+}
+
+/// Inbox listing filters; empty fields don't filter
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationQuery {
+    pub is_read: Option<bool>,
+    pub notification_type: Option<String>,
+    /// RFC 3339; notifications created at or after
+    pub created_from: Option<String>,
+    /// RFC 3339; notifications created before
+    pub created_to: Option<String>,
+    #[serde(default)]
+    pub page_size: u32,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationPage {
+    pub notifications: Vec<Notification>,
+    pub next_cursor: Option<String>,
+    /// All of the caller's unread notifications, regardless of the filters
+    pub unread_count: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationIds {
+    pub notification_ids: Vec<u64>,
+}
+
+/// Who a broadcast goes to, e.g. `{"topic": "role_mentor"}` or `{"user_ids": [1, 2]}`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastTarget {
+    Topic(String),
+    Condition(String),
+    UserIds(Vec<u64>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewBroadcast {
+    #[serde(flatten)]
+    pub target: BroadcastTarget,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub payload: Option<crate::firebase::NotificationType>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BroadcastResult {
+    /// FCM message ID of a topic or condition broadcast
+    pub message_id: Option<String>,
+    /// One per device of a broadcast to users
+    pub devices: Vec<crate::broadcast::DeviceResult>,
+    pub sent_count: u32,
+    pub failed_count: u32,
+    /// Listed users not pushed to because they chose in-app only or are in quiet hours
+    pub skipped_user_ids: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationChannelInput {
+    pub notification_type: String,
+    pub channel: String,
+}
+
+/// Replaces all of the caller's notification preferences
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationPreferencesUpdate {
+    /// Types left out go back to the default channel
+    #[serde(default)]
+    pub channels: Vec<NotificationChannelInput>,
+    /// `HH:MM` in the caller's time zone; both or neither
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    /// Defaults to true
+    pub calls_bypass_quiet_hours: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationPreferences {
+    /// Every notification type with its channel
+    pub channels: Vec<crate::models::NotificationChannel>,
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    pub calls_bypass_quiet_hours: bool,
+    pub time_zone: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewDeviceToken {
+    /// FCM registration token; empty for a Web Push subscription
+    #[serde(default)]
+    pub token: String,
+    pub device_type: String,
+    #[serde(default)]
+    pub web_push: Option<WebPushSubscription>,
+}
+
+/// A browser's `PushSubscription`, in the shape of its `toJSON()`
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebPushSubscription {
+    pub endpoint: String,
+    pub keys: WebPushKeys,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebPushKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// An item on a `subscribe_notifications` stream
+#[derive(Debug, Clone)]
+pub enum NotificationEvent {
+    Notification(Notification),
+    Heartbeat(chrono::NaiveDateTime),
+}
+
+fn default_notification_type() -> String {
+    "standard".to_string()
+}
+
+/// Count resource-level denials the same way `auth::AuthLayer` counts method-level ones
+fn authorize(state: &AppState, check: Result<(), Status>) -> Result<(), Status> {
+    if let Err(status) = &check
+        && status.code() == tonic::Code::PermissionDenied
+    {
+        state.metrics.increment_permission_denied();
+        tracing::warn!("Permission denied: {}", status.message());
+    }
+    check
+}
+
+/// Resolve the optional `user_id` of a listing request to a user the caller may read
+pub fn target_user(state: &AppState, caller: &CallerContext, requested: u64, resource: &str) -> Result<u64, Status> {
+    let caller_id = caller.user_id()?;
+    if requested == 0 || requested == caller_id {
+        return Ok(caller_id);
+    }
+    authorize(state, crate::policy::require_owner(caller, requested, resource))?;
+    Ok(requested)
+}
+
+fn require_non_empty(value: &str, field: &str) -> Result<(), Status> {
+    if value.trim().is_empty() {
+        return Err(Status::invalid_argument(format!("{} must not be empty", field)));
+    }
+    Ok(())
+}
+
+fn require_one_of(value: &str, allowed: &[&str], field: &str) -> Result<(), Status> {
+    if !allowed.contains(&value) {
+        return Err(Status::invalid_argument(format!(
+            "{} must be one of: {}",
+            field,
+            allowed.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// FAILED_PRECONDITION naming the overlapping sessions, with one `PreconditionFailure` violation each
+fn booking_conflict(conflicts: &[u64]) -> Status {
+    let mut details = ErrorDetails::new();
+    for id in conflicts {
+        details.add_precondition_failure_violation(
+            "SESSION_CONFLICT",
+            format!("sessions/{}", id),
+            "Overlaps the requested time window",
+        );
+    }
+
+    let ids: Vec<String> = conflicts.iter().map(u64::to_string).collect();
+    Status::with_error_details(
+        tonic::Code::FailedPrecondition,
+        format!("Session conflicts with existing sessions: {}", ids.join(", ")),
+        details,
+    )
+}
+
+/// The caller's preferred time zone, UTC until they set one
+fn viewer_time_zone(caller: &CallerContext) -> chrono_tz::Tz {
+    caller
+        .user
+        .as_ref()
+        .and_then(|user| user.time_zone.parse().ok())
+        .unwrap_or(chrono_tz::Tz::UTC)
+}
+
+/// A meeting link must be an http(s) URL; it goes into calendar invites verbatim, so
+/// control characters that could start another iCalendar property are refused
+fn validate_meeting_link(link: Option<String>) -> Result<Option<String>, Status> {
+    let Some(link) = link.filter(|link| !link.trim().is_empty()) else {
+        return Ok(None);
+    };
+    if link.chars().any(char::is_control) {
+        return Err(Status::invalid_argument("meeting_link must not contain control characters"));
+    }
+    match reqwest::Url::parse(&link) {
+        Ok(url) if url.scheme() == "https" || url.scheme() == "http" => Ok(Some(link)),
+        _ => Err(Status::invalid_argument("meeting_link must be an http or https URL")),
+    }
+}
+
+/// Whether `email` is a plain `local@domain` address (an RFC 5322 addr-spec without
+/// quoting or comments), safe to put in a `mailto:` URI
+fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    let local_ok = !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c));
+    let domain_ok = domain.split('.').count() >= 2
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    email.len() <= 254 && local_ok && domain_ok
+}
+
+/// Check a self-registration, returning the email to store and the time zone if one was given.
+/// The email comes from the ID token when it has one; the body may only repeat it.
+fn validate_new_user(input: &NewUser, token_email: Option<&str>) -> Result<(String, Option<String>), Status> {
+    let email = match token_email {
+        Some(token_email) if input.email.trim().is_empty() || input.email.trim().eq_ignore_ascii_case(token_email) => {
+            token_email.to_string()
+        }
+        Some(_) => return Err(Status::invalid_argument("email must match the email of the signed-in account")),
+        None => {
+            require_non_empty(&input.email, "email")?;
+            input.email.trim().to_string()
+        }
+    };
+    if !is_valid_email(&email) {
+        return Err(Status::invalid_argument("email must be a valid email address"));
+    }
+
+    match input.role.as_deref() {
+        None | Some("user") => {}
+        Some(role) => {
+            require_one_of(role, ROLES, "role")?;
+            return Err(Status::permission_denied(
+                "New users get the user role; apply with ApplyForMentor to become a mentor",
+            ));
+        }
+    }
+    let time_zone = match input.time_zone.as_deref().filter(|time_zone| !time_zone.is_empty()) {
+        Some(time_zone) => Some(crate::availability::parse_time_zone(time_zone)?.name().to_string()),
+        None => None,
+    };
+    Ok((email, time_zone))
+}
+
+pub async fn create_user(state: &AppState, caller: &CallerContext, input: NewUser) -> Result<User, Status> {
+    state.metrics.increment_requests();
+
+    // The Firebase UID always comes from the verified token, never the request body
+    let firebase_uid = caller.identity.uid.clone();
+
+    // Rate limiting check
+    if !state.rate_limiter.check_rate_limit(&firebase_uid) {
+        tracing::warn!("Rate limit exceeded for user: {}", firebase_uid);
+        state.metrics.increment_failed();
+        return Err(Status::resource_exhausted("Rate limit exceeded. Please try again later."));
+    }
+
+    let (email, time_zone) = match validate_new_user(&input, caller.identity.email.as_deref()) {
+        Ok(validated) => validated,
+        Err(status) => {
+            state.metrics.increment_failed();
+            return Err(status);
+        }
+    };
+
+    tracing::info!("Creating user: {}", email);
+
+    let user = crate::models::CreateUser {
+        firebase_uid,
+        email,
+        display_name: input.display_name,
+        photo_url: input.photo_url,
+        time_zone,
+    };
+
+    let user_id = crate::db::create_user(&state.db, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create user: {}", e);
+            state.metrics.increment_failed();
+            Status::internal(format!("Failed to create user: {}", e))
+        })?;
+
+    let created_user = crate::db::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| {
+            state.metrics.increment_failed();
+            Status::internal(format!("Failed to get user: {}", e))
+        })?
+        .ok_or_else(|| {
+            state.metrics.increment_failed();
+            Status::not_found("User not found after creation")
+        })?;
+
+    tracing::info!("User created successfully: ID {}", created_user.id);
+    state.metrics.increment_successful();
+    state.metrics.increment_users_created();
+
+    Ok(created_user)
+}
+
+pub async fn get_user(state: &AppState, caller: &CallerContext, lookup: UserLookup) -> Result<User, Status> {
+    let user = match lookup {
+        UserLookup::Id(id) => crate::db::get_user_by_id(&state.db, id).await,
+        UserLookup::FirebaseUid(uid) => crate::db::get_user_by_firebase_uid(&state.db, &uid).await,
+    }
+    .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+    .ok_or_else(|| Status::not_found("User not found"))?;
+
+    authorize(state, crate::policy::require_user_visible(caller, &user))?;
+
+    Ok(user)
+}
+
+/// Set the caller's preferred time zone, used to render times for them
+pub async fn set_time_zone(state: &AppState, caller: &CallerContext, input: TimeZoneUpdate) -> Result<User, Status> {
+    let user_id = caller.user_id()?;
+    let time_zone = crate::availability::parse_time_zone(&input.time_zone)?;
+
+    crate::db::update_user_time_zone(&state.db, user_id, time_zone.name())
+        .await
+        .map_err(|e| Status::internal(format!("Failed to update time zone: {}", e)))?;
+
+    // A mentor's availability windows are in their time zone
+    state.search.queue_mentor(user_id);
+
+    crate::db::get_user_by_id(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get user: {}", e)))?
+        .ok_or_else(|| Status::not_found("User not found"))
+}
+
+pub async fn create_session(state: &AppState, caller: &CallerContext, input: NewSession) -> Result<Session, Status> {
+    let user_id = caller.user_id()?;
+
+    require_non_empty(&input.title, "title")?;
+    let scheduled_at = crate::timestamp::parse(&input.scheduled_at)?;
+    if matches!(input.duration_minutes, Some(minutes) if minutes <= 0) {
+        return Err(Status::invalid_argument("duration_minutes must be positive"));
+    }
+    let meeting_link = validate_meeting_link(input.meeting_link)?;
+
+    let recurrence = input
+        .recurrence
+        .as_deref()
+        .filter(|rule| !rule.trim().is_empty())
+        .map(crate::recurrence::RecurrenceRule::parse)
+        .transpose()?;
+
+    let mentor = load_mentor(state, input.mentor_id).await?;
+    let duration = chrono::Duration::minutes(input.duration_minutes.unwrap_or(crate::db::DEFAULT_DURATION_MINUTES).into());
+
+    // Occurrences keep the caller's wall-clock time across daylight-saving changes
+    let time_zone = viewer_time_zone(caller);
+    let occurrences = match &recurrence {
+        Some(rule) => rule.occurrences(scheduled_at, time_zone)?,
+        None => vec![scheduled_at],
+    };
+    if occurrences.windows(2).any(|pair| pair[0] + duration > pair[1]) {
+        return Err(Status::invalid_argument("Occurrences of the series overlap each other"));
+    }
+
+    // Any time zone offset keeps the mentor's local date within a day of the UTC one
+    let first_date = occurrences[0].date();
+    let last_date = occurrences[occurrences.len() - 1].date();
+    let schedule = load_schedule(state, &mentor, first_date - chrono::Days::new(1), last_date + chrono::Days::new(1)).await?;
+    let unavailable: Vec<String> = occurrences
+        .iter()
+        .filter(|start_at| !schedule.contains(**start_at, **start_at + duration))
+        .map(|start_at| crate::timestamp::format_utc(*start_at))
+        .collect();
+    if !unavailable.is_empty() {
+        return Err(Status::failed_precondition(format!(
+            "Requested time is outside the mentor's availability, see ListBookableSlots: {}",
+            unavailable.join(", ")
+        )));
+    }
+
+    let session = crate::models::CreateSession {
+        user_id,
+        mentor_id: input.mentor_id,
+        title: input.title,
+        description: input.description,
+        scheduled_at,
+        duration_minutes: input.duration_minutes,
+        meeting_link,
+    };
+
+    let booking = match &recurrence {
+        Some(rule) => {
+            let series = crate::models::CreateSessionSeries {
+                recurrence_rule: rule.to_string(),
+                time_zone: time_zone.name().to_string(),
+            };
+            crate::db::create_session_series(&state.db, &series, &session, &occurrences).await
+        }
+        None => crate::db::create_session(&state.db, &session).await,
+    }
+    .map_err(|e| Status::internal(format!("Failed to create session: {}", e)))?;
+
+    let session_id = match booking {
+        crate::db::SessionBooking::Created(session_id) => session_id,
+        crate::db::SessionBooking::Conflicts(conflicts) => return Err(booking_conflict(&conflicts)),
+    };
+
+    state.metrics.increment_sessions_created();
+    state.search.queue_mentor(input.mentor_id);
+
+    crate::db::get_session_by_id(&state.db, session_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get session: {}", e)))?
+        .ok_or_else(|| Status::not_found("Session not found after creation"))
+}
+
+pub async fn list_sessions(state: &AppState, caller: &CallerContext, user_id: u64) -> Result<Vec<Session>, Status> {
+    let user_id = target_user(state, caller, user_id, "Sessions")?;
+
+    crate::db::get_sessions_by_user(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get sessions: {}", e)))
+}
+
+/// Load a user who must be a mentor
+async fn load_mentor(state: &AppState, mentor_id: u64) -> Result<User, Status> {
+    let mentor = crate::db::get_user_by_id(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get mentor: {}", e)))?
+        .ok_or_else(|| Status::not_found("Mentor not found"))?;
+
+    if mentor.role != "mentor" {
+        return Err(Status::invalid_argument(format!("User {} is not a mentor", mentor_id)));
+    }
+    Ok(mentor)
+}
+
+/// A mentor's schedule with the date-specific availability between two local dates
+async fn load_schedule(
+    state: &AppState,
+    mentor: &User,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+) -> Result<Schedule, Status> {
+    let time_zone = crate::availability::parse_time_zone(&mentor.time_zone)?;
+
+    let rules = crate::db::get_availability_rules(&state.db, mentor.id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get availability: {}", e)))?;
+    let exceptions = crate::db::get_availability_exceptions(&state.db, mentor.id, from, to)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get availability: {}", e)))?;
+
+    Ok(Schedule::new(time_zone, rules, exceptions))
+}
+
+/// Reject windows on the same day that overlap each other
+fn reject_overlaps(mut windows: Vec<(chrono::NaiveTime, chrono::NaiveTime)>) -> Result<(), Status> {
+    windows.sort();
+    for pair in windows.windows(2) {
+        if pair[1].0 < pair[0].1 {
+            return Err(Status::invalid_argument(format!(
+                "Availability windows overlap at {}",
+                pair[1].0.format("%H:%M")
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn parse_window(start_time: &str, end_time: &str) -> Result<(chrono::NaiveTime, chrono::NaiveTime), Status> {
+    let start = crate::availability::parse_time(start_time)?;
+    let end = crate::availability::parse_time(end_time)?;
+    crate::availability::validate_window(start, end)?;
+    Ok((start, end))
+}
+
+/// Replace the calling mentor's weekly availability
+pub async fn set_weekly_availability(
+    state: &AppState,
+    caller: &CallerContext,
+    input: WeeklyAvailability,
+) -> Result<MentorAvailability, Status> {
+    let mentor_id = caller.user_id()?;
+
+    let time_zone = match input.time_zone.filter(|time_zone| !time_zone.is_empty()) {
+        Some(time_zone) => crate::availability::parse_time_zone(&time_zone)?.name().to_string(),
+        None => caller.user.as_ref().map_or_else(|| "UTC".to_string(), |user| user.time_zone.clone()),
+    };
+
+    let mut rules = Vec::with_capacity(input.windows.len());
+    for window in &input.windows {
+        if !(1..=7).contains(&window.weekday) {
+            return Err(Status::invalid_argument("weekday must be 1 (Monday) to 7 (Sunday)"));
+        }
+        let (start_time, end_time) = parse_window(&window.start_time, &window.end_time)?;
+        rules.push(crate::models::CreateAvailabilityRule {
+            weekday: window.weekday as u8,
+            start_time,
+            end_time,
+        });
+    }
+    for weekday in 1..=7 {
+        reject_overlaps(
+            rules
+                .iter()
+                .filter(|rule| rule.weekday == weekday)
+                .map(|rule| (rule.start_time, rule.end_time))
+                .collect(),
+        )?;
+    }
+
+    crate::db::replace_weekly_availability(&state.db, mentor_id, &time_zone, &rules)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to save availability: {}", e)))?;
+
+    state.search.queue_mentor(mentor_id);
+    get_availability(state, caller, mentor_id).await
+}
+
+/// Override or black out the calling mentor's availability on one date.
+/// No windows and no blackout reverts the date to the weekly windows.
+pub async fn set_date_availability(
+    state: &AppState,
+    caller: &CallerContext,
+    input: DateAvailability,
+) -> Result<MentorAvailability, Status> {
+    let mentor_id = caller.user_id()?;
+    let date = crate::availability::parse_date(&input.date)?;
+
+    let exceptions = if input.blackout {
+        if !input.windows.is_empty() {
+            return Err(Status::invalid_argument("A blackout date can't have availability windows"));
+        }
+        vec![crate::models::CreateAvailabilityException {
+            date,
+            is_blackout: true,
+            start_time: None,
+            end_time: None,
+        }]
+    } else {
+        let windows = input
+            .windows
+            .iter()
+            .map(|window| parse_window(&window.start_time, &window.end_time))
+            .collect::<Result<Vec<_>, _>>()?;
+        reject_overlaps(windows.clone())?;
+
+        windows
+            .into_iter()
+            .map(|(start_time, end_time)| crate::models::CreateAvailabilityException {
+                date,
+                is_blackout: false,
+                start_time: Some(start_time),
+                end_time: Some(end_time),
+            })
+            .collect()
+    };
+
+    crate::db::replace_date_availability(&state.db, mentor_id, date, &exceptions)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to save availability: {}", e)))?;
+
+    state.search.queue_mentor(mentor_id);
+    get_availability(state, caller, mentor_id).await
+}
+
+/// A mentor's weekly availability and upcoming date-specific changes; 0 means the caller
+pub async fn get_availability(
+    state: &AppState,
+    caller: &CallerContext,
+    mentor_id: u64,
+) -> Result<MentorAvailability, Status> {
+    let mentor_id = if mentor_id == 0 { caller.user_id()? } else { mentor_id };
+    let mentor = load_mentor(state, mentor_id).await?;
+
+    let time_zone = crate::availability::parse_time_zone(&mentor.time_zone)?;
+    let today = chrono::Utc::now().with_timezone(&time_zone).date_naive();
+
+    let weekly = crate::db::get_availability_rules(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get availability: {}", e)))?;
+    let until = today + chrono::Days::new(UPCOMING_AVAILABILITY_DAYS);
+    let dates = crate::db::get_availability_exceptions(&state.db, mentor_id, today, until)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get availability: {}", e)))?;
+
+    Ok(MentorAvailability {
+        mentor_id,
+        time_zone: mentor.time_zone,
+        weekly,
+        dates,
+    })
+}
+
+/// Open slots in a mentor's availability on local dates `from_date..=to_date`, minus their booked sessions
+pub async fn list_bookable_slots(state: &AppState, query: SlotQuery) -> Result<BookableSlots, Status> {
+    let from = crate::availability::parse_date(&query.from_date)?;
+    let to = crate::availability::parse_date(&query.to_date)?;
+    if to < from {
+        return Err(Status::invalid_argument("to_date must not be before from_date"));
+    }
+    if (to - from).num_days() >= crate::availability::MAX_SLOT_RANGE_DAYS {
+        return Err(Status::invalid_argument(format!(
+            "Date range must be at most {} days",
+            crate::availability::MAX_SLOT_RANGE_DAYS
+        )));
+    }
+
+    let slot_minutes = query.slot_minutes.unwrap_or(crate::availability::DEFAULT_SLOT_MINUTES);
+    if !(15..=480).contains(&slot_minutes) {
+        return Err(Status::invalid_argument("slot_minutes must be between 15 and 480"));
+    }
+
+    let mentor = load_mentor(state, query.mentor_id).await?;
+    let slots = open_slots(state, &mentor, from, to, slot_minutes)
+        .await?
+        .into_iter()
+        .map(|(start_at, end_at)| Slot { start_at, end_at })
+        .collect();
+
+    Ok(BookableSlots {
+        time_zone: mentor.time_zone,
+        slots,
+    })
+}
+
+/// Open slots of `slot_minutes` on the mentor's local dates `from..=to` that are not booked or past
+async fn open_slots(
+    state: &AppState,
+    mentor: &User,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    slot_minutes: i32,
+) -> Result<Vec<(chrono::NaiveDateTime, chrono::NaiveDateTime)>, Status> {
+    let schedule = load_schedule(state, mentor, from, to).await?;
+
+    // Local dates map to UTC instants at most a day either side
+    let busy_from = (from - chrono::Days::new(1)).and_time(chrono::NaiveTime::MIN);
+    let busy_to = (to + chrono::Days::new(2)).and_time(chrono::NaiveTime::MIN);
+    let busy: Vec<_> = crate::db::get_mentor_sessions_between(&state.db, mentor.id, busy_from, busy_to)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get sessions: {}", e)))?
+        .into_iter()
+        .map(|session| {
+            let end = session.scheduled_at + chrono::Duration::minutes(session.duration_minutes.into());
+            (session.scheduled_at, end)
+        })
+        .collect();
+
+    Ok(schedule.open_slots(
+        from,
+        to,
+        chrono::Duration::minutes(slot_minutes.into()),
+        &busy,
+        chrono::Utc::now().naive_utc(),
+    ))
+}
+
+/// Validate profile input into the row to write for `mentor_id`
+fn mentor_profile_row(mentor_id: u64, input: MentorProfileInput) -> Result<crate::models::CreateMentorProfile, Status> {
+    require_non_empty(&input.headline, "headline")?;
+    let headline = input.headline.trim().to_string();
+    if headline.chars().count() > crate::mentor_profile::MAX_HEADLINE_LEN {
+        return Err(Status::invalid_argument(format!(
+            "headline must be at most {} characters",
+            crate::mentor_profile::MAX_HEADLINE_LEN
+        )));
+    }
+    let years_experience = u16::try_from(input.years_experience)
+        .ok()
+        .filter(|years| *years <= 80)
+        .ok_or_else(|| Status::invalid_argument("years_experience must be between 0 and 80"))?;
+
+    Ok(crate::models::CreateMentorProfile {
+        user_id: mentor_id,
+        headline,
+        bio: input.bio.filter(|bio| !bio.trim().is_empty()),
+        years_experience,
+        currency: crate::mentor_profile::validate_rate(input.hourly_rate_cents, input.currency.as_deref())?,
+        hourly_rate_cents: input.hourly_rate_cents,
+        languages: crate::mentor_profile::normalize_languages(&input.languages)?,
+        skills: crate::mentor_profile::normalize_skills(&input.skills)?,
+    })
+}
+
+/// Create (`create`) or update the profile of `mentor_id` (0 means the caller)
+async fn save_mentor_profile(
+    state: &AppState,
+    caller: &CallerContext,
+    mentor_id: u64,
+    input: MentorProfileInput,
+    create: bool,
+) -> Result<MentorProfileDetails, Status> {
+    let mentor_id = target_user(state, caller, mentor_id, "Mentor profile")?;
+    load_mentor(state, mentor_id).await?;
+    let row = mentor_profile_row(mentor_id, input)?;
+
+    let outcome = crate::db::save_mentor_profile(&state.db, &row, create)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to save mentor profile: {}", e)))?;
+
+    match outcome {
+        crate::db::ProfileWrite::Saved => {
+            state.search.queue_mentor(mentor_id);
+            load_mentor_profile(state, mentor_id).await
+        }
+        crate::db::ProfileWrite::AlreadyExists => Err(Status::already_exists("Mentor profile already exists")),
+        crate::db::ProfileWrite::Missing => Err(Status::not_found("Mentor profile not found")),
+    }
+}
+
+pub async fn create_mentor_profile(
+    state: &AppState,
+    caller: &CallerContext,
+    mentor_id: u64,
+    input: MentorProfileInput,
+) -> Result<MentorProfileDetails, Status> {
+    save_mentor_profile(state, caller, mentor_id, input, true).await
+}
+
+pub async fn update_mentor_profile(
+    state: &AppState,
+    caller: &CallerContext,
+    mentor_id: u64,
+    input: MentorProfileInput,
+) -> Result<MentorProfileDetails, Status> {
+    save_mentor_profile(state, caller, mentor_id, input, false).await
+}
+
+/// Profiles are public to registered users so mentees can choose a mentor; 0 means the caller
+pub async fn get_mentor_profile(
+    state: &AppState,
+    caller: &CallerContext,
+    mentor_id: u64,
+) -> Result<MentorProfileDetails, Status> {
+    let mentor_id = if mentor_id == 0 { caller.user_id()? } else { mentor_id };
+    load_mentor_profile(state, mentor_id).await
+}
+
+async fn load_mentor_profile(state: &AppState, mentor_id: u64) -> Result<MentorProfileDetails, Status> {
+    let profile = crate::db::get_mentor_profile(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get mentor profile: {}", e)))?
+        .ok_or_else(|| Status::not_found("Mentor profile not found"))?;
+
+    let languages = crate::db::get_mentor_languages(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get mentor profile: {}", e)))?;
+    let skills = crate::db::get_mentor_skills(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get mentor profile: {}", e)))?;
+
+    Ok(MentorProfileDetails {
+        profile,
+        languages,
+        skills,
+    })
+}
+
+pub async fn delete_mentor_profile(state: &AppState, caller: &CallerContext, mentor_id: u64) -> Result<(), Status> {
+    let mentor_id = target_user(state, caller, mentor_id, "Mentor profile")?;
+
+    let deleted = crate::db::delete_mentor_profile(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to delete mentor profile: {}", e)))?;
+    if !deleted {
+        return Err(Status::not_found("Mentor profile not found"));
+    }
+
+    state.search.queue_mentor(mentor_id);
+    Ok(())
+}
+
+/// Record the outcome of an admin's review of a mentor's credentials
+pub async fn set_mentor_verification(
+    state: &AppState,
+    mentor_id: u64,
+    input: VerificationUpdate,
+) -> Result<MentorProfileDetails, Status> {
+    require_one_of(
+        &input.verification_status,
+        crate::mentor_profile::VERIFICATION_STATUSES,
+        "verification_status",
+    )?;
+
+    let updated = crate::db::set_mentor_verification(&state.db, mentor_id, &input.verification_status)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to update verification: {}", e)))?;
+    if !updated {
+        return Err(Status::not_found("Mentor profile not found"));
+    }
+
+    state.search.queue_mentor(mentor_id);
+    load_mentor_profile(state, mentor_id).await
+}
+
+/// Ask an admin to promote the calling `user` to `mentor`
+pub async fn apply_for_mentor(
+    state: &AppState,
+    caller: &CallerContext,
+    input: NewMentorApplication,
+) -> Result<MentorApplication, Status> {
+    let user_id = caller.user_id()?;
+    require_non_empty(&input.motivation, "motivation")?;
+
+    if caller.user.as_ref().is_some_and(|user| user.role != "user") {
+        return Err(Status::failed_precondition("Only users with the user role can apply to become mentors"));
+    }
+
+    let pending = crate::db::get_pending_mentor_application(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to check applications: {}", e)))?;
+    if pending.is_some() {
+        return Err(Status::already_exists("A mentor application is already pending"));
+    }
+
+    let application = crate::models::CreateMentorApplication {
+        user_id,
+        motivation: input.motivation.trim().to_string(),
+    };
+    let application_id = crate::db::create_mentor_application(&state.db, &application)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to create application: {}", e)))?;
+
+    tracing::info!("User {} applied to become a mentor (application {})", user_id, application_id);
+
+    load_mentor_application(state, application_id).await
+}
+
+async fn load_mentor_application(state: &AppState, application_id: u64) -> Result<MentorApplication, Status> {
+    crate::db::get_mentor_application(&state.db, application_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get application: {}", e)))?
+        .ok_or_else(|| Status::not_found("Mentor application not found"))
+}
+
+/// Applications in `status`, pending when empty
+pub async fn list_mentor_applications(state: &AppState, status: &str) -> Result<Vec<MentorApplication>, Status> {
+    let status = if status.is_empty() { "pending" } else { status };
+    require_one_of(status, APPLICATION_STATUSES, "status")?;
+
+    crate::db::get_mentor_applications_by_status(&state.db, status)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to list applications: {}", e)))
+}
+
+/// Approve or reject a pending application; approval makes the applicant a mentor
+pub async fn review_mentor_application(
+    state: &AppState,
+    caller: &CallerContext,
+    application_id: u64,
+    input: MentorApplicationReview,
+) -> Result<MentorApplication, Status> {
+    let reviewer_id = caller.user_id()?;
+    let note = input.note.filter(|note| !note.trim().is_empty());
+
+    let reviewed = crate::db::review_mentor_application(&state.db, application_id, reviewer_id, input.approve, note.as_deref())
+        .await
+        .map_err(|e| Status::internal(format!("Failed to review application: {}", e)))?;
+
+    let application = load_mentor_application(state, application_id).await?;
+    if !reviewed {
+        return Err(Status::failed_precondition(format!(
+            "Mentor application is already {}",
+            application.status
+        )));
+    }
+
+    tracing::info!("Mentor application {} {} by user {}", application_id, application.status, reviewer_id);
+    if input.approve {
+        // Move the new mentor's devices to the mentor topic
+        state.topics.wake();
+    }
+
+    let body = match (input.approve, &note) {
+        (true, _) => "You are now a mentor. Create your mentor profile so mentees can find you.".to_string(),
+        (false, Some(note)) => format!("Your mentor application was not approved: {}", note),
+        (false, None) => "Your mentor application was not approved".to_string(),
+    };
+    let notification = crate::models::CreateNotification {
+        user_id: application.user_id,
+        title: "Mentor application reviewed".to_string(),
+        body,
+        notification_type: "standard".to_string(),
+        data: Some(serde_json::json!({ "application_id": application.id, "status": application.status }).to_string()),
+    };
+    if let Err(status) = deliver_notification(state, notification).await {
+        tracing::warn!("Failed to notify applicant {}: {}", application.user_id, status.message());
+    }
+
+    Ok(application)
+}
+
+/// The search document for a mentor, or `None` if they are not a mentor with a profile
+pub async fn mentor_search_document(state: &AppState, mentor_id: u64) -> Result<Option<MentorDocument>, Status> {
+    let mentor = crate::db::get_user_by_id(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get mentor: {}", e)))?;
+    let Some(mentor) = mentor.filter(|user| user.role == "mentor") else {
+        return Ok(None);
+    };
+    let details = match load_mentor_profile(state, mentor_id).await {
+        Ok(details) => details,
+        Err(status) if status.code() == tonic::Code::NotFound => return Ok(None),
+        Err(status) => return Err(status),
+    };
+
+    let time_zone = crate::availability::parse_time_zone(&mentor.time_zone)?;
+    let today = chrono::Utc::now().with_timezone(&time_zone).date_naive();
+    let until = today + chrono::Days::new(crate::availability::MAX_SLOT_RANGE_DAYS as u64 - 1);
+    let next_available_at = open_slots(state, &mentor, today, until, crate::availability::DEFAULT_SLOT_MINUTES)
+        .await?
+        .first()
+        .map(|(start_at, _)| *start_at);
+
+    let rating = crate::db::get_mentor_rating(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get mentor rating: {}", e)))?;
+    let rating = crate::review::RatingSummary::from(rating);
+
+    Ok(Some(MentorDocument {
+        mentor_id,
+        display_name: mentor.display_name,
+        photo_url: mentor.photo_url,
+        headline: Some(details.profile.headline),
+        bio: details.profile.bio,
+        skills: details.skills,
+        languages: details.languages,
+        hourly_rate_cents: details.profile.hourly_rate_cents,
+        currency: details.profile.currency,
+        verification_status: Some(details.profile.verification_status),
+        rating: rating.average,
+        review_count: rating.review_count,
+        next_available_at,
+    }))
+}
+
+/// Full-text mentor search with filters and cursor pagination
+pub async fn search_mentors(state: &AppState, input: MentorSearch) -> Result<MentorSearchResults, Status> {
+    let sort = if input.sort.is_empty() { "relevance" } else { input.sort.as_str() };
+    require_one_of(sort, SEARCH_SORT_ORDERS, "sort")?;
+    let sort = match sort {
+        "rating" => crate::search::SortOrder::Rating,
+        "price" => crate::search::SortOrder::Price,
+        _ => crate::search::SortOrder::Relevance,
+    };
+    if input.min_price_cents.zip(input.max_price_cents).is_some_and(|(min, max)| min > max) {
+        return Err(Status::invalid_argument("min_price_cents must not exceed max_price_cents"));
+    }
+    if input.min_rating.is_some_and(|rating| !(0.0..=5.0).contains(&rating)) {
+        return Err(Status::invalid_argument("min_rating must be between 0 and 5"));
+    }
+    let available_before = match input.available_within_days {
+        Some(days) if days == 0 || i64::from(days) > crate::availability::MAX_SLOT_RANGE_DAYS => {
+            return Err(Status::invalid_argument(format!(
+                "available_within_days must be between 1 and {}",
+                crate::availability::MAX_SLOT_RANGE_DAYS
+            )));
+        }
+        Some(days) => Some(chrono::Utc::now().naive_utc() + chrono::Days::new(days.into())),
+        None => None,
+    };
+
+    let query = crate::search::MentorQuery {
+        text: input.query,
+        skills: crate::mentor_profile::normalize_skills(&input.skills)?,
+        languages: crate::mentor_profile::normalize_languages(&input.languages)?,
+        min_price_cents: input.min_price_cents,
+        max_price_cents: input.max_price_cents,
+        currency: input.currency.filter(|currency| !currency.trim().is_empty()),
+        min_rating: input.min_rating,
+        available_before,
+        sort,
+        limit: input.page_size,
+        cursor: input.cursor.filter(|cursor| !cursor.is_empty()),
+    };
+    let page = state.search.search(&query)?;
+
+    Ok(MentorSearchResults {
+        mentors: page.mentors,
+        next_cursor: page.next_cursor,
+    })
+}
+
+pub async fn start_session(state: &AppState, caller: &CallerContext, session_id: u64) -> Result<Session, Status> {
+    transition_session(state, caller, session_id, SessionAction::Start, OccurrenceScope::This, None, None).await
+}
+
+pub async fn complete_session(state: &AppState, caller: &CallerContext, session_id: u64) -> Result<Session, Status> {
+    transition_session(state, caller, session_id, SessionAction::Complete, OccurrenceScope::This, None, None).await
+}
+
+pub async fn cancel_session(
+    state: &AppState,
+    caller: &CallerContext,
+    session_id: u64,
+    input: SessionCancellation,
+) -> Result<Session, Status> {
+    let scope = OccurrenceScope::parse(&input.scope)?;
+    transition_session(state, caller, session_id, SessionAction::Cancel, scope, input.reason, None).await
+}
+
+pub async fn reschedule_session(
+    state: &AppState,
+    caller: &CallerContext,
+    session_id: u64,
+    input: SessionReschedule,
+) -> Result<Session, Status> {
+    let scope = OccurrenceScope::parse(&input.scope)?;
+    let scheduled_at = crate::timestamp::parse(&input.scheduled_at)?;
+    if scheduled_at <= chrono::Utc::now().naive_utc() {
+        return Err(Status::invalid_argument("scheduled_at must be in the future"));
+    }
+
+    transition_session(state, caller, session_id, SessionAction::Reschedule, scope, input.reason, Some(scheduled_at)).await
+}
+
+/// Apply a lifecycle action to a session, or to it and the later occurrences of its series,
+/// record it in the session history and notify the other participant
+async fn transition_session(
+    state: &AppState,
+    caller: &CallerContext,
+    session_id: u64,
+    action: SessionAction,
+    scope: OccurrenceScope,
+    reason: Option<String>,
+    new_scheduled_at: Option<chrono::NaiveDateTime>,
+) -> Result<Session, Status> {
+    let caller_id = caller.user_id()?;
+
+    let session = crate::db::get_session_by_id(&state.db, session_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get session: {}", e)))?
+        .ok_or_else(|| Status::not_found("Session not found"))?;
+
+    authorize(state, crate::policy::require_session_participant(caller, &session, action.mentor_only()))?;
+    let to_status = action.next_status(&session.status)?;
+    let reason = reason.filter(|reason| !reason.trim().is_empty());
+
+    // Later occurrences already past this action (e.g. cancelled ones) are left alone, and a
+    // reschedule moves them by the same wall-clock offset in the series' time zone
+    let mut targets = vec![(session.clone(), new_scheduled_at)];
+    if scope == OccurrenceScope::Following {
+        let series_id = session
+            .series_id
+            .ok_or_else(|| Status::invalid_argument("scope \"following\" requires a session that belongs to a series"))?;
+        let series = crate::db::get_session_series(&state.db, series_id)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to get session series: {}", e)))?
+            .ok_or_else(|| Status::not_found("Session series not found"))?;
+        let time_zone = crate::availability::parse_time_zone(&series.time_zone)?;
+        let delta = new_scheduled_at.map(|at| crate::recurrence::wall_clock_delta(session.scheduled_at, at, time_zone));
+
+        let from = session.original_scheduled_at.unwrap_or(session.scheduled_at);
+        let later = crate::db::get_series_sessions_from(&state.db, series_id, from)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to get series sessions: {}", e)))?;
+        for occurrence in later {
+            if occurrence.id == session_id || action.next_status(&occurrence.status).is_err() {
+                continue;
+            }
+            let moved_to = delta.map(|delta| crate::recurrence::shift_wall_clock(occurrence.scheduled_at, delta, time_zone));
+            targets.push((occurrence, moved_to));
+        }
+    }
+
+    let changes: Vec<_> = targets
+        .iter()
+        .map(|(target, new_scheduled_at)| crate::models::SessionStatusChange {
+            session_id: target.id,
+            changed_by: caller_id,
+            from_status: target.status.clone(),
+            to_status: to_status.to_string(),
+            reason: reason.clone(),
+            previous_scheduled_at: new_scheduled_at.map(|_| target.scheduled_at),
+            new_scheduled_at: *new_scheduled_at,
+        })
+        .collect();
+
+    let outcome = crate::db::transition_sessions(&state.db, &changes)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to update session: {}", e)))?;
+
+    match outcome {
+        crate::db::TransitionOutcome::Applied => {}
+        crate::db::TransitionOutcome::Stale => {
+            return Err(Status::aborted("Session was changed concurrently, please retry"));
+        }
+        crate::db::TransitionOutcome::Conflicts(conflicts) => return Err(booking_conflict(&conflicts)),
+    }
+
+    tracing::info!(
+        "Session {} {} -> {} by user {} ({} sessions)",
+        session_id,
+        session.status,
+        to_status,
+        caller_id,
+        changes.len()
+    );
+    state.search.queue_mentor(session.mentor_id);
+
+    let updated = crate::db::get_session_by_id(&state.db, session_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get session: {}", e)))?
+        .ok_or_else(|| Status::not_found("Session not found after update"))?;
+
+    let (title, mut body) = match action {
+        SessionAction::Start => ("Session started", format!("{} has started", updated.title)),
+        SessionAction::Complete => ("Session completed", format!("{} has been completed", updated.title)),
+        SessionAction::Cancel => ("Session cancelled", format!("{} was cancelled", updated.title)),
+        SessionAction::Reschedule => (
+            "Session rescheduled",
+            format!("{} moved to {}", updated.title, crate::timestamp::format_utc(updated.scheduled_at)),
+        ),
+    };
+    match changes.len() {
+        1 => {}
+        2 => body.push_str(" along with 1 later session"),
+        count => body.push_str(&format!(" along with {} later sessions", count - 1)),
+    }
+    if let (SessionAction::Cancel, Some(reason)) = (action, &reason) {
+        body.push_str(&format!(": {}", reason));
+    }
+
+    // The caller already knows; an admin acting on someone else's session notifies both participants
+    for recipient in [updated.user_id, updated.mentor_id] {
+        if recipient == caller_id {
+            continue;
+        }
+
+        let notification = crate::models::CreateNotification {
+            user_id: recipient,
+            title: title.to_string(),
+            body: body.clone(),
+            notification_type: "standard".to_string(),
+            data: Some(serde_json::json!({ "session_id": updated.id, "status": updated.status }).to_string()),
+        };
+
+        if let Err(status) = deliver_notification(state, notification).await {
+            tracing::warn!("Failed to notify user {} about session {}: {}", recipient, session_id, status.message());
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Issue the caller a new secret calendar feed URL, revoking any previous one
+pub async fn create_calendar_feed(state: &AppState, caller: &CallerContext) -> Result<CalendarFeed, Status> {
+    let user_id = caller.user_id()?;
+
+    let token = crate::calendar::generate_feed_token();
+    crate::db::replace_calendar_feed(&state.db, user_id, &crate::calendar::hash_feed_token(&token))
+        .await
+        .map_err(|e| Status::internal(format!("Failed to create calendar feed: {}", e)))?;
+
+    tracing::info!("Calendar feed issued for user {}", user_id);
+
+    Ok(CalendarFeed {
+        feed_url: format!("{}/calendar/{}.ics", state.public_base_url, token),
+    })
+}
+
+pub async fn revoke_calendar_feed(state: &AppState, caller: &CallerContext) -> Result<(), Status> {
+    let user_id = caller.user_id()?;
+
+    let revoked = crate::db::delete_calendar_feed(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to revoke calendar feed: {}", e)))?;
+    if !revoked {
+        return Err(Status::not_found("No calendar feed to revoke"));
+    }
+    Ok(())
+}
+
+/// The iCalendar feed for a feed token; unknown tokens are NOT_FOUND
+pub async fn calendar_feed(state: &AppState, token: &str) -> Result<String, Status> {
+    let user_id = crate::db::get_calendar_feed_user(&state.db, &crate::calendar::hash_feed_token(token))
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get calendar feed: {}", e)))?
+        .ok_or_else(|| Status::not_found("Calendar feed not found"))?;
+
+    let since = chrono::Utc::now().naive_utc() - chrono::Days::new(crate::calendar::FEED_HISTORY_DAYS);
+    let sessions = crate::db::get_sessions_by_user(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get sessions: {}", e)))?;
+
+    let mut users = HashMap::new();
+    let mut events = Vec::new();
+    for session in sessions.iter().filter(|session| session.scheduled_at >= since) {
+        events.push(session_calendar_event(state, &mut users, session).await?);
+    }
+
+    Ok(crate::calendar::calendar(Some("LinkWithMentor sessions"), events))
+}
+
+/// A single session as an `.ics` invite for one of its participants
+pub async fn session_invite(state: &AppState, caller: &CallerContext, session_id: u64) -> Result<String, Status> {
+    let session = crate::db::get_session_by_id(&state.db, session_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get session: {}", e)))?
+        .ok_or_else(|| Status::not_found("Session not found"))?;
+    authorize(state, crate::policy::require_session_participant(caller, &session, false))?;
+
+    let event = session_calendar_event(state, &mut HashMap::new(), &session).await?;
+    Ok(crate::calendar::calendar(None, [event]))
+}
+
+/// The `VEVENT` for a session, loading its participants through `users`
+async fn session_calendar_event(
+    state: &AppState,
+    users: &mut HashMap<u64, User>,
+    session: &Session,
+) -> Result<Vec<String>, Status> {
+    for user_id in [session.mentor_id, session.user_id] {
+        if users.contains_key(&user_id) {
+            continue;
+        }
+        let user = crate::db::get_user_by_id(&state.db, user_id)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to get user: {}", e)))?
+            .ok_or_else(|| Status::not_found(format!("User {} not found", user_id)))?;
+        users.insert(user_id, user);
+    }
+
+    Ok(crate::calendar::session_event(session, &users[&session.mentor_id], &users[&session.user_id]))
+}
+
+/// Review a completed session the caller took part in, once per participant
+pub async fn submit_review(
+    state: &AppState,
+    caller: &CallerContext,
+    session_id: u64,
+    input: ReviewInput,
+) -> Result<SessionReview, Status> {
+    let reviewer_id = caller.user_id()?;
+    let rating = crate::review::validate_rating(input.rating)?;
+    let comment = crate::review::normalize_comment(input.comment)?;
+
+    let session = crate::db::get_session_by_id(&state.db, session_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get session: {}", e)))?
+        .ok_or_else(|| Status::not_found("Session not found"))?;
+
+    // Admins moderate reviews but can't write them for sessions they didn't attend
+    let participant = reviewer_id == session.user_id || reviewer_id == session.mentor_id;
+    authorize(
+        state,
+        if participant { Ok(()) } else { Err(Status::permission_denied("Only session participants can review it")) },
+    )?;
+    let (reviewer_role, reviewee_id) = if reviewer_id == session.user_id {
+        ("mentee", session.mentor_id)
+    } else {
+        ("mentor", session.user_id)
+    };
+    if session.status != "completed" {
+        return Err(Status::failed_precondition(format!(
+            "Only completed sessions can be reviewed, this one is {}",
+            session.status
+        )));
+    }
+
+    let review = crate::models::CreateSessionReview {
+        session_id,
+        reviewer_id,
+        reviewee_id,
+        reviewer_role: reviewer_role.to_string(),
+        rating,
+        comment,
+    };
+    let review_id = crate::db::create_review(&state.db, &review)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to save review: {}", e)))?
+        .ok_or_else(|| Status::already_exists("You have already reviewed this session"))?;
+
+    if reviewer_role == "mentee" {
+        state.search.queue_mentor(reviewee_id);
+    }
+
+    load_review(state, review_id).await
+}
+
+/// Change the rating or comment of the caller's review within the edit window
+pub async fn edit_review(
+    state: &AppState,
+    caller: &CallerContext,
+    review_id: u64,
+    input: ReviewInput,
+) -> Result<SessionReview, Status> {
+    let caller_id = caller.user_id()?;
+    let rating = crate::review::validate_rating(input.rating)?;
+    let comment = crate::review::normalize_comment(input.comment)?;
+
+    let review = load_review(state, review_id).await?;
+    authorize(
+        state,
+        if review.reviewer_id == caller_id { Ok(()) } else { Err(Status::permission_denied("Only the reviewer can edit a review")) },
+    )?;
+    crate::review::ensure_editable(review.created_at, chrono::Utc::now().naive_utc())?;
+
+    let updated = crate::db::update_review(&state.db, review_id, rating, comment.as_deref())
+        .await
+        .map_err(|e| Status::internal(format!("Failed to update review: {}", e)))?;
+    if !updated {
+        return Err(Status::not_found("Review not found"));
+    }
+
+    if review.reviewer_role == "mentee" {
+        state.search.queue_mentor(review.reviewee_id);
+    }
+
+    load_review(state, review_id).await
+}
+
+async fn load_review(state: &AppState, review_id: u64) -> Result<SessionReview, Status> {
+    crate::db::get_review(&state.db, review_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get review: {}", e)))?
+        .ok_or_else(|| Status::not_found("Review not found"))
+}
+
+/// Mentees' reviews of a mentor, newest first. Hidden reviews are only listed for admins.
+pub async fn list_mentor_reviews(
+    state: &AppState,
+    caller: &CallerContext,
+    mentor_id: u64,
+    page_size: u32,
+    cursor: Option<&str>,
+) -> Result<MentorReviews, Status> {
+    let mentor_id = if mentor_id == 0 { caller.user_id()? } else { mentor_id };
+    load_mentor(state, mentor_id).await?;
+
+    let limit = match page_size {
+        0 => crate::review::DEFAULT_PAGE_SIZE,
+        size => size.min(crate::review::MAX_PAGE_SIZE),
+    };
+    // The cursor is the ID of the last review on the previous page
+    let before_id = match cursor.filter(|cursor| !cursor.is_empty()) {
+        Some(cursor) => cursor.parse().map_err(|_| Status::invalid_argument("Invalid cursor"))?,
+        None => u64::MAX,
+    };
+
+    let mut reviews = crate::db::get_mentor_reviews(
+        &state.db,
+        mentor_id,
+        crate::policy::is_admin(caller),
+        before_id,
+        limit + 1,
+    )
+    .await
+    .map_err(|e| Status::internal(format!("Failed to list reviews: {}", e)))?;
+
+    let next_cursor = if reviews.len() > limit as usize {
+        reviews.truncate(limit as usize);
+        reviews.last().map(|review| review.id.to_string())
+    } else {
+        None
+    };
+
+    let rating = crate::db::get_mentor_rating(&state.db, mentor_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get mentor rating: {}", e)))?;
+
+    Ok(MentorReviews {
+        mentor_id,
+        summary: rating.into(),
+        reviews,
+        next_cursor,
+    })
+}
+
+/// Hide an abusive review from listings and rating aggregates, or show it again
+pub async fn set_review_visibility(
+    state: &AppState,
+    caller: &CallerContext,
+    review_id: u64,
+    input: ReviewVisibilityUpdate,
+) -> Result<SessionReview, Status> {
+    let admin_id = caller.user_id()?;
+    let reason = input.reason.filter(|reason| !reason.trim().is_empty());
+
+    let updated = crate::db::set_review_hidden(&state.db, review_id, input.hidden, admin_id, reason.as_deref())
+        .await
+        .map_err(|e| Status::internal(format!("Failed to update review: {}", e)))?;
+    if !updated {
+        return Err(Status::not_found("Review not found"));
+    }
+
+    let review = load_review(state, review_id).await?;
+    tracing::info!(
+        "Review {} {} by admin {}",
+        review_id,
+        if review.is_hidden { "hidden" } else { "shown" },
+        admin_id
+    );
+    if review.reviewer_role == "mentee" {
+        state.search.queue_mentor(review.reviewee_id);
+    }
+
+    Ok(review)
+}
+
+pub async fn send_notification(
+    state: &AppState,
+    caller: &CallerContext,
+    input: NewNotification,
+) -> Result<Notification, Status> {
+    require_non_empty(&input.title, "title")?;
+    require_one_of(&input.notification_type, NOTIFICATION_TYPES, "notification_type")?;
+    let payload = notification_payload(&input.notification_type, input.payload)?;
+    let data = match payload.to_data() {
+        Some(_) if input.data.is_some() => {
+            return Err(Status::invalid_argument("data can only be set on standard notifications"));
+        }
+        Some(fields) => Some(fields),
+        None => input.data,
+    };
+
+    // Mentors may only notify people they have a session with
+    if !crate::policy::is_admin(caller) {
+        let shares_session = crate::db::users_share_session(&state.db, caller.user_id()?, input.user_id)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to check sessions: {}", e)))?;
+        if !shares_session {
+            authorize(state, Err(Status::permission_denied("Recipient has no session with the caller")))?;
+        }
+    }
+
+    let notification = crate::models::CreateNotification {
+        user_id: input.user_id,
+        title: input.title,
+        body: input.body,
+        notification_type: payload.name().to_string(),
+        data,
+    };
+
+    deliver_notification(state, notification).await
+}
+
+/// Push a message to a topic, a condition over topics, or every device of a list of users.
+/// Broadcasts aren't stored as notifications, so they never show up in inboxes.
+pub async fn broadcast_notification(
+    state: &Arc<AppState>,
+    caller: &CallerContext,
+    input: NewBroadcast,
+) -> Result<BroadcastResult, Status> {
+    require_non_empty(&input.title, "title")?;
+    let payload = notification_payload("standard", input.payload)?;
+
+    let target = match &input.target {
+        BroadcastTarget::Topic(topic) => {
+            if !crate::broadcast::is_known_topic(topic) {
+                return Err(Status::invalid_argument(format!("Unknown topic {}", topic)));
+            }
+            crate::firebase::MessageTarget::Topic(topic)
+        }
+        BroadcastTarget::Condition(condition) => {
+            crate::broadcast::validate_condition(condition).map_err(Status::invalid_argument)?;
+            crate::firebase::MessageTarget::Condition(condition)
+        }
+        BroadcastTarget::UserIds(user_ids) => {
+            if user_ids.is_empty() || user_ids.len() > MAX_BROADCAST_USERS {
+                return Err(Status::invalid_argument(format!(
+                    "user_ids must list between 1 and {} users",
+                    MAX_BROADCAST_USERS
+                )));
+            }
+            let mut user_ids = user_ids.clone();
+            user_ids.sort_unstable();
+            user_ids.dedup();
+
+            // Broadcasts aren't stored, so a push that preferences would defer is skipped instead
+            let now = chrono::Utc::now().naive_utc();
+            let mut devices = Vec::new();
+            let mut skipped_user_ids = Vec::new();
+            for user_id in user_ids {
+                let preferences = crate::db::get_push_preferences(&state.db, user_id, payload.name())
+                    .await
+                    .map_err(|e| Status::internal(format!("Failed to get notification preferences: {}", e)))?;
+                let Some(preferences) = preferences else {
+                    continue;
+                };
+                if crate::notification_preferences::push_at(&preferences, payload.name(), now) != Some(now) {
+                    skipped_user_ids.push(user_id);
+                    continue;
+                }
+
+                let tokens = crate::db::get_device_tokens_by_user(&state.db, user_id)
+                    .await
+                    .map_err(|e| Status::internal(format!("Failed to get device tokens: {}", e)))?;
+                devices.extend(tokens);
+            }
+
+            let devices = crate::broadcast::fan_out(state, devices, &input.title, &input.body, &payload).await;
+            let sent_count = devices.iter().filter(|device| device.sent).count() as u32;
+            tracing::info!(
+                "Broadcast by user {} sent to {} of {} devices, skipping {} users",
+                caller.user_id()?,
+                sent_count,
+                devices.len(),
+                skipped_user_ids.len()
+            );
+            return Ok(BroadcastResult {
+                sent_count,
+                failed_count: devices.len() as u32 - sent_count,
+                devices,
+                skipped_user_ids,
+                message_id: None,
+            });
+        }
+    };
+
+    let message_id = state
+        .firebase
+        .send_message(target, &input.title, &input.body, &payload)
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to send broadcast: {}", e);
+            if e.is_retryable() { Status::unavailable(message) } else { Status::internal(message) }
+        })?;
+    tracing::info!("Broadcast by user {} sent as {}", caller.user_id()?, message_id);
+
+    Ok(BroadcastResult {
+        message_id: Some(message_id),
+        ..Default::default()
+    })
+}
+
+/// Resolve and validate the payload of a new notification. `notification_type` may be left as
+/// `standard` when a typed payload is given, but a typed `notification_type` needs its payload.
+fn notification_payload(
+    notification_type: &str,
+    payload: Option<crate::firebase::NotificationType>,
+) -> Result<crate::firebase::NotificationType, Status> {
+    use crate::firebase::NotificationType;
+
+    let payload = match payload {
+        Some(payload) if notification_type == "standard" || notification_type == payload.name() => payload,
+        Some(payload) => {
+            return Err(Status::invalid_argument(format!(
+                "notification_type {} doesn't match the {} payload",
+                notification_type,
+                payload.name()
+            )));
+        }
+        None if notification_type == "standard" => NotificationType::Standard,
+        None => {
+            return Err(Status::invalid_argument(format!(
+                "{} notifications need a {} payload",
+                notification_type, notification_type
+            )));
+        }
+    };
+
+    match &payload {
+        NotificationType::Standard => {}
+        NotificationType::Link { url } => require_non_empty(url, "link.url")?,
+        NotificationType::Image { url } => {
+            // FCM only fetches images over HTTPS
+            if !url.starts_with("https://") {
+                return Err(Status::invalid_argument("image.url must be an HTTPS URL"));
+            }
+        }
+        NotificationType::Chat { sender_id, chat_id } => {
+            require_non_empty(sender_id, "chat.sender_id")?;
+            require_non_empty(chat_id, "chat.chat_id")?;
+        }
+        NotificationType::Call { caller_id, call_id, .. } => {
+            require_non_empty(caller_id, "call.caller_id")?;
+            require_non_empty(call_id, "call.call_id")?;
+        }
+    }
+    Ok(payload)
+}
+
+/// Persist a notification with its pending FCM deliveries and push it to live subscribers.
+/// The outbox worker sends it to the recipient's devices, retrying failures. The recipient's
+/// preferences can keep it in-app only or hold the push back until their quiet hours end.
+pub async fn deliver_notification(
+    state: &AppState,
+    notification: crate::models::CreateNotification,
+) -> Result<Notification, Status> {
+    deliver_notification_by(state, notification, None).await
+}
+
+/// Like [`deliver_notification`], but a push that would be held back past `push_deadline`
+/// is dropped and the notification kept in-app only
+pub async fn deliver_notification_by(
+    state: &AppState,
+    notification: crate::models::CreateNotification,
+    push_deadline: Option<chrono::NaiveDateTime>,
+) -> Result<Notification, Status> {
+    let preferences = crate::db::get_push_preferences(&state.db, notification.user_id, &notification.notification_type)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notification preferences: {}", e)))?
+        .ok_or_else(|| Status::not_found("Recipient not found"))?;
+    let now = chrono::Utc::now().naive_utc();
+    let push_at = match push_deadline {
+        Some(deadline) => crate::notification_preferences::push_at_by(&preferences, &notification.notification_type, now, deadline),
+        None => crate::notification_preferences::push_at(&preferences, &notification.notification_type, now),
+    };
+
+    let notification_id = crate::db::create_notification(&state.db, &notification, push_at)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to create notification: {}", e)))?;
+    if push_at == Some(now) {
+        state.outbox.wake();
+    }
+
+    let created = crate::db::get_notification_by_id(&state.db, notification_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notification: {}", e)))?
+        .ok_or_else(|| Status::not_found("Notification not found after creation"))?;
+
+    state.notification_hub.publish(&created);
+
+    Ok(created)
+}
+
+pub async fn unread_notifications(
+    state: &AppState,
+    caller: &CallerContext,
+    user_id: u64,
+) -> Result<Vec<Notification>, Status> {
+    let user_id = target_user(state, caller, user_id, "Notifications")?;
+
+    crate::db::get_unread_notifications(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notifications: {}", e)))
+}
+
+/// Open a live notification stream for the caller. Notifications newer than
+/// `last_seen_id` are replayed first (none when it is 0), then new ones are
+/// pushed as they are persisted, with a heartbeat while the stream is idle.
+pub async fn subscribe_notifications(
+    state: Arc<AppState>,
+    caller: &CallerContext,
+    last_seen_id: u64,
+) -> Result<mpsc::Receiver<Result<NotificationEvent, Status>>, Status> {
+    let user_id = caller.user_id()?;
+
+    // Subscribe before replaying so nothing persisted in between is missed
+    let mut subscription = state.notification_hub.subscribe(user_id);
+    let (tx, rx) = mpsc::channel(crate::notification_hub::SUBSCRIBER_BUFFER);
+
+    tokio::spawn(async move {
+        let mut last_sent = last_seen_id;
+        if last_seen_id > 0 {
+            match replay_backlog(&state, user_id, last_seen_id, &tx).await {
+                Some(id) => last_sent = id,
+                None => return,
+            }
+        }
+
+        let start = tokio::time::Instant::now() + HEARTBEAT_INTERVAL;
+        let mut heartbeat = tokio::time::interval_at(start, HEARTBEAT_INTERVAL);
+
+        loop {
+            let event = tokio::select! {
+                notification = subscription.recv() => match notification {
+                    // Already delivered during replay
+                    Some(notification) if notification.id <= last_sent => continue,
+                    Some(notification) => {
+                        last_sent = notification.id;
+                        Ok(NotificationEvent::Notification(notification))
+                    }
+                    None => Err(Status::resource_exhausted(
+                        "Notification stream fell behind; resubscribe with last_seen_notification_id",
+                    )),
+                },
+                _ = heartbeat.tick() => Ok(NotificationEvent::Heartbeat(chrono::Utc::now().naive_utc())),
+                // Client disconnected
+                _ = tx.closed() => break,
+            };
+
+            let finished = event.is_err();
+            if tx.send(event).await.is_err() || finished {
+                break;
+            }
+        }
+
+        tracing::debug!("Notification stream for user {} closed", user_id);
+    });
+
+    Ok(rx)
+}
+
+/// Send every notification after `after_id` to `tx`, returning the last ID sent,
+/// or `None` when the stream has ended
+async fn replay_backlog(
+    state: &AppState,
+    user_id: u64,
+    mut after_id: u64,
+    tx: &mpsc::Sender<Result<NotificationEvent, Status>>,
+) -> Option<u64> {
+    loop {
+        let page = match crate::db::get_notifications_after(&state.db, user_id, after_id, REPLAY_PAGE_SIZE)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to replay notifications: {}", e)))
+        {
+            Ok(page) => page,
+            Err(status) => {
+                let _ = tx.send(Err(status)).await;
+                return None;
+            }
+        };
+
+        let done = page.len() < REPLAY_PAGE_SIZE as usize;
+        for notification in page {
+            after_id = notification.id;
+            tx.send(Ok(NotificationEvent::Notification(notification))).await.ok()?;
+        }
+        if done {
+            return Some(after_id);
+        }
+    }
+}
+
+pub async fn mark_notification_read(state: &AppState, caller: &CallerContext, notification_id: u64) -> Result<(), Status> {
+    let notification = crate::db::get_notification_by_id(&state.db, notification_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notification: {}", e)))?
+        .ok_or_else(|| Status::not_found("Notification not found"))?;
+
+    authorize(state, crate::policy::require_owner(caller, notification.user_id, "Notification"))?;
+
+    crate::db::mark_notification_read(&state.db, notification_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to mark notification as read: {}", e)))
+}
+
+/// The caller's notifications, newest first, with cursor pagination
+pub async fn list_notifications(state: &AppState, caller: &CallerContext, query: NotificationQuery) -> Result<NotificationPage, Status> {
+    let user_id = caller.user_id()?;
+
+    let notification_type = query.notification_type.filter(|notification_type| !notification_type.is_empty());
+    if let Some(notification_type) = &notification_type {
+        require_one_of(notification_type, NOTIFICATION_TYPES, "notification_type")?;
+    }
+    let timestamp = |value: Option<String>| {
+        value
+            .filter(|value| !value.is_empty())
+            .map(|value| crate::timestamp::parse(&value))
+            .transpose()
+    };
+    let filter = crate::models::NotificationFilter {
+        is_read: query.is_read,
+        notification_type,
+        created_from: timestamp(query.created_from)?,
+        created_to: timestamp(query.created_to)?,
+    };
+
+    let limit = match query.page_size {
+        0 => DEFAULT_NOTIFICATION_PAGE_SIZE,
+        size => size.min(MAX_NOTIFICATION_PAGE_SIZE),
+    };
+    // The cursor is the ID of the last notification on the previous page
+    let before_id = match query.cursor.filter(|cursor| !cursor.is_empty()) {
+        Some(cursor) => cursor.parse().map_err(|_| Status::invalid_argument("Invalid cursor"))?,
+        None => u64::MAX,
+    };
+
+    let mut notifications = crate::db::list_notifications(&state.db, user_id, &filter, before_id, limit + 1)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to list notifications: {}", e)))?;
+    let next_cursor = if notifications.len() > limit as usize {
+        notifications.truncate(limit as usize);
+        notifications.last().map(|notification| notification.id.to_string())
+    } else {
+        None
+    };
+
+    let unread_count = crate::db::count_unread_notifications(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to count unread notifications: {}", e)))?;
+
+    Ok(NotificationPage {
+        notifications,
+        next_cursor,
+        unread_count,
+    })
+}
+
+/// Mark the caller's notifications with the given IDs as read, returning how many were unread.
+/// IDs of other users' notifications are ignored.
+pub async fn mark_notifications_read(state: &AppState, caller: &CallerContext, input: NotificationIds) -> Result<u64, Status> {
+    let user_id = caller.user_id()?;
+
+    if input.notification_ids.len() > MAX_BULK_NOTIFICATION_IDS {
+        return Err(Status::invalid_argument(format!(
+            "At most {} notification_ids per request",
+            MAX_BULK_NOTIFICATION_IDS
+        )));
+    }
+    let mut ids = input.notification_ids;
+    ids.sort_unstable();
+    ids.dedup();
+
+    crate::db::mark_notifications_read(&state.db, user_id, &ids)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to mark notifications as read: {}", e)))
+}
+
+/// Mark all of the caller's notifications as read, returning how many were unread
+pub async fn mark_all_notifications_read(state: &AppState, caller: &CallerContext) -> Result<u64, Status> {
+    let user_id = caller.user_id()?;
+
+    crate::db::mark_all_notifications_read(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to mark notifications as read: {}", e)))
+}
+
+pub async fn delete_notification(state: &AppState, caller: &CallerContext, notification_id: u64) -> Result<(), Status> {
+    let notification = crate::db::get_notification_by_id(&state.db, notification_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notification: {}", e)))?
+        .ok_or_else(|| Status::not_found("Notification not found"))?;
+
+    authorize(state, crate::policy::require_owner(caller, notification.user_id, "Notification"))?;
+
+    crate::db::delete_notification(&state.db, notification_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to delete notification: {}", e)))?;
+    Ok(())
+}
+
+/// Per-device push delivery status of a notification
+pub async fn notification_deliveries(
+    state: &AppState,
+    caller: &CallerContext,
+    notification_id: u64,
+) -> Result<Vec<crate::models::NotificationDelivery>, Status> {
+    let notification = crate::db::get_notification_by_id(&state.db, notification_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notification: {}", e)))?
+        .ok_or_else(|| Status::not_found("Notification not found"))?;
+
+    authorize(state, crate::policy::require_owner(caller, notification.user_id, "Notification"))?;
+
+    crate::db::get_notification_deliveries(&state.db, notification_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notification deliveries: {}", e)))
+}
+
+pub async fn notification_preferences(state: &AppState, caller: &CallerContext) -> Result<NotificationPreferences, Status> {
+    let user_id = caller.user_id()?;
+
+    let channels = crate::db::get_notification_channels(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notification preferences: {}", e)))?;
+    let settings = crate::db::get_notification_settings(&state.db, user_id)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to get notification preferences: {}", e)))?;
+
+    let channels = NOTIFICATION_TYPES
+        .iter()
+        .map(|notification_type| crate::models::NotificationChannel {
+            notification_type: notification_type.to_string(),
+            channel: channels
+                .iter()
+                .find(|channel| channel.notification_type == *notification_type)
+                .map_or(crate::notification_preferences::DEFAULT_CHANNEL, |channel| channel.channel.as_str())
+                .to_string(),
+        })
+        .collect();
+    let time = |time: Option<chrono::NaiveTime>| time.map(|time| time.format("%H:%M").to_string());
+
+    Ok(NotificationPreferences {
+        channels,
+        quiet_hours_start: time(settings.as_ref().and_then(|settings| settings.quiet_hours_start)),
+        quiet_hours_end: time(settings.as_ref().and_then(|settings| settings.quiet_hours_end)),
+        calls_bypass_quiet_hours: settings.is_none_or(|settings| settings.calls_bypass_quiet_hours),
+        time_zone: viewer_time_zone(caller).name().to_string(),
+    })
+}
+
+pub async fn update_notification_preferences(
+    state: &AppState,
+    caller: &CallerContext,
+    input: NotificationPreferencesUpdate,
+) -> Result<NotificationPreferences, Status> {
+    let user_id = caller.user_id()?;
+
+    let mut channels: Vec<crate::models::NotificationChannel> = Vec::new();
+    for channel in input.channels {
+        require_one_of(&channel.notification_type, NOTIFICATION_TYPES, "notification_type")?;
+        require_one_of(&channel.channel, crate::notification_preferences::CHANNELS, "channel")?;
+        if channels.iter().any(|existing| existing.notification_type == channel.notification_type) {
+            return Err(Status::invalid_argument(format!(
+                "{} notifications are listed more than once",
+                channel.notification_type
+            )));
+        }
+        channels.push(crate::models::NotificationChannel {
+            notification_type: channel.notification_type,
+            channel: channel.channel,
+        });
+    }
+
+    let time = |value: Option<String>| -> Result<Option<chrono::NaiveTime>, Status> {
+        value
+            .filter(|value| !value.is_empty())
+            .map(|value| crate::availability::parse_time(&value))
+            .transpose()
+    };
+    let (quiet_hours_start, quiet_hours_end) = match (time(input.quiet_hours_start)?, time(input.quiet_hours_end)?) {
+        (Some(start), Some(end)) if start == end => {
+            return Err(Status::invalid_argument("Quiet hours must end at a different time than they start"));
+        }
+        (start, end) if start.is_some() != end.is_some() => {
+            return Err(Status::invalid_argument("Set both quiet_hours_start and quiet_hours_end, or neither"));
+        }
+        window => window,
+    };
+
+    let settings = crate::models::NotificationSettings {
+        user_id,
+        quiet_hours_start,
+        quiet_hours_end,
+        calls_bypass_quiet_hours: input.calls_bypass_quiet_hours.unwrap_or(true),
+    };
+    crate::db::replace_notification_preferences(&state.db, &settings, &channels)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to update notification preferences: {}", e)))?;
+
+    notification_preferences(state, caller).await
+}
+
+pub async fn register_device_token(state: &AppState, caller: &CallerContext, input: NewDeviceToken) -> Result<(), Status> {
+    let user_id = caller.user_id()?;
+
+    require_one_of(&input.device_type, DEVICE_TYPES, "device_type")?;
+
+    let token = match input.web_push {
+        Some(subscription) => {
+            if input.device_type != "web" {
+                return Err(Status::invalid_argument("web_push subscriptions need device_type web"));
+            }
+            if !input.token.is_empty() && input.token != subscription.endpoint {
+                return Err(Status::invalid_argument("token must be empty or the endpoint for web_push subscriptions"));
+            }
+            let Some(web_push) = &state.web_push else {
+                return Err(Status::failed_precondition("Web Push is not enabled on this server"));
+            };
+            let (p256dh, auth) = web_push
+                .normalize_subscription(&subscription.endpoint, &subscription.keys.p256dh, &subscription.keys.auth)
+                .map_err(|e| Status::invalid_argument(format!("Invalid web_push subscription: {}", e)))?;
+
+            crate::models::CreateDeviceToken {
+                user_id,
+                token: subscription.endpoint,
+                device_type: input.device_type,
+                provider: "web_push".to_string(),
+                web_push_p256dh: Some(p256dh),
+                web_push_auth: Some(auth),
+            }
+        }
+        None => {
+            require_non_empty(&input.token, "token")?;
+            crate::models::CreateDeviceToken {
+                user_id,
+                token: input.token,
+                device_type: input.device_type,
+                provider: "fcm".to_string(),
+                web_push_p256dh: None,
+                web_push_auth: None,
+            }
+        }
+    };
+    if token.token.chars().count() > MAX_DEVICE_TOKEN_LEN {
+        return Err(Status::invalid_argument(format!("token must be at most {} characters", MAX_DEVICE_TOKEN_LEN)));
+    }
+
+    crate::db::upsert_device_token(&state.db, &token)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to register device token: {}", e)))?;
+
+    // Subscribe it to the topics of the caller's role; Web Push has no topics
+    if token.provider == "fcm" {
+        state.topics.wake();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rest_instants_are_rfc_3339() {
+        let at = crate::timestamp::parse("2025-11-22T12:00:00Z").unwrap();
+        let notification = Notification {
+            id: 1,
+            user_id: 1,
+            title: "Hi".to_string(),
+            body: String::new(),
+            notification_type: "standard".to_string(),
+            data: None,
+            is_read: false,
+            created_at: at,
+        };
+        let json = serde_json::to_value(&notification).unwrap();
+        assert_eq!(json["created_at"], "2025-11-22T12:00:00Z");
+    }
+
+    #[test]
+    fn test_validate_meeting_link() {
+        let link = |link: &str| validate_meeting_link(Some(link.to_string()));
+
+        assert_eq!(link("https://meet.example.com/abc").unwrap(), Some("https://meet.example.com/abc".to_string()));
+        assert_eq!(link(" ").unwrap(), None);
+        assert_eq!(validate_meeting_link(None).unwrap(), None);
+        assert!(link("https://x\r\nATTENDEE:mailto:eve@example.com").is_err());
+        assert!(link("https://meet.example.com/\tabc").is_err());
+        assert!(link("javascript:alert(1)").is_err());
+        assert!(link("meet.example.com/abc").is_err());
+    }
+
+    #[test]
+    fn test_new_users_cannot_pick_their_role() {
+        let input = |role: Option<&str>| NewUser {
+            email: "user1@example.com".to_string(),
+            display_name: None,
+            photo_url: None,
+            role: role.map(str::to_string),
+            time_zone: Some("Europe/Berlin".to_string()),
+        };
+
+        assert_eq!(validate_new_user(&input(None), None).unwrap().1, Some("Europe/Berlin".to_string()));
+        assert!(validate_new_user(&input(Some("user")), None).is_ok());
+        assert_eq!(validate_new_user(&input(Some("admin")), None).unwrap_err().code(), tonic::Code::PermissionDenied);
+        assert_eq!(validate_new_user(&input(Some("mentor")), None).unwrap_err().code(), tonic::Code::PermissionDenied);
+        assert_eq!(validate_new_user(&input(Some("root")), None).unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_new_user_email_comes_from_the_token() {
+        let input = |email: &str| NewUser {
+            email: email.to_string(),
+            display_name: None,
+            photo_url: None,
+            role: None,
+            time_zone: None,
+        };
+        let email = |body: &str, token: Option<&str>| validate_new_user(&input(body), token).map(|(email, _)| email);
+
+        assert_eq!(email("", Some("ada@example.com")).unwrap(), "ada@example.com");
+        assert_eq!(email("Ada@Example.com", Some("ada@example.com")).unwrap(), "ada@example.com");
+        assert!(email("eve@example.com", Some("ada@example.com")).is_err());
+        assert_eq!(email("ada@example.com", None).unwrap(), "ada@example.com");
+        assert!(email("", None).is_err());
+        assert!(email("a@x.com\r\nATTENDEE:mailto:eve@evil.example", None).is_err());
+        assert!(email("a;b@x.com", None).is_err());
+        assert!(email("ada@localhost", None).is_err());
+        assert!(email("ada..lovelace@example.com", None).is_err());
+    }
+
+    #[test]
+    fn test_session_view_renders_viewer_time_zone() {
+        let at = crate::timestamp::parse("2025-12-01T10:00:00Z").unwrap();
+        let user = |time_zone: &str| User {
+            id: 1,
+            firebase_uid: "uid1".to_string(),
+            email: "user1@example.com".to_string(),
+            display_name: None,
+            photo_url: None,
+            role: "user".to_string(),
+            time_zone: time_zone.to_string(),
+            created_at: at,
+            updated_at: at,
+        };
+        let caller = |user: Option<User>| CallerContext {
+            identity: crate::auth::AuthenticatedUser {
+                uid: "uid1".to_string(),
+                email: None,
+                email_verified: false,
+                auth_time: 0,
+                custom_claims: serde_json::Map::new(),
+            },
+            user,
+        };
+        let session = Session {
+            id: 1,
+            user_id: 1,
+            mentor_id: 2,
+            title: "Career chat".to_string(),
+            description: None,
+            scheduled_at: at,
+            duration_minutes: 60,
+            status: "scheduled".to_string(),
+            meeting_link: None,
+            cancelled_by: None,
+            cancellation_reason: None,
+            series_id: None,
+            original_scheduled_at: None,
+            sequence: 0,
+            created_at: at,
+            updated_at: at,
+        };
+
+        let view = SessionView::new(&caller(Some(user("Asia/Tokyo"))), session.clone());
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["scheduled_at"], "2025-12-01T10:00:00Z");
+        assert_eq!(json["scheduled_at_local"], "2025-12-01T19:00:00+09:00");
+        assert_eq!(json["time_zone"], "Asia/Tokyo");
+
+        let view = SessionView::new(&caller(None), session);
+        assert_eq!(view.scheduled_at_local, "2025-12-01T10:00:00+00:00");
+        assert_eq!(view.time_zone, "UTC");
+    }
+
+    #[test]
+    fn test_booking_conflict_details() {
+        let status = booking_conflict(&[4, 9]);
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert!(status.message().contains("4, 9"));
+
+        let failure = status.get_details_precondition_failure().unwrap();
+        let subjects: Vec<&str> = failure.violations.iter().map(|v| v.subject.as_str()).collect();
+        assert_eq!(subjects, ["sessions/4", "sessions/9"]);
+    }
+
+    #[test]
+    fn test_reject_overlaps() {
+        let window = |start: &str, end: &str| parse_window(start, end).unwrap();
+        assert!(reject_overlaps(vec![window("13:00", "14:00"), window("09:00", "13:00")]).is_ok());
+        assert!(reject_overlaps(vec![window("09:00", "12:00"), window("11:00", "14:00")]).is_err());
+        assert!(parse_window("12:00", "09:00").is_err());
+    }
+
+    #[test]
+    fn test_enum_validation() {
+        assert!(require_one_of("chat", NOTIFICATION_TYPES, "notification_type").is_ok());
+        assert!(require_one_of("sms", NOTIFICATION_TYPES, "notification_type").is_err());
+        assert!(require_one_of("web", DEVICE_TYPES, "device_type").is_ok());
+        assert!(require_non_empty("  ", "title").is_err());
+    }
+
+    #[test]
+    fn test_notification_payload() {
+        use crate::firebase::NotificationType;
+        let link = || Some(NotificationType::Link { url: "https://example.com".to_string() });
+
+        assert_eq!(notification_payload("standard", None).unwrap(), NotificationType::Standard);
+        assert_eq!(notification_payload("standard", link()).unwrap(), link().unwrap());
+        assert_eq!(notification_payload("link", link()).unwrap(), link().unwrap());
+        assert!(notification_payload("chat", link()).is_err());
+        assert!(notification_payload("call", None).is_err());
+
+        let http_image = Some(NotificationType::Image { url: "http://example.com/a.png".to_string() });
+        assert!(notification_payload("image", http_image).is_err());
+        let empty_chat = Some(NotificationType::Chat { sender_id: "1".to_string(), chat_id: " ".to_string() });
+        assert!(notification_payload("chat", empty_chat).is_err());
+    }
+
+    #[test]
+    fn test_broadcast_target_from_json() {
+        let topic: NewBroadcast = serde_json::from_str(r#"{"topic": "role_mentor", "title": "Hi", "body": ""}"#).unwrap();
+        assert!(matches!(topic.target, BroadcastTarget::Topic(topic) if topic == "role_mentor"));
+
+        let users: NewBroadcast = serde_json::from_str(r#"{"user_ids": [1, 2], "title": "Hi", "body": ""}"#).unwrap();
+        assert!(matches!(users.target, BroadcastTarget::UserIds(ids) if ids == [1, 2]));
+
+        assert!(serde_json::from_str::<NewBroadcast>(r#"{"title": "Hi", "body": ""}"#).is_err());
+    }
+}
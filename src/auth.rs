@@ -1,60 +1,417 @@
-use tonic::{Request, Status};
-use crate::firebase::FirebaseClient;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use tokio::sync::{Mutex, RwLock};
+use tonic::Status;
+
+use crate::config::Config;
+use crate::AppState;
+
+/// Public keys Google uses to sign Firebase ID tokens, in JWKS form
+pub const GOOGLE_SECURETOKEN_JWKS_URL: &str =
+    "https://www.googleapis.com/service_accounts/v1/jwk/securetoken@system.gserviceaccount.com";
+
+/// Used when the key endpoint does not send a usable Cache-Control max-age
+const DEFAULT_KEY_TTL: Duration = Duration::from_secs(3600);
+
+/// Least time between key fetches; tokens with a forged `kid` can't trigger them more often
+const MIN_KEY_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Claims Firebase sets itself; everything else in the token is a custom claim
+const RESERVED_CLAIMS: &[&str] = &[
+    "acr", "amr", "at_hash", "aud", "auth_time", "azp", "cnf", "c_hash", "exp", "firebase",
+    "iat", "iss", "jti", "name", "nbf", "nonce", "phone_number", "picture", "sub", "user_id",
+];
+
+/// Caller identity decoded from a verified Firebase ID token
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticatedUser {
+    pub uid: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub auth_time: i64,
+    pub custom_claims: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    iat: i64,
+    exp: i64,
+    auth_time: i64,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl From<IdTokenClaims> for AuthenticatedUser {
+    fn from(claims: IdTokenClaims) -> Self {
+        let custom_claims = claims
+            .extra
+            .into_iter()
+            .filter(|(name, _)| !RESERVED_CLAIMS.contains(&name.as_str()))
+            .collect();
+
+        Self {
+            uid: claims.sub,
+            email: claims.email,
+            email_verified: claims.email_verified,
+            auth_time: claims.auth_time,
+            custom_claims,
+        }
+    }
+}
+
+/// Who is making a gRPC call, attached to request extensions by [`AuthLayer`]
+#[derive(Debug, Clone)]
+pub struct CallerContext {
+    pub identity: AuthenticatedUser,
+    /// The caller's `users` row, or `None` until they have called CreateUser
+    pub user: Option<crate::models::User>,
+}
+
+impl CallerContext {
+    /// Database ID of the caller, for RPCs that need a registered user
+    pub fn user_id(&self) -> Result<u64, Status> {
+        self.user
+            .as_ref()
+            .map(|user| user.id)
+            .ok_or_else(|| Status::failed_precondition("Caller is not registered, call CreateUser first"))
+    }
+}
+
+struct CachedKeys {
+    keys: HashMap<String, DecodingKey>,
+    expires_at: Instant,
+}
 
 /// Middleware for Firebase token authentication
 pub struct AuthMiddleware {
-    firebase: FirebaseClient,
+    client: reqwest::Client,
+    project_id: String,
+    jwks_url: String,
+    clock_skew: Duration,
+    public_methods: HashSet<String>,
+    keys: RwLock<Option<CachedKeys>>,
+    // Serializes key refreshes so concurrent requests don't all hit the key endpoint,
+    // and holds when the keys were last fetched
+    refresh_lock: Mutex<Option<Instant>>,
 }
 
 impl AuthMiddleware {
-    pub fn new(firebase: FirebaseClient) -> Self {
-        Self { firebase }
-    }
-
-    /// Verify Firebase ID token from request metadata
-    pub async fn verify_token(&self, token: &str) -> Result<String, Status> {
-        if self.firebase.verify_token(token).await {
-            // In production, decode the token to get the user ID
-            // For now, return a placeholder
-            Ok("verified_user_id".to_string())
-        } else {
-            Err(Status::unauthenticated("Invalid token"))
+    pub fn new(config: &Config) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            project_id: config.firebase_project_id.clone(),
+            jwks_url: config.firebase_jwks_url.clone(),
+            clock_skew: Duration::from_secs(config.auth_clock_skew_secs),
+            public_methods: config.auth_public_methods.iter().cloned().collect(),
+            keys: RwLock::new(None),
+            refresh_lock: Mutex::new(None),
+        }
+    }
+
+    /// Verify a Firebase ID token and return the identity it carries
+    pub async fn verify_token(&self, token: &str) -> Result<AuthenticatedUser, Status> {
+        let header = decode_header(token)
+            .map_err(|_| Status::unauthenticated("Malformed token"))?;
+
+        if header.alg != Algorithm::RS256 {
+            return Err(Status::unauthenticated("Unexpected token algorithm"));
         }
+
+        let kid = header
+            .kid
+            .ok_or_else(|| Status::unauthenticated("Token has no key ID"))?;
+
+        let key = self.decoding_key(&kid).await?;
+
+        let data = decode::<IdTokenClaims>(token, &key, &self.validation()).map_err(|e| {
+            tracing::debug!("Rejected ID token: {}", e);
+            Status::unauthenticated("Invalid token")
+        })?;
+
+        validate_timestamps(
+            &data.claims,
+            chrono::Utc::now().timestamp(),
+            self.clock_skew.as_secs() as i64,
+        )
+        .map_err(Status::unauthenticated)?;
+
+        Ok(data.claims.into())
     }
 
-    /// Extract token from gRPC request metadata
-    pub fn extract_token<T>(request: &Request<T>) -> Result<String, Status> {
-        let metadata = request.metadata();
-        
-        metadata
-            .get("authorization")
+    /// Whether a gRPC path (e.g. `/service.LinkWithMentor/Ping`) may be called without a token
+    pub fn is_public(&self, path: &str) -> bool {
+        self.public_methods.contains(path)
+    }
+
+    /// Verify the caller's token and load their `users` row
+    async fn authenticate(&self, db: &crate::db::DbPool, token: &str) -> Result<CallerContext, Status> {
+        let identity = self.verify_token(token).await?;
+
+        let user = crate::db::get_user_by_firebase_uid(db, &identity.uid)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load caller {}: {}", identity.uid, e);
+                Status::internal("Failed to load caller")
+            })?;
+
+        Ok(CallerContext { identity, user })
+    }
+
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[format!("https://securetoken.google.com/{}", self.project_id)]);
+        validation.set_audience(&[&self.project_id]);
+        validation.set_required_spec_claims(&["exp", "iat", "sub", "iss", "aud"]);
+        validation.leeway = self.clock_skew.as_secs();
+        validation
+    }
+
+    /// Look up the signing key for `kid`, refreshing the cache when it has expired or doesn't
+    /// have the key, at most once per `MIN_KEY_REFRESH_INTERVAL`
+    async fn decoding_key(&self, kid: &str) -> Result<DecodingKey, Status> {
+        if let Some(key) = self.cached_key(kid, false).await {
+            return Ok(key);
+        }
+
+        let mut last_fetch = self.refresh_lock.lock().await;
+
+        // Another request may have refreshed the keys while we waited
+        if let Some(key) = self.cached_key(kid, false).await {
+            return Ok(key);
+        }
+
+        if last_fetch.is_some_and(|at| at.elapsed() < MIN_KEY_REFRESH_INTERVAL) {
+            let keys = self.keys.read().await;
+            return match keys.as_ref() {
+                Some(cached) => cached.keys.get(kid).cloned().ok_or_else(|| Status::unauthenticated("Token signed with unknown key")),
+                None => Err(Status::unavailable("Token signing keys are unavailable")),
+            };
+        }
+        *last_fetch = Some(Instant::now());
+
+        // Stringify the error so the future stays Send across the fallback lookup
+        match self.fetch_keys().await.map_err(|e| e.to_string()) {
+            Ok(fresh) => {
+                *self.keys.write().await = Some(fresh);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to refresh token signing keys: {}", e);
+                // Keep serving with the last known keys rather than locking everyone out
+                return self
+                    .cached_key(kid, true)
+                    .await
+                    .ok_or_else(|| Status::unavailable("Token signing keys are unavailable"));
+            }
+        }
+
+        self.cached_key(kid, false)
+            .await
+            .ok_or_else(|| Status::unauthenticated("Token signed with unknown key"))
+    }
+
+    async fn cached_key(&self, kid: &str, allow_stale: bool) -> Option<DecodingKey> {
+        let keys = self.keys.read().await;
+        let cached = keys.as_ref()?;
+
+        if !allow_stale && cached.expires_at <= Instant::now() {
+            return None;
+        }
+
+        cached.keys.get(kid).cloned()
+    }
+
+    async fn fetch_keys(&self) -> Result<CachedKeys, Box<dyn std::error::Error>> {
+        let res = self.client.get(&self.jwks_url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch signing keys: {}", e))?;
+
+        if !res.status().is_success() {
+            return Err(format!("Signing key endpoint returned {}", res.status()).into());
+        }
+
+        let ttl = res
+            .headers()
+            .get(reqwest::header::CACHE_CONTROL)
             .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.strip_prefix("Bearer "))
-            .map(|s| s.to_string())
-            .ok_or_else(|| Status::unauthenticated("Missing authorization token"))
+            .and_then(parse_max_age)
+            .unwrap_or(DEFAULT_KEY_TTL);
+
+        let jwks: JwkSet = res.json().await
+            .map_err(|e| format!("Failed to parse signing keys: {}", e))?;
+
+        let mut keys = HashMap::new();
+        for jwk in &jwks.keys {
+            let Some(kid) = jwk.common.key_id.clone() else {
+                continue;
+            };
+            match DecodingKey::from_jwk(jwk) {
+                Ok(key) => {
+                    keys.insert(kid, key);
+                }
+                Err(e) => tracing::warn!("Skipping unusable signing key {}: {}", kid, e),
+            }
+        }
+
+        tracing::debug!("Loaded {} token signing keys, valid for {}s", keys.len(), ttl.as_secs());
+
+        Ok(CachedKeys {
+            keys,
+            expires_at: Instant::now() + ttl,
+        })
     }
 }
 
-/// Interceptor for gRPC authentication
-pub fn auth_interceptor(
-    mut req: Request<()>,
-) -> Result<Request<()>, Status> {
-    // Extract and validate token
-    let token = req
-        .metadata()
-        .get("authorization")
+/// Read the max-age directive from a Cache-Control header value
+fn parse_max_age(cache_control: &str) -> Option<Duration> {
+    cache_control
+        .split(',')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|secs| secs.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Check the token timestamps and subject the way the Firebase Admin SDK does
+fn validate_timestamps(claims: &IdTokenClaims, now: i64, skew: i64) -> Result<(), &'static str> {
+    if claims.sub.is_empty() || claims.sub.len() > 128 {
+        return Err("Token has an invalid subject");
+    }
+    if claims.exp <= now - skew {
+        return Err("Token has expired");
+    }
+    if claims.iat > now + skew {
+        return Err("Token issued in the future");
+    }
+    if claims.auth_time > now + skew {
+        return Err("Token authenticated in the future");
+    }
+    Ok(())
+}
+
+/// Extract the bearer token from the `authorization` header
+fn bearer_token(headers: &http::HeaderMap) -> Result<String, Status> {
+    let value = headers
+        .get(http::header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
         .ok_or_else(|| Status::unauthenticated("Missing authorization header"))?;
 
-    if !token.starts_with("Bearer ") {
-        return Err(Status::unauthenticated("Invalid authorization format"));
+    value
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
+        .ok_or_else(|| Status::unauthenticated("Invalid authorization format"))
+}
+
+/// Authenticate a call to the gRPC method `path` and check it against the policy table.
+/// Public methods return `None` since they carry no caller identity.
+pub async fn authorize_call(
+    state: &AppState,
+    path: &str,
+    headers: &http::HeaderMap,
+) -> Result<Option<CallerContext>, Status> {
+    if state.auth.is_public(path) {
+        return Ok(None);
+    }
+
+    let caller = match bearer_token(headers) {
+        Ok(token) => state.auth.authenticate(&state.db, &token).await,
+        Err(status) => Err(status),
+    };
+
+    let authorized = caller.and_then(|caller| {
+        crate::policy::authorize_method(path, &caller).map(|()| caller)
+    });
+
+    match authorized {
+        Ok(caller) => Ok(Some(caller)),
+        Err(status) => {
+            if status.code() == tonic::Code::PermissionDenied {
+                state.metrics.increment_permission_denied();
+                tracing::warn!("Denied call to {}: {}", path, status.message());
+            } else {
+                tracing::debug!("Rejected call to {}: {}", path, status.message());
+            }
+            Err(status)
+        }
+    }
+}
+
+/// Tower layer that authenticates every gRPC call before it reaches a service
+#[derive(Clone)]
+pub struct AuthLayer {
+    state: Arc<AppState>,
+}
+
+impl AuthLayer {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+}
+
+impl<S> tower::Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    state: Arc<AppState>,
+}
+
+impl<S, ReqBody, ResBody> tower::Service<http::Request<ReqBody>> for AuthService<S>
+where
+    S: tower::Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
     }
 
-    // Token validation would happen here in production
-    // For now, just check if it exists
-    println!("Auth interceptor: Token present");
+    fn call(&mut self, mut req: http::Request<ReqBody>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
 
-    Ok(req)
+        let path = req.uri().path().to_string();
+        let headers = req.headers().clone();
+
+        Box::pin(async move {
+            match authorize_call(&state, &path, &headers).await {
+                Ok(Some(caller)) => {
+                    req.extensions_mut().insert(caller);
+                }
+                Ok(None) => {}
+                Err(status) => return Ok(status.into_http()),
+            }
+
+            inner.call(req).await
+        })
+    }
 }
 
 /// Helper to verify user has required role
@@ -70,10 +427,133 @@ pub fn check_role(user_role: &str, required_role: &str) -> Result<(), Status> {
 mod tests {
     use super::*;
 
+    fn claims(now: i64) -> IdTokenClaims {
+        IdTokenClaims {
+            sub: "uid123".to_string(),
+            iat: now,
+            exp: now + 3600,
+            auth_time: now,
+            email: Some("user@example.com".to_string()),
+            email_verified: true,
+            extra: serde_json::Map::new(),
+        }
+    }
+
     #[test]
     fn test_check_role() {
         assert!(check_role("admin", "user").is_ok());
         assert!(check_role("user", "user").is_ok());
         assert!(check_role("user", "admin").is_err());
     }
+
+    #[test]
+    fn test_bearer_token() {
+        let mut headers = http::HeaderMap::new();
+        assert!(bearer_token(&headers).is_err());
+
+        headers.insert(http::header::AUTHORIZATION, "Basic abc".parse().unwrap());
+        assert!(bearer_token(&headers).is_err());
+
+        headers.insert(http::header::AUTHORIZATION, "Bearer abc.def.ghi".parse().unwrap());
+        assert_eq!(bearer_token(&headers).unwrap(), "abc.def.ghi");
+    }
+
+    #[test]
+    fn test_parse_max_age() {
+        assert_eq!(
+            parse_max_age("public, max-age=19204, must-revalidate, no-transform"),
+            Some(Duration::from_secs(19204))
+        );
+        assert_eq!(parse_max_age("no-cache"), None);
+        assert_eq!(parse_max_age("max-age=abc"), None);
+    }
+
+    #[test]
+    fn test_validate_timestamps() {
+        let now = 1_700_000_000;
+        assert!(validate_timestamps(&claims(now), now, 60).is_ok());
+
+        // Small clock drift is tolerated
+        let mut drifted = claims(now + 30);
+        drifted.exp = now + 3600;
+        assert!(validate_timestamps(&drifted, now, 60).is_ok());
+
+        let mut future = claims(now + 300);
+        future.exp = now + 3600;
+        assert!(validate_timestamps(&future, now, 60).is_err());
+
+        let mut expired = claims(now - 7200);
+        expired.exp = now - 3600;
+        assert!(validate_timestamps(&expired, now, 60).is_err());
+
+        let mut no_subject = claims(now);
+        no_subject.sub.clear();
+        assert!(validate_timestamps(&no_subject, now, 60).is_err());
+    }
+
+    #[test]
+    fn test_custom_claims_exclude_reserved() {
+        let token: IdTokenClaims = serde_json::from_value(serde_json::json!({
+            "iss": "https://securetoken.google.com/demo",
+            "aud": "demo",
+            "sub": "uid123",
+            "user_id": "uid123",
+            "iat": 1_700_000_000,
+            "exp": 1_700_003_600,
+            "auth_time": 1_700_000_000,
+            "email": "user@example.com",
+            "email_verified": true,
+            "firebase": { "sign_in_provider": "password" },
+            "admin": true
+        }))
+        .unwrap();
+
+        let user = AuthenticatedUser::from(token);
+        assert_eq!(user.uid, "uid123");
+        assert_eq!(user.email.as_deref(), Some("user@example.com"));
+        assert!(user.email_verified);
+        assert_eq!(user.custom_claims.len(), 1);
+        assert_eq!(user.custom_claims["admin"], serde_json::json!(true));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_kid_refetches_keys_at_most_once_a_minute() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let jwks_url = format!("http://{}/jwks", listener.local_addr().unwrap());
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counter = fetches.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = stream.read(&mut [0; 4096]).await;
+                let body = r#"{"keys": []}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let auth = AuthMiddleware {
+            client: reqwest::Client::new(),
+            project_id: "project".to_string(),
+            jwks_url,
+            clock_skew: Duration::from_secs(60),
+            public_methods: HashSet::new(),
+            keys: RwLock::new(None),
+            refresh_lock: Mutex::new(None),
+        };
+
+        for _ in 0..3 {
+            let status = auth.decoding_key("forged").await.err().unwrap();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
 }
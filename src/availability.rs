@@ -0,0 +1,226 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use tonic::Status;
+
+use crate::models::{AvailabilityException, AvailabilityRule};
+
+/// Longest date range `ListBookableSlots` will expand
+pub const MAX_SLOT_RANGE_DAYS: i64 = 31;
+
+/// Slot length used when the request doesn't give one
+pub const DEFAULT_SLOT_MINUTES: i32 = 60;
+
+pub fn parse_time_zone(name: &str) -> Result<Tz, Status> {
+    name.parse()
+        .map_err(|_| Status::invalid_argument(format!("Unknown IANA time zone: {}", name)))
+}
+
+/// Parse a `YYYY-MM-DD` date
+pub fn parse_date(value: &str) -> Result<NaiveDate, Status> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| Status::invalid_argument(format!("Invalid date {:?}: {}", value, e)))
+}
+
+/// Parse an `HH:MM` (or `HH:MM:SS`) local time of day
+pub fn parse_time(value: &str) -> Result<NaiveTime, Status> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .map_err(|e| Status::invalid_argument(format!("Invalid time {:?}: {}", value, e)))
+}
+
+/// Windows stay within one local day, so the end must come after the start
+pub fn validate_window(start: NaiveTime, end: NaiveTime) -> Result<(), Status> {
+    if start >= end {
+        return Err(Status::invalid_argument(format!(
+            "Availability window must end after it starts ({} - {})",
+            start.format("%H:%M"),
+            end.format("%H:%M")
+        )));
+    }
+    Ok(())
+}
+
+/// The UTC instant of a wall-clock time in `time_zone`. Ambiguous times (clocks going
+/// back) take the earlier instant; times skipped when clocks go forward move to the end of the gap.
+pub fn local_to_utc(time_zone: Tz, local: NaiveDateTime) -> Option<NaiveDateTime> {
+    time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| time_zone.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|at| at.naive_utc())
+}
+
+/// A mentor's published availability, evaluated in their time zone. All
+/// `NaiveDateTime`s going in or out are UTC.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    time_zone: Tz,
+    rules: Vec<AvailabilityRule>,
+    exceptions: Vec<AvailabilityException>,
+}
+
+impl Schedule {
+    pub fn new(time_zone: Tz, rules: Vec<AvailabilityRule>, exceptions: Vec<AvailabilityException>) -> Self {
+        Self {
+            time_zone,
+            rules,
+            exceptions,
+        }
+    }
+
+    /// The mentor's local date at a UTC instant
+    pub fn local_date(&self, at: NaiveDateTime) -> NaiveDate {
+        self.time_zone.from_utc_datetime(&at).date_naive()
+    }
+
+    /// Local windows on `date`: a blackout removes the whole day, overrides replace the weekly windows
+    fn local_windows(&self, date: NaiveDate) -> Vec<(NaiveTime, NaiveTime)> {
+        let exceptions: Vec<&AvailabilityException> =
+            self.exceptions.iter().filter(|exception| exception.date == date).collect();
+
+        if exceptions.iter().any(|exception| exception.is_blackout) {
+            return Vec::new();
+        }
+        if !exceptions.is_empty() {
+            return exceptions
+                .iter()
+                .filter_map(|exception| Some((exception.start_time?, exception.end_time?)))
+                .collect();
+        }
+
+        let weekday = date.weekday().number_from_monday() as u8;
+        self.rules
+            .iter()
+            .filter(|rule| rule.weekday == weekday)
+            .map(|rule| (rule.start_time, rule.end_time))
+            .collect()
+    }
+
+    /// Availability windows on the mentor's local `date`, in UTC
+    pub fn windows_on(&self, date: NaiveDate) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        self.local_windows(date)
+            .into_iter()
+            .filter_map(|(start, end)| Some((self.to_utc(date.and_time(start))?, self.to_utc(date.and_time(end))?)))
+            .collect()
+    }
+
+    fn to_utc(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        local_to_utc(self.time_zone, local)
+    }
+
+    /// Whether `[start, end)` lies entirely inside one availability window
+    pub fn contains(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        // Windows never cross local midnight, so only the start's local date can hold it
+        self.windows_on(self.local_date(start))
+            .iter()
+            .any(|(window_start, window_end)| *window_start <= start && end <= *window_end)
+    }
+
+    /// Back-to-back slots of length `slot` within the windows on local dates
+    /// `from..=to`, skipping any that overlap `busy` or start before `not_before`
+    pub fn open_slots(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        slot: Duration,
+        busy: &[(NaiveDateTime, NaiveDateTime)],
+        not_before: NaiveDateTime,
+    ) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let mut slots = Vec::new();
+
+        for date in from.iter_days().take_while(|date| *date <= to) {
+            for (window_start, window_end) in self.windows_on(date) {
+                let mut start = window_start;
+                while start + slot <= window_end {
+                    let end = start + slot;
+                    let free = !busy.iter().any(|(busy_start, busy_end)| *busy_start < end && start < *busy_end);
+                    if free && start >= not_before {
+                        slots.push((start, end));
+                    }
+                    start = end;
+                }
+            }
+        }
+
+        slots.sort();
+        slots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(weekday: u8, start: &str, end: &str) -> AvailabilityRule {
+        AvailabilityRule {
+            id: 0,
+            mentor_id: 1,
+            weekday,
+            start_time: parse_time(start).unwrap(),
+            end_time: parse_time(end).unwrap(),
+        }
+    }
+
+    fn exception(date: &str, window: Option<(&str, &str)>) -> AvailabilityException {
+        AvailabilityException {
+            id: 0,
+            mentor_id: 1,
+            date: parse_date(date).unwrap(),
+            is_blackout: window.is_none(),
+            start_time: window.map(|(start, _)| parse_time(start).unwrap()),
+            end_time: window.map(|(_, end)| parse_time(end).unwrap()),
+        }
+    }
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_weekly_windows_convert_to_utc() {
+        // Mondays 09:00-12:00 in New York, which is UTC-5 in January
+        let schedule = Schedule::new(parse_time_zone("America/New_York").unwrap(), vec![rule(1, "09:00", "12:00")], vec![]);
+
+        let windows = schedule.windows_on(parse_date("2025-01-06").unwrap());
+        assert_eq!(windows, vec![(at("2025-01-06 14:00:00"), at("2025-01-06 17:00:00"))]);
+        assert!(schedule.windows_on(parse_date("2025-01-07").unwrap()).is_empty());
+
+        assert!(schedule.contains(at("2025-01-06 14:00:00"), at("2025-01-06 15:00:00")));
+        assert!(!schedule.contains(at("2025-01-06 16:30:00"), at("2025-01-06 17:30:00")));
+    }
+
+    #[test]
+    fn test_overrides_and_blackouts() {
+        let schedule = Schedule::new(
+            Tz::UTC,
+            vec![rule(1, "09:00", "17:00"), rule(2, "09:00", "17:00")],
+            vec![exception("2025-01-06", None), exception("2025-01-07", Some(("13:00", "14:00")))],
+        );
+
+        assert!(schedule.windows_on(parse_date("2025-01-06").unwrap()).is_empty());
+        assert_eq!(
+            schedule.windows_on(parse_date("2025-01-07").unwrap()),
+            vec![(at("2025-01-07 13:00:00"), at("2025-01-07 14:00:00"))]
+        );
+        assert_eq!(schedule.windows_on(parse_date("2025-01-13").unwrap()).len(), 1);
+    }
+
+    #[test]
+    fn test_open_slots_skip_busy_and_past() {
+        let schedule = Schedule::new(Tz::UTC, vec![rule(1, "09:00", "13:00")], vec![]);
+        let monday = parse_date("2025-01-06").unwrap();
+        let busy = [(at("2025-01-06 10:30:00"), at("2025-01-06 11:30:00"))];
+
+        let slots = schedule.open_slots(monday, monday, Duration::hours(1), &busy, at("2025-01-06 09:30:00"));
+        assert_eq!(slots, vec![(at("2025-01-06 12:00:00"), at("2025-01-06 13:00:00"))]);
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(parse_time_zone("Europe/Berlin").is_ok());
+        assert!(parse_time_zone("Mars/Olympus").is_err());
+        assert!(parse_time("9:30").is_ok());
+        assert!(parse_time("25:00").is_err());
+        assert!(validate_window(parse_time("10:00").unwrap(), parse_time("09:00").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! Pushes to many devices at once. Every device token is subscribed to the `all`
+//! FCM topic and to its owner's role topic, so admins can broadcast to a topic or
+//! a condition over them; lists of users are sent to device by device instead,
+//! which also reaches Web Push subscriptions.
+//!
+//! Subscriptions are synced by this worker rather than on registration, so
+//! registering doesn't wait on the Instance ID API and failed syncs are retried.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::{Notify, Semaphore};
+
+use crate::firebase::NotificationType;
+use crate::models::{DeviceToken, TopicSubscription};
+use crate::push::PushDevice;
+use crate::AppState;
+
+/// Topic every device token is subscribed to
+pub const ALL_TOPIC: &str = "all";
+
+/// Most topics FCM allows in one condition
+const MAX_CONDITION_TOPICS: usize = 5;
+
+/// Sends in flight at once when fanning out to devices
+const FAN_OUT_CONCURRENCY: usize = 16;
+
+/// Fallback for wakeups missed by this replica, and the retry interval after failed syncs
+const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+const BATCH_SIZE: u32 = crate::firebase::MAX_TOPIC_BATCH as u32;
+
+/// Instance ID errors meaning the token will never work again
+const INVALID_TOKEN_ERRORS: &[&str] = &["NOT_FOUND", "INVALID_ARGUMENT"];
+
+/// Topic of everyone with `role`, e.g. `role_mentor`
+pub fn role_topic(role: &str) -> String {
+    format!("role_{}", role)
+}
+
+/// Whether device tokens are ever subscribed to `topic`
+pub fn is_known_topic(topic: &str) -> bool {
+    topic == ALL_TOPIC || topic.strip_prefix("role_").is_some_and(|role| crate::api::ROLES.contains(&role))
+}
+
+/// Check an FCM condition such as `'role_mentor' in topics && !('role_admin' in topics)`:
+/// known topics combined with `&&`, `||`, `!` and parentheses, at most five of them
+pub fn validate_condition(condition: &str) -> Result<(), String> {
+    let mut rest = condition.trim_start();
+    let mut topics = 0;
+    let mut depth = 0;
+    let mut expect_operand = true;
+
+    while !rest.is_empty() {
+        if expect_operand {
+            if let Some(after) = rest.strip_prefix('!') {
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('(') {
+                depth += 1;
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('\'') {
+                let (topic, after) = after.split_once('\'').ok_or("Unterminated topic name in condition")?;
+                if !is_known_topic(topic) {
+                    return Err(format!("Unknown topic '{}' in condition", topic));
+                }
+                rest = after
+                    .trim_start()
+                    .strip_prefix("in")
+                    .filter(|after| after.starts_with(char::is_whitespace))
+                    .and_then(|after| after.trim_start().strip_prefix("topics"))
+                    .ok_or_else(|| format!("Expected \"in topics\" after '{}'", topic))?;
+                topics += 1;
+                expect_operand = false;
+            } else {
+                return Err("Expected a topic, ! or ( in condition".to_string());
+            }
+        } else if let Some(after) = rest.strip_prefix(')') {
+            if depth == 0 {
+                return Err("Unbalanced parentheses in condition".to_string());
+            }
+            depth -= 1;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("&&").or_else(|| rest.strip_prefix("||")) {
+            rest = after;
+            expect_operand = true;
+        } else {
+            return Err("Expected &&, || or ) in condition".to_string());
+        }
+        rest = rest.trim_start();
+    }
+
+    if expect_operand || depth != 0 {
+        return Err("Incomplete condition".to_string());
+    }
+    if topics > MAX_CONDITION_TOPICS {
+        return Err(format!("A condition can use at most {} topics", MAX_CONDITION_TOPICS));
+    }
+    Ok(())
+}
+
+/// Outcome of a broadcast to one device
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceResult {
+    pub device_token_id: u64,
+    pub user_id: u64,
+    pub device_type: String,
+    pub sent: bool,
+    pub error: Option<String>,
+    /// The provider reported the token permanently invalid and it was deleted
+    pub token_removed: bool,
+}
+
+/// Send a message to each device, `FAN_OUT_CONCURRENCY` at a time, without retries.
+/// Results are ordered by device token ID.
+pub async fn fan_out(
+    state: &Arc<AppState>,
+    devices: Vec<DeviceToken>,
+    title: &str,
+    body: &str,
+    payload: &NotificationType,
+) -> Vec<DeviceResult> {
+    let message = Arc::new((title.to_string(), body.to_string(), payload.clone()));
+    let permits = Arc::new(Semaphore::new(FAN_OUT_CONCURRENCY));
+    let mut sends = tokio::task::JoinSet::new();
+
+    for device in devices {
+        let Ok(permit) = permits.clone().acquire_owned().await else {
+            break;
+        };
+        let state = state.clone();
+        let message = message.clone();
+        sends.spawn(async move {
+            let result = send_to_device(&state, device, &message).await;
+            drop(permit);
+            result
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(result) = sends.join_next().await {
+        match result {
+            Ok(result) => results.push(result),
+            Err(e) => tracing::error!("Broadcast send task failed: {}", e),
+        }
+    }
+    results.sort_by_key(|result| result.device_token_id);
+    results
+}
+
+async fn send_to_device(
+    state: &Arc<AppState>,
+    device: DeviceToken,
+    (title, body, payload): &(String, String, NotificationType),
+) -> DeviceResult {
+    let result = match PushDevice::from_stored(
+        &device.provider,
+        &device.token,
+        device.web_push_p256dh.as_deref(),
+        device.web_push_auth.as_deref(),
+    ) {
+        Ok(push_device) => crate::push::send(state, push_device, title, body, payload).await,
+        Err(e) => Err(e),
+    };
+
+    let mut token_removed = false;
+    if let Err(e) = &result {
+        if e.is_token_invalid() {
+            match crate::db::delete_device_token(&state.db, &device.token).await.map_err(|e| e.to_string()) {
+                Ok(removed) => token_removed = removed,
+                Err(e) => tracing::warn!("Failed to remove device token {}: {}", device.id, e),
+            }
+        }
+    } else {
+        state.metrics.increment_notifications_sent();
+    }
+
+    DeviceResult {
+        device_token_id: device.id,
+        user_id: device.user_id,
+        device_type: device.device_type,
+        sent: result.is_ok(),
+        error: result.err().map(|e| e.to_string()),
+        token_removed,
+    }
+}
+
+/// Wakes the worker when a token is registered or a user's role changes
+#[derive(Default)]
+pub struct TopicSync {
+    pending: Notify,
+}
+
+impl TopicSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wake(&self) {
+        self.pending.notify_one();
+    }
+}
+
+pub async fn run_worker(state: Arc<AppState>) {
+    loop {
+        match sync_topics(&state).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to sync topic subscriptions: {}", e),
+        }
+
+        tokio::select! {
+            _ = state.topics.pending.notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// Bring one batch of tokens' subscriptions in line with their owners' roles, returning
+/// whether more may be waiting
+async fn sync_topics(state: &Arc<AppState>) -> Result<bool, String> {
+    let tokens = crate::db::get_unsynced_device_tokens(&state.db, BATCH_SIZE)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut changes: BTreeMap<(Option<&str>, &str), Vec<&TopicSubscription>> = BTreeMap::new();
+    for token in &tokens {
+        changes.entry((token.topic_role.as_deref(), &token.role)).or_default().push(token);
+    }
+
+    let mut synced = 0;
+    for ((from, to), group) in changes {
+        synced += sync_group(state, from, to, &group).await?;
+    }
+    // Tokens that keep failing stay in the batch, so only go again if it made progress
+    Ok(tokens.len() == BATCH_SIZE as usize && synced > 0)
+}
+
+/// Move tokens from the `from` role topic to the `to` one, or into `all` and the `to` topic
+/// when they have none yet. Returns how many tokens were synced or removed as invalid.
+async fn sync_group(state: &Arc<AppState>, from: Option<&str>, to: &str, tokens: &[&TopicSubscription]) -> Result<usize, String> {
+    let values: Vec<String> = tokens.iter().map(|token| token.token.clone()).collect();
+
+    let first = match from {
+        // Otherwise the token keeps receiving the old role's broadcasts
+        Some(from) => state.firebase.unsubscribe_from_topic(&role_topic(from), &values).await,
+        None => state.firebase.subscribe_to_topic(ALL_TOPIC, &values).await,
+    }
+    .map_err(|e| e.to_string())?;
+    let second = state
+        .firebase
+        .subscribe_to_topic(&role_topic(to), &values)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut synced = 0;
+    for ((token, first), second) in tokens.iter().zip(first).zip(second) {
+        match first.or(second) {
+            None => {
+                crate::db::set_device_token_topic_role(&state.db, token.id, to)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                synced += 1;
+            }
+            Some(error) if INVALID_TOKEN_ERRORS.contains(&error.as_str()) => {
+                tracing::info!("Removing device token {}: {}", token.id, error);
+                crate::db::delete_device_token(&state.db, &token.token)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                synced += 1;
+            }
+            Some(error) => tracing::warn!("Failed to subscribe device token {} to {}: {}", token.id, role_topic(to), error),
+        }
+    }
+    Ok(synced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_known_topic() {
+        assert!(is_known_topic("all"));
+        assert!(is_known_topic(&role_topic("mentor")));
+        assert!(!is_known_topic("role_superuser"));
+        assert!(!is_known_topic("mentors"));
+    }
+
+    #[test]
+    fn test_validate_condition() {
+        assert_eq!(validate_condition("'role_mentor' in topics"), Ok(()));
+        assert_eq!(validate_condition("'all' in topics && !('role_admin' in topics || 'role_mentor' in topics)"), Ok(()));
+        assert_eq!(validate_condition("  ('role_user' in   topics)  "), Ok(()));
+
+        assert!(validate_condition("").is_err());
+        assert!(validate_condition("'role_mentor' in topics &&").is_err());
+        assert!(validate_condition("'role_mentor' intopics").is_err());
+        assert!(validate_condition("'role_mentor' in topics)").is_err());
+        assert!(validate_condition("('role_mentor' in topics").is_err());
+        assert!(validate_condition("'role_mentor' topics").is_err());
+        assert!(validate_condition("'beta' in topics").unwrap_err().contains("Unknown topic"));
+
+        let six = ["'all' in topics"; 6].join(" || ");
+        assert!(validate_condition(&six).unwrap_err().contains("at most 5"));
+    }
+}
@@ -0,0 +1,245 @@
+//! iCalendar (RFC 5545) export of sessions, and the secret tokens that
+//! protect each user's subscribable feed.
+
+use chrono::NaiveDateTime;
+use sha2::{Digest, Sha256};
+
+use crate::models::{Session, User};
+
+pub const PRODID: &str = "-//LinkWithMentor//Sessions//EN";
+
+/// Sessions that started longer ago than this are left out of feeds
+pub const FEED_HISTORY_DAYS: u64 = 90;
+
+/// How often subscribed calendars should refetch a feed
+const REFRESH_INTERVAL: &str = "PT1H";
+
+/// Content lines longer than this many octets are folded
+const MAX_LINE_OCTETS: usize = 75;
+
+/// A new random feed token, 256 bits as hex
+pub fn generate_feed_token() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Only this hash is stored, so a database leak doesn't expose feed URLs
+pub fn hash_feed_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Stable across changes, so calendars update the event instead of adding another
+pub fn event_uid(session_id: u64) -> String {
+    format!("session-{}@linkwithmentor", session_id)
+}
+
+/// A `VEVENT` for a session, organized by its mentor with the mentee as attendee
+pub fn session_event(session: &Session, mentor: &User, mentee: &User) -> Vec<String> {
+    let ends_at = session.scheduled_at + chrono::Duration::minutes(session.duration_minutes.into());
+    let status = if session.status == "cancelled" { "CANCELLED" } else { "CONFIRMED" };
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", event_uid(session.id)),
+        format!("DTSTAMP:{}", format_time(session.updated_at)),
+        format!("CREATED:{}", format_time(session.created_at)),
+        format!("LAST-MODIFIED:{}", format_time(session.updated_at)),
+        format!("SEQUENCE:{}", session.sequence),
+        format!("DTSTART:{}", format_time(session.scheduled_at)),
+        format!("DTEND:{}", format_time(ends_at)),
+        format!("SUMMARY:{}", escape_text(&session.title)),
+        format!("STATUS:{}", status),
+    ];
+    if let Some(description) = session.description.as_deref().filter(|description| !description.is_empty()) {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(link) = session.meeting_link.as_deref().filter(|link| !link.is_empty()) {
+        lines.push(format!("LOCATION:{}", escape_text(link)));
+        // URI values aren't escaped, so links stored before validation could inject properties
+        if (link.starts_with("https://") || link.starts_with("http://")) && !link.chars().any(char::is_control) {
+            lines.push(format!("URL:{}", link));
+        }
+    }
+    if is_safe_email(&mentor.email) {
+        lines.push(format!("ORGANIZER{}:mailto:{}", common_name(mentor), mentor.email));
+    }
+    if is_safe_email(&mentee.email) {
+        lines.push(format!(
+            "ATTENDEE{};ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED:mailto:{}",
+            common_name(mentee),
+            mentee.email
+        ));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// A `VCALENDAR` holding the given events, folded and CRLF-terminated
+pub fn calendar(name: Option<&str>, events: impl IntoIterator<Item = Vec<String>>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+    ];
+    if let Some(name) = name {
+        lines.push(format!("NAME:{}", escape_text(name)));
+        lines.push(format!("X-WR-CALNAME:{}", escape_text(name)));
+        lines.push(format!("REFRESH-INTERVAL;VALUE=DURATION:{}", REFRESH_INTERVAL));
+        lines.push(format!("X-PUBLISHED-TTL:{}", REFRESH_INTERVAL));
+    }
+    lines.extend(events.into_iter().flatten());
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+fn format_time(at: NaiveDateTime) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Whether `email` can go into a `mailto:` value as is. Addresses stored before they were
+/// validated could otherwise end the line or start another property or parameter.
+fn is_safe_email(email: &str) -> bool {
+    !email.is_empty() && !email.chars().any(|c| c.is_control() || c == ':' || c == ';')
+}
+
+/// `;CN="..."` with the user's display name, or nothing if they have none
+fn common_name(user: &User) -> String {
+    match user.display_name.as_deref().map(str::trim).filter(|name| !name.is_empty()) {
+        // DQUOTE and control characters can't appear in a quoted parameter value
+        Some(name) => format!(";CN=\"{}\"", name.replace(|c: char| c == '"' || c.is_control(), "")),
+        None => String::new(),
+    }
+}
+
+/// Escape a TEXT value (RFC 5545 section 3.3.11)
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold a content line into chunks of at most 75 octets without splitting a character
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(id: u64, name: Option<&str>) -> User {
+        let now = chrono::Utc::now().naive_utc();
+        User {
+            id,
+            firebase_uid: format!("uid-{}", id),
+            email: format!("user{}@example.com", id),
+            display_name: name.map(str::to_string),
+            photo_url: None,
+            role: "mentee".to_string(),
+            time_zone: "UTC".to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn session(status: &str) -> Session {
+        let at = crate::timestamp::parse("2025-12-01T10:00:00Z").unwrap();
+        Session {
+            id: 7,
+            user_id: 1,
+            mentor_id: 2,
+            title: "Career chat; résumé, goals".to_string(),
+            description: Some("Bring\nquestions".to_string()),
+            scheduled_at: at,
+            duration_minutes: 45,
+            status: status.to_string(),
+            meeting_link: Some("https://meet.example.com/abc".to_string()),
+            cancelled_by: None,
+            cancellation_reason: None,
+            series_id: None,
+            original_scheduled_at: None,
+            sequence: 2,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[test]
+    fn test_session_event() {
+        let lines = session_event(&session("scheduled"), &user(2, Some("Ada \"The\" Mentor")), &user(1, None));
+
+        assert!(lines.contains(&"UID:session-7@linkwithmentor".to_string()));
+        assert!(lines.contains(&"SEQUENCE:2".to_string()));
+        assert!(lines.contains(&"DTSTART:20251201T100000Z".to_string()));
+        assert!(lines.contains(&"DTEND:20251201T104500Z".to_string()));
+        assert!(lines.contains(&"SUMMARY:Career chat\\; résumé\\, goals".to_string()));
+        assert!(lines.contains(&"DESCRIPTION:Bring\\nquestions".to_string()));
+        assert!(lines.contains(&"STATUS:CONFIRMED".to_string()));
+        assert!(lines.contains(&"URL:https://meet.example.com/abc".to_string()));
+        assert!(lines.contains(&"ORGANIZER;CN=\"Ada The Mentor\":mailto:user2@example.com".to_string()));
+        assert!(lines.contains(&"ATTENDEE;ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED:mailto:user1@example.com".to_string()));
+
+        let cancelled = session_event(&session("cancelled"), &user(2, None), &user(1, None));
+        assert!(cancelled.contains(&"STATUS:CANCELLED".to_string()));
+
+        let mut injected = session("scheduled");
+        injected.meeting_link = Some("https://x\r\nATTENDEE:mailto:eve@example.com".to_string());
+        let lines = session_event(&injected, &user(2, None), &user(1, None));
+        assert!(lines.contains(&"LOCATION:https://x\\nATTENDEE:mailto:eve@example.com".to_string()));
+        assert!(!lines.iter().any(|line| line.starts_with("URL:")));
+        assert_eq!(lines.iter().filter(|line| line.starts_with("ATTENDEE")).count(), 1);
+
+        let mut mentee = user(1, None);
+        mentee.email = "a@x.com\r\nATTENDEE:mailto:eve@evil.example".to_string();
+        let mut mentor = user(2, None);
+        mentor.email = "m@x.com;RSVP=TRUE".to_string();
+        let lines = session_event(&session("scheduled"), &mentor, &mentee);
+        assert!(!lines.iter().any(|line| line.starts_with("ATTENDEE") || line.starts_with("ORGANIZER")));
+        assert!(!lines.iter().any(|line| line.contains('\n') || line.contains("eve@")));
+    }
+
+    #[test]
+    fn test_calendar_folds_long_lines() {
+        let mut long = session("scheduled");
+        long.description = Some("ü".repeat(100));
+        let ics = calendar(Some("Sessions"), [session_event(&long, &user(2, None), &user(1, None))]);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("DESCRIPTION:{}\r\n", "ü".repeat(100))));
+    }
+
+    #[test]
+    fn test_feed_token() {
+        let token = generate_feed_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, generate_feed_token());
+        assert_eq!(hash_feed_token(&token), hash_feed_token(&token));
+        assert_ne!(hash_feed_token(&token), token);
+    }
+}
@@ -1,5 +1,15 @@
 use std::env;
 
+/// gRPC paths callable without a bearer token unless `AUTH_PUBLIC_METHODS` overrides them
+const DEFAULT_PUBLIC_METHODS: &[&str] = &[
+    "/service.LinkWithMentor/Ping",
+    "/service.LinkWithMentor/HealthCheck",
+    "/grpc.health.v1.Health/Check",
+    "/grpc.health.v1.Health/Watch",
+    "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+
 pub struct Config {
     pub host: String,
     pub port: u16,
@@ -8,6 +18,34 @@ pub struct Config {
     pub db_name: String,
     pub db_user: String,
     pub db_pass: String,
+    pub firebase_project_id: String,
+    pub firebase_jwks_url: String,
+    /// OAuth token endpoint for the FCM service account; overridable to point at a mock
+    pub google_oauth_token_url: String,
+    /// Origin of the FCM HTTP v1 API; overridable to point at a mock
+    pub fcm_base_url: String,
+    /// Origin of the Instance ID API that manages topic subscriptions; overridable to point at a mock
+    pub iid_base_url: String,
+    /// Base64url P-256 private key that signs Web Push requests; Web Push is disabled when unset
+    pub vapid_private_key: Option<String>,
+    /// Contact for push service operators, a `mailto:` or `https:` URL
+    pub vapid_subject: String,
+    /// Public key web clients subscribe with, expected to match `vapid_private_key`
+    pub firebase_web_push_cert: Option<String>,
+    /// Push service hosts Web Push endpoints may point at; `*.` matches subdomains
+    pub web_push_allowed_hosts: Vec<String>,
+    /// Device tokens not registered again for this many days are deleted; 0 keeps them forever
+    pub device_token_max_age_days: u32,
+    /// Read notifications older than this many days are deleted; 0 keeps them forever
+    pub notification_retention_days: u32,
+    pub auth_clock_skew_secs: u64,
+    pub auth_public_methods: Vec<String>,
+    /// Directory of the mentor search index; kept in memory when unset
+    pub search_index_dir: Option<String>,
+    /// Origin of the HTTP/3 listener as clients reach it, used in links such as calendar feed URLs
+    pub public_base_url: String,
+    /// Minutes before a session's start to remind its participants, besides the "starting now" ping
+    pub reminder_offsets: Vec<u32>,
 }
 
 impl Config {
@@ -28,6 +66,58 @@ impl Config {
         let db_user = env::var("DB_USERNAME").map_err(|_| "DB_USERNAME must be set")?;
         let db_pass = env::var("DB_PASSWORD").map_err(|_| "DB_PASSWORD must be set")?;
 
+        let firebase_project_id = env::var("FIREBASE_PROJECT_ID")
+            .map_err(|_| "FIREBASE_PROJECT_ID must be set")?;
+        let firebase_jwks_url = env::var("FIREBASE_JWKS_URL")
+            .unwrap_or_else(|_| crate::auth::GOOGLE_SECURETOKEN_JWKS_URL.to_string());
+        let google_oauth_token_url = env::var("GOOGLE_OAUTH_TOKEN_URL")
+            .unwrap_or_else(|_| crate::firebase::GOOGLE_OAUTH_TOKEN_URL.to_string());
+        let fcm_base_url = env::var("FCM_BASE_URL")
+            .unwrap_or_else(|_| crate::firebase::FCM_BASE_URL.to_string());
+        let iid_base_url = env::var("IID_BASE_URL")
+            .unwrap_or_else(|_| crate::firebase::IID_BASE_URL.to_string());
+        let vapid_private_key = env::var("VAPID_PRIVATE_KEY").ok().filter(|key| !key.is_empty());
+        let vapid_subject = env::var("VAPID_SUBJECT").unwrap_or_default();
+        if vapid_private_key.is_some() && vapid_subject.is_empty() {
+            return Err("VAPID_SUBJECT must be set when VAPID_PRIVATE_KEY is".into());
+        }
+        let firebase_web_push_cert = env::var("FIREBASE_WEB_PUSH_CERT").ok().filter(|cert| !cert.is_empty());
+        let web_push_allowed_hosts = env::var("WEB_PUSH_ALLOWED_HOSTS")
+            .unwrap_or_else(|_| crate::webpush::DEFAULT_ALLOWED_HOSTS.join(","))
+            .split(',')
+            .map(|host| host.trim().to_ascii_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        let device_token_max_age_days = env::var("DEVICE_TOKEN_MAX_AGE_DAYS").unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .map_err(|_| "DEVICE_TOKEN_MAX_AGE_DAYS must be a number")?;
+        let notification_retention_days = env::var("NOTIFICATION_RETENTION_DAYS").unwrap_or_else(|_| "90".to_string())
+            .parse()
+            .map_err(|_| "NOTIFICATION_RETENTION_DAYS must be a number")?;
+        let auth_clock_skew_secs = env::var("AUTH_CLOCK_SKEW_SECS").unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .map_err(|_| "AUTH_CLOCK_SKEW_SECS must be a number")?;
+
+        // gRPC paths that can be called without a bearer token
+        let auth_public_methods = env::var("AUTH_PUBLIC_METHODS")
+            .unwrap_or_else(|_| DEFAULT_PUBLIC_METHODS.join(","))
+            .split(',')
+            .map(|method| method.trim().to_string())
+            .filter(|method| !method.is_empty())
+            .collect();
+
+        let search_index_dir = env::var("SEARCH_INDEX_DIR").ok().filter(|dir| !dir.is_empty());
+        let public_base_url = env::var("PUBLIC_BASE_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| format!("https://{}:{}", host, port))
+            .trim_end_matches('/')
+            .to_string();
+
+        let reminder_offsets = crate::reminders::parse_offsets(
+            &env::var("REMINDER_OFFSETS").unwrap_or_else(|_| crate::reminders::DEFAULT_OFFSETS.to_string()),
+        )?;
+
         Ok(Self {
             host,
             port,
@@ -36,6 +126,22 @@ impl Config {
             db_name,
             db_user,
             db_pass,
+            firebase_project_id,
+            firebase_jwks_url,
+            google_oauth_token_url,
+            fcm_base_url,
+            iid_base_url,
+            vapid_private_key,
+            vapid_subject,
+            firebase_web_push_cert,
+            web_push_allowed_hosts,
+            device_token_max_age_days,
+            notification_retention_days,
+            auth_clock_skew_secs,
+            auth_public_methods,
+            search_index_dir,
+            public_base_url,
+            reminder_offsets,
         })
     }
 }
@@ -39,14 +39,14 @@ pub async fn init(config: &Config) -> Result<DbPool, Box<dyn std::error::Error>>
 pub async fn create_user(pool: &DbPool, user: &crate::models::CreateUser) -> Result<u64, Box<dyn std::error::Error>> {
     let result = sqlx::query!(
         r#"
-        INSERT INTO users (firebase_uid, email, display_name, photo_url, role)
-        VALUES (?, ?, ?, ?, COALESCE(?, 'user'))
+        INSERT INTO users (firebase_uid, email, display_name, photo_url, role, time_zone)
+        VALUES (?, ?, ?, ?, 'user', COALESCE(?, 'UTC'))
         "#,
         user.firebase_uid,
         user.email,
         user.display_name,
         user.photo_url,
-        user.role
+        user.time_zone
     )
     .execute(pool)
     .await?;
@@ -57,7 +57,7 @@ pub async fn create_user(pool: &DbPool, user: &crate::models::CreateUser) -> Res
 pub async fn get_user_by_firebase_uid(pool: &DbPool, firebase_uid: &str) -> Result<Option<crate::models::User>, Box<dyn std::error::Error>> {
     let user = sqlx::query_as!(
         crate::models::User,
-        r#"SELECT id, firebase_uid, email, display_name, photo_url, role, created_at, updated_at FROM users WHERE firebase_uid = ?"#,
+        r#"SELECT id, firebase_uid, email, display_name, photo_url, role, time_zone, created_at, updated_at FROM users WHERE firebase_uid = ?"#,
         firebase_uid
     )
     .fetch_optional(pool)
@@ -69,7 +69,7 @@ pub async fn get_user_by_firebase_uid(pool: &DbPool, firebase_uid: &str) -> Resu
 pub async fn get_user_by_id(pool: &DbPool, user_id: u64) -> Result<Option<crate::models::User>, Box<dyn std::error::Error>> {
     let user = sqlx::query_as!(
         crate::models::User,
-        r#"SELECT id, firebase_uid, email, display_name, photo_url, role, created_at, updated_at FROM users WHERE id = ?"#,
+        r#"SELECT id, firebase_uid, email, display_name, photo_url, role, time_zone, created_at, updated_at FROM users WHERE id = ?"#,
         user_id
     )
     .fetch_optional(pool)
@@ -78,31 +78,243 @@ pub async fn get_user_by_id(pool: &DbPool, user_id: u64) -> Result<Option<crate:
     Ok(user)
 }
 
+pub async fn update_user_time_zone(pool: &DbPool, user_id: u64, time_zone: &str) -> Result<(), Box<dyn std::error::Error>> {
+    sqlx::query!(
+        "UPDATE users SET time_zone = ? WHERE id = ?",
+        time_zone,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 // Session CRUD operations
-pub async fn create_session(pool: &DbPool, session: &crate::models::CreateSession) -> Result<u64, Box<dyn std::error::Error>> {
+
+/// Length of a session when `duration_minutes` is not given
+pub const DEFAULT_DURATION_MINUTES: i32 = 60;
+
+/// Outcome of booking a session
+#[derive(Debug)]
+pub enum SessionBooking {
+    Created(u64),
+    /// IDs of the active sessions overlapping the requested window
+    Conflicts(Vec<u64>),
+}
+
+/// Outcome of a session status change
+#[derive(Debug)]
+pub enum TransitionOutcome {
+    Applied,
+    /// The session was no longer in the expected status
+    Stale,
+    /// IDs of the active sessions overlapping the new window of a reschedule
+    Conflicts(Vec<u64>),
+}
+
+/// Lock both participants' `users` rows for the rest of the transaction. A range lock on
+/// `sessions` can't express overlap, so bookings are serialized per participant instead.
+async fn lock_participants(tx: &mut sqlx::Transaction<'_, MySql>, user_id: u64, mentor_id: u64) -> Result<(), Box<dyn std::error::Error>> {
+    sqlx::query!(
+        "SELECT id FROM users WHERE id IN (?, ?) ORDER BY id FOR UPDATE",
+        user_id,
+        mentor_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// IDs of scheduled or ongoing sessions of either participant overlapping `[start, start + duration)`
+async fn find_conflicting_sessions(
+    tx: &mut sqlx::Transaction<'_, MySql>,
+    user_id: u64,
+    mentor_id: u64,
+    start: chrono::NaiveDateTime,
+    duration_minutes: i32,
+    exclude_session_id: Option<u64>,
+) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM sessions
+        WHERE (user_id IN (?, ?) OR mentor_id IN (?, ?))
+          AND status IN ('scheduled', 'ongoing')
+          AND id <> ?
+          AND scheduled_at < DATE_ADD(?, INTERVAL ? MINUTE)
+          AND DATE_ADD(scheduled_at, INTERVAL COALESCE(duration_minutes, ?) MINUTE) > ?
+        ORDER BY id
+        "#,
+        user_id,
+        mentor_id,
+        user_id,
+        mentor_id,
+        exclude_session_id.unwrap_or(0),
+        start,
+        duration_minutes,
+        DEFAULT_DURATION_MINUTES,
+        start
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Insert a session unless it overlaps an active session of the mentor or mentee
+pub async fn create_session(pool: &DbPool, session: &crate::models::CreateSession) -> Result<SessionBooking, Box<dyn std::error::Error>> {
+    let duration_minutes = session.duration_minutes.unwrap_or(DEFAULT_DURATION_MINUTES);
+    let mut tx = pool.begin().await?;
+
+    lock_participants(&mut tx, session.user_id, session.mentor_id).await?;
+
+    let conflicts = find_conflicting_sessions(
+        &mut tx,
+        session.user_id,
+        session.mentor_id,
+        session.scheduled_at,
+        duration_minutes,
+        None,
+    )
+    .await?;
+    if !conflicts.is_empty() {
+        tx.rollback().await?;
+        return Ok(SessionBooking::Conflicts(conflicts));
+    }
+
     let result = sqlx::query!(
         r#"
         INSERT INTO sessions (user_id, mentor_id, title, description, scheduled_at, duration_minutes, meeting_link)
-        VALUES (?, ?, ?, ?, ?, COALESCE(?, 60), ?)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         "#,
         session.user_id,
         session.mentor_id,
         session.title,
         session.description,
         session.scheduled_at,
-        session.duration_minutes,
+        duration_minutes,
         session.meeting_link
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await?;
 
-    Ok(result.last_insert_id())
+    tx.commit().await?;
+
+    Ok(SessionBooking::Created(result.last_insert_id()))
+}
+
+/// Insert a series and one session per occurrence, unless any occurrence overlaps
+/// an active session of the mentor or mentee. `Created` holds the first session's ID.
+pub async fn create_session_series(
+    pool: &DbPool,
+    series: &crate::models::CreateSessionSeries,
+    session: &crate::models::CreateSession,
+    occurrences: &[chrono::NaiveDateTime],
+) -> Result<SessionBooking, Box<dyn std::error::Error>> {
+    let duration_minutes = session.duration_minutes.unwrap_or(DEFAULT_DURATION_MINUTES);
+    let mut tx = pool.begin().await?;
+
+    lock_participants(&mut tx, session.user_id, session.mentor_id).await?;
+
+    let mut conflicts = Vec::new();
+    for scheduled_at in occurrences {
+        conflicts.extend(
+            find_conflicting_sessions(&mut tx, session.user_id, session.mentor_id, *scheduled_at, duration_minutes, None).await?,
+        );
+    }
+    if !conflicts.is_empty() {
+        conflicts.sort_unstable();
+        conflicts.dedup();
+        tx.rollback().await?;
+        return Ok(SessionBooking::Conflicts(conflicts));
+    }
+
+    let series_id = sqlx::query!(
+        r#"
+        INSERT INTO session_series (user_id, mentor_id, recurrence_rule, time_zone)
+        VALUES (?, ?, ?, ?)
+        "#,
+        session.user_id,
+        session.mentor_id,
+        series.recurrence_rule,
+        series.time_zone
+    )
+    .execute(&mut *tx)
+    .await?
+    .last_insert_id();
+
+    let mut first_session_id = None;
+    for scheduled_at in occurrences {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO sessions (user_id, mentor_id, title, description, scheduled_at, duration_minutes, meeting_link, series_id, original_scheduled_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            session.user_id,
+            session.mentor_id,
+            session.title,
+            session.description,
+            scheduled_at,
+            duration_minutes,
+            session.meeting_link,
+            series_id,
+            scheduled_at
+        )
+        .execute(&mut *tx)
+        .await?;
+        first_session_id.get_or_insert(result.last_insert_id());
+    }
+
+    tx.commit().await?;
+
+    Ok(SessionBooking::Created(first_session_id.ok_or("A series needs at least one occurrence")?))
+}
+
+pub async fn get_session_series(pool: &DbPool, series_id: u64) -> Result<Option<crate::models::SessionSeries>, Box<dyn std::error::Error>> {
+    let series = sqlx::query_as!(
+        crate::models::SessionSeries,
+        "SELECT id, user_id, mentor_id, recurrence_rule, time_zone, created_at FROM session_series WHERE id = ?",
+        series_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(series)
+}
+
+/// Occurrences of a series whose rule-given start is at or after `from`, in series order
+pub async fn get_series_sessions_from(pool: &DbPool, series_id: u64, from: chrono::NaiveDateTime) -> Result<Vec<crate::models::Session>, Box<dyn std::error::Error>> {
+    let sessions = sqlx::query_as!(
+        crate::models::Session,
+        r#"SELECT id, user_id, mentor_id, title, description, scheduled_at, duration_minutes, status, meeting_link, cancelled_by, cancellation_reason, series_id, original_scheduled_at, sequence, created_at, updated_at
+        FROM sessions WHERE series_id = ? AND original_scheduled_at >= ? ORDER BY original_scheduled_at"#,
+        series_id,
+        from
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+pub async fn get_session_by_id(pool: &DbPool, session_id: u64) -> Result<Option<crate::models::Session>, Box<dyn std::error::Error>> {
+    let session = sqlx::query_as!(
+        crate::models::Session,
+        r#"SELECT id, user_id, mentor_id, title, description, scheduled_at, duration_minutes, status, meeting_link, cancelled_by, cancellation_reason, series_id, original_scheduled_at, sequence, created_at, updated_at 
+        FROM sessions WHERE id = ?"#,
+        session_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(session)
 }
 
 pub async fn get_sessions_by_user(pool: &DbPool, user_id: u64) -> Result<Vec<crate::models::Session>, Box<dyn std::error::Error>> {
     let sessions = sqlx::query_as!(
         crate::models::Session,
-        r#"SELECT id, user_id, mentor_id, title, description, scheduled_at, duration_minutes, status, meeting_link, created_at, updated_at 
+        r#"SELECT id, user_id, mentor_id, title, description, scheduled_at, duration_minutes, status, meeting_link, cancelled_by, cancellation_reason, series_id, original_scheduled_at, sequence, created_at, updated_at 
         FROM sessions WHERE user_id = ? OR mentor_id = ? ORDER BY scheduled_at DESC"#,
         user_id,
         user_id
@@ -113,8 +325,811 @@ pub async fn get_sessions_by_user(pool: &DbPool, user_id: u64) -> Result<Vec<cra
     Ok(sessions)
 }
 
+/// Apply status changes to one or more sessions in one transaction, recording each in `session_history`.
+/// Nothing is applied if any session is no longer in its `from_status`, or if a reschedule would overlap
+/// an active session of either participant outside the batch.
+pub async fn transition_sessions(pool: &DbPool, changes: &[crate::models::SessionStatusChange]) -> Result<TransitionOutcome, Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    // Sessions moved together, like the rest of a series, can't conflict with their own old times
+    let batch: Vec<u64> = changes.iter().map(|change| change.session_id).collect();
+    let mut conflicts = Vec::new();
+
+    for change in changes {
+        let Some(new_scheduled_at) = change.new_scheduled_at else {
+            continue;
+        };
+        let Some(session) = sqlx::query!(
+            "SELECT user_id, mentor_id, duration_minutes FROM sessions WHERE id = ?",
+            change.session_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            tx.rollback().await?;
+            return Ok(TransitionOutcome::Stale);
+        };
+
+        lock_participants(&mut tx, session.user_id, session.mentor_id).await?;
+
+        let found = find_conflicting_sessions(
+            &mut tx,
+            session.user_id,
+            session.mentor_id,
+            new_scheduled_at,
+            session.duration_minutes.unwrap_or(DEFAULT_DURATION_MINUTES),
+            Some(change.session_id),
+        )
+        .await?;
+        conflicts.extend(found.into_iter().filter(|id| !batch.contains(id)));
+    }
+    if !conflicts.is_empty() {
+        conflicts.sort_unstable();
+        conflicts.dedup();
+        tx.rollback().await?;
+        return Ok(TransitionOutcome::Conflicts(conflicts));
+    }
+
+    for change in changes {
+        let cancelled = change.to_status == "cancelled";
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET status = ?,
+                scheduled_at = COALESCE(?, scheduled_at),
+                cancelled_by = IF(?, ?, cancelled_by),
+                cancellation_reason = IF(?, ?, cancellation_reason),
+                sequence = sequence + IF(? OR ? IS NOT NULL, 1, 0)
+            WHERE id = ? AND status = ?
+            "#,
+            change.to_status,
+            change.new_scheduled_at,
+            cancelled,
+            change.changed_by,
+            cancelled,
+            change.reason,
+            cancelled,
+            change.new_scheduled_at,
+            change.session_id,
+            change.from_status
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(TransitionOutcome::Stale);
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO session_history (session_id, changed_by, from_status, to_status, reason, previous_scheduled_at, new_scheduled_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            change.session_id,
+            change.changed_by,
+            change.from_status,
+            change.to_status,
+            change.reason,
+            change.previous_scheduled_at,
+            change.new_scheduled_at
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(TransitionOutcome::Applied)
+}
+
+/// Scheduled or ongoing sessions of a mentor overlapping `[from, to)`
+pub async fn get_mentor_sessions_between(pool: &DbPool, mentor_id: u64, from: chrono::NaiveDateTime, to: chrono::NaiveDateTime) -> Result<Vec<crate::models::Session>, Box<dyn std::error::Error>> {
+    let sessions = sqlx::query_as!(
+        crate::models::Session,
+        r#"SELECT id, user_id, mentor_id, title, description, scheduled_at, duration_minutes, status, meeting_link, cancelled_by, cancellation_reason, series_id, original_scheduled_at, sequence, created_at, updated_at 
+        FROM sessions
+        WHERE mentor_id = ? AND status IN ('scheduled', 'ongoing')
+          AND scheduled_at < ?
+          AND DATE_ADD(scheduled_at, INTERVAL COALESCE(duration_minutes, ?) MINUTE) > ?
+        ORDER BY scheduled_at"#,
+        mentor_id,
+        to,
+        DEFAULT_DURATION_MINUTES,
+        from
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+/// Scheduled sessions starting in `(from, to]` whose reminder at `offset_minutes` hasn't been claimed for their current start
+pub async fn get_sessions_due_for_reminder(
+    pool: &DbPool,
+    offset_minutes: u32,
+    from: chrono::NaiveDateTime,
+    to: chrono::NaiveDateTime,
+    limit: u32,
+) -> Result<Vec<crate::models::Session>, Box<dyn std::error::Error>> {
+    let sessions = sqlx::query_as!(
+        crate::models::Session,
+        r#"SELECT id, user_id, mentor_id, title, description, scheduled_at, duration_minutes, status, meeting_link, cancelled_by, cancellation_reason, series_id, original_scheduled_at, sequence, created_at, updated_at
+        FROM sessions s
+        WHERE s.status = 'scheduled' AND s.scheduled_at > ? AND s.scheduled_at <= ?
+          AND NOT EXISTS (
+            SELECT 1 FROM session_reminders r
+            WHERE r.session_id = s.id AND r.scheduled_at = s.scheduled_at AND r.offset_minutes = ?
+          )
+        ORDER BY s.scheduled_at
+        LIMIT ?"#,
+        from,
+        to,
+        offset_minutes,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+/// Claim a reminder for this server, returning false if it was already claimed here or by another replica
+pub async fn claim_session_reminder(
+    pool: &DbPool,
+    session_id: u64,
+    scheduled_at: chrono::NaiveDateTime,
+    offset_minutes: u32,
+    skipped: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let result = sqlx::query!(
+        "INSERT INTO session_reminders (session_id, scheduled_at, offset_minutes, skipped) VALUES (?, ?, ?, ?)",
+        session_id,
+        scheduled_at,
+        offset_minutes,
+        skipped
+    )
+    .execute(pool)
+    .await;
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(e) if e.as_database_error().is_some_and(|e| e.is_unique_violation()) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Availability operations
+pub async fn get_availability_rules(pool: &DbPool, mentor_id: u64) -> Result<Vec<crate::models::AvailabilityRule>, Box<dyn std::error::Error>> {
+    let rules = sqlx::query_as!(
+        crate::models::AvailabilityRule,
+        r#"SELECT id, mentor_id, weekday, start_time, end_time FROM availability_rules
+        WHERE mentor_id = ? ORDER BY weekday, start_time"#,
+        mentor_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rules)
+}
+
+/// Date-specific availability of a mentor between two local dates, inclusive
+pub async fn get_availability_exceptions(pool: &DbPool, mentor_id: u64, from: chrono::NaiveDate, to: chrono::NaiveDate) -> Result<Vec<crate::models::AvailabilityException>, Box<dyn std::error::Error>> {
+    let exceptions = sqlx::query_as!(
+        crate::models::AvailabilityException,
+        r#"SELECT id, mentor_id, date, is_blackout as "is_blackout: bool", start_time, end_time FROM availability_exceptions
+        WHERE mentor_id = ? AND date BETWEEN ? AND ? ORDER BY date, start_time"#,
+        mentor_id,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(exceptions)
+}
+
+/// Replace a mentor's weekly availability and time zone
+pub async fn replace_weekly_availability(pool: &DbPool, mentor_id: u64, time_zone: &str, rules: &[crate::models::CreateAvailabilityRule]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!("UPDATE users SET time_zone = ? WHERE id = ?", time_zone, mentor_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!("DELETE FROM availability_rules WHERE mentor_id = ?", mentor_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for rule in rules {
+        sqlx::query!(
+            "INSERT INTO availability_rules (mentor_id, weekday, start_time, end_time) VALUES (?, ?, ?, ?)",
+            mentor_id,
+            rule.weekday,
+            rule.start_time,
+            rule.end_time
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Replace a mentor's date-specific availability for one date; an empty list reverts to the weekly windows
+pub async fn replace_date_availability(pool: &DbPool, mentor_id: u64, date: chrono::NaiveDate, exceptions: &[crate::models::CreateAvailabilityException]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!("DELETE FROM availability_exceptions WHERE mentor_id = ? AND date = ?", mentor_id, date)
+        .execute(&mut *tx)
+        .await?;
+
+    for exception in exceptions {
+        sqlx::query!(
+            "INSERT INTO availability_exceptions (mentor_id, date, is_blackout, start_time, end_time) VALUES (?, ?, ?, ?, ?)",
+            mentor_id,
+            exception.date,
+            exception.is_blackout,
+            exception.start_time,
+            exception.end_time
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Whether the two users are mentor and mentee on at least one session
+pub async fn users_share_session(pool: &DbPool, user_a: u64, user_b: u64) -> Result<bool, Box<dyn std::error::Error>> {
+    let shared = sqlx::query_scalar!(
+        r#"SELECT EXISTS(
+            SELECT 1 FROM sessions
+            WHERE (user_id = ? AND mentor_id = ?) OR (user_id = ? AND mentor_id = ?)
+        ) as "shared: bool""#,
+        user_a,
+        user_b,
+        user_b,
+        user_a
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(shared)
+}
+
+// Mentor profile operations
+
+/// Outcome of writing a mentor profile
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProfileWrite {
+    Saved,
+    /// Create found an existing profile
+    AlreadyExists,
+    /// Update found no profile
+    Missing,
+}
+
+/// Insert the profile when `create` is set, otherwise update the existing one.
+/// The mentor's `users` row is locked so concurrent writes to one profile are serialized.
+pub async fn save_mentor_profile(pool: &DbPool, profile: &crate::models::CreateMentorProfile, create: bool) -> Result<ProfileWrite, Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!("SELECT id FROM users WHERE id = ? FOR UPDATE", profile.user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM mentor_profiles WHERE user_id = ?) as "exists: bool""#,
+        profile.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    match (create, exists) {
+        (true, true) => {
+            tx.rollback().await?;
+            return Ok(ProfileWrite::AlreadyExists);
+        }
+        (false, false) => {
+            tx.rollback().await?;
+            return Ok(ProfileWrite::Missing);
+        }
+        (true, false) => {
+            sqlx::query!(
+                r#"
+                INSERT INTO mentor_profiles (user_id, headline, bio, years_experience, hourly_rate_cents, currency)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+                profile.user_id,
+                profile.headline,
+                profile.bio,
+                profile.years_experience,
+                profile.hourly_rate_cents,
+                profile.currency
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        (false, true) => {
+            sqlx::query!(
+                r#"
+                UPDATE mentor_profiles
+                SET headline = ?, bio = ?, years_experience = ?, hourly_rate_cents = ?, currency = ?
+                WHERE user_id = ?
+                "#,
+                profile.headline,
+                profile.bio,
+                profile.years_experience,
+                profile.hourly_rate_cents,
+                profile.currency,
+                profile.user_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    sqlx::query!("DELETE FROM mentor_languages WHERE mentor_id = ?", profile.user_id)
+        .execute(&mut *tx)
+        .await?;
+    for language in &profile.languages {
+        sqlx::query!(
+            "INSERT INTO mentor_languages (mentor_id, language) VALUES (?, ?)",
+            profile.user_id,
+            language
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query!("DELETE FROM mentor_skills WHERE mentor_id = ?", profile.user_id)
+        .execute(&mut *tx)
+        .await?;
+    for skill in &profile.skills {
+        // LAST_INSERT_ID(id) makes an existing skill report its ID as if inserted
+        let skill_id = sqlx::query!(
+            "INSERT INTO skills (name) VALUES (?) ON DUPLICATE KEY UPDATE id = LAST_INSERT_ID(id)",
+            skill
+        )
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id();
+
+        sqlx::query!(
+            "INSERT INTO mentor_skills (mentor_id, skill_id) VALUES (?, ?)",
+            profile.user_id,
+            skill_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(ProfileWrite::Saved)
+}
+
+pub async fn get_mentor_profile(pool: &DbPool, user_id: u64) -> Result<Option<crate::models::MentorProfile>, Box<dyn std::error::Error>> {
+    let profile = sqlx::query_as!(
+        crate::models::MentorProfile,
+        r#"SELECT user_id, headline, bio, years_experience, hourly_rate_cents, currency, verification_status, created_at, updated_at
+        FROM mentor_profiles WHERE user_id = ?"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(profile)
+}
+
+pub async fn get_mentor_languages(pool: &DbPool, user_id: u64) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let languages = sqlx::query_scalar!(
+        "SELECT language FROM mentor_languages WHERE mentor_id = ? ORDER BY language",
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(languages)
+}
+
+pub async fn get_mentor_skills(pool: &DbPool, user_id: u64) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let skills = sqlx::query_scalar!(
+        r#"SELECT s.name FROM mentor_skills ms JOIN skills s ON s.id = ms.skill_id
+        WHERE ms.mentor_id = ? ORDER BY s.name"#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(skills)
+}
+
+/// IDs of mentors with a profile, i.e. everyone who belongs in mentor search
+pub async fn get_mentor_ids(pool: &DbPool) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+    let ids = sqlx::query_scalar!(
+        r#"SELECT u.id FROM users u JOIN mentor_profiles mp ON mp.user_id = u.id
+        WHERE u.role = 'mentor' ORDER BY u.id"#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Delete a profile with its languages and skills, returning whether it existed
+pub async fn delete_mentor_profile(pool: &DbPool, user_id: u64) -> Result<bool, Box<dyn std::error::Error>> {
+    let result = sqlx::query!("DELETE FROM mentor_profiles WHERE user_id = ?", user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Set a profile's verification status, returning whether the profile exists
+pub async fn set_mentor_verification(pool: &DbPool, user_id: u64, status: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let result = sqlx::query!(
+        "UPDATE mentor_profiles SET verification_status = ? WHERE user_id = ?",
+        status,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Mentor application operations
+pub async fn create_mentor_application(pool: &DbPool, application: &crate::models::CreateMentorApplication) -> Result<u64, Box<dyn std::error::Error>> {
+    let result = sqlx::query!(
+        "INSERT INTO mentor_applications (user_id, motivation) VALUES (?, ?)",
+        application.user_id,
+        application.motivation
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_id())
+}
+
+pub async fn get_mentor_application(pool: &DbPool, application_id: u64) -> Result<Option<crate::models::MentorApplication>, Box<dyn std::error::Error>> {
+    let application = sqlx::query_as!(
+        crate::models::MentorApplication,
+        r#"SELECT id, user_id, motivation, status, reviewed_by, review_note, created_at, reviewed_at
+        FROM mentor_applications WHERE id = ?"#,
+        application_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(application)
+}
+
+pub async fn get_pending_mentor_application(pool: &DbPool, user_id: u64) -> Result<Option<crate::models::MentorApplication>, Box<dyn std::error::Error>> {
+    let application = sqlx::query_as!(
+        crate::models::MentorApplication,
+        r#"SELECT id, user_id, motivation, status, reviewed_by, review_note, created_at, reviewed_at
+        FROM mentor_applications WHERE user_id = ? AND status = 'pending'"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(application)
+}
+
+/// Applications with the given status, oldest first
+pub async fn get_mentor_applications_by_status(pool: &DbPool, status: &str) -> Result<Vec<crate::models::MentorApplication>, Box<dyn std::error::Error>> {
+    let applications = sqlx::query_as!(
+        crate::models::MentorApplication,
+        r#"SELECT id, user_id, motivation, status, reviewed_by, review_note, created_at, reviewed_at
+        FROM mentor_applications WHERE status = ? ORDER BY created_at, id"#,
+        status
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(applications)
+}
+
+/// Approve or reject a pending application; approval promotes a `user` to `mentor`.
+/// Returns false when the application was no longer pending.
+pub async fn review_mentor_application(pool: &DbPool, application_id: u64, reviewer_id: u64, approve: bool, note: Option<&str>) -> Result<bool, Box<dyn std::error::Error>> {
+    let status = if approve { "approved" } else { "rejected" };
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE mentor_applications
+        SET status = ?, reviewed_by = ?, review_note = ?, reviewed_at = CURRENT_TIMESTAMP
+        WHERE id = ? AND status = 'pending'
+        "#,
+        status,
+        reviewer_id,
+        note,
+        application_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+
+    if approve {
+        sqlx::query!(
+            r#"
+            UPDATE users SET role = 'mentor'
+            WHERE id = (SELECT user_id FROM mentor_applications WHERE id = ?) AND role = 'user'
+            "#,
+            application_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+// Session review operations
+
+/// Add (`delta` 1) or remove (`delta` -1) one rating from a mentor's aggregates
+async fn adjust_mentor_rating(tx: &mut sqlx::Transaction<'_, MySql>, mentor_id: u64, rating: u8, delta: i32) -> Result<(), Box<dyn std::error::Error>> {
+    sqlx::query!("INSERT IGNORE INTO mentor_ratings (mentor_id) VALUES (?)", mentor_id)
+        .execute(&mut **tx)
+        .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE mentor_ratings
+        SET review_count = review_count + ?,
+            rating_sum = rating_sum + ? * ?,
+            rating_1_count = rating_1_count + IF(? = 1, ?, 0),
+            rating_2_count = rating_2_count + IF(? = 2, ?, 0),
+            rating_3_count = rating_3_count + IF(? = 3, ?, 0),
+            rating_4_count = rating_4_count + IF(? = 4, ?, 0),
+            rating_5_count = rating_5_count + IF(? = 5, ?, 0)
+        WHERE mentor_id = ?
+        "#,
+        delta,
+        delta,
+        rating,
+        rating,
+        delta,
+        rating,
+        delta,
+        rating,
+        delta,
+        rating,
+        delta,
+        rating,
+        delta,
+        mentor_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Whether a review is part of its reviewee's rating aggregates
+fn counts_towards_rating(reviewer_role: &str, is_hidden: bool) -> bool {
+    reviewer_role == "mentee" && !is_hidden
+}
+
+/// Insert a review and update the mentor's aggregates; `None` if the reviewer already reviewed the session
+pub async fn create_review(pool: &DbPool, review: &crate::models::CreateSessionReview) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO session_reviews (session_id, reviewer_id, reviewee_id, reviewer_role, rating, comment)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        review.session_id,
+        review.reviewer_id,
+        review.reviewee_id,
+        review.reviewer_role,
+        review.rating,
+        review.comment
+    )
+    .execute(&mut *tx)
+    .await;
+
+    let review_id = match inserted {
+        Ok(result) => result.last_insert_id(),
+        Err(e) if e.as_database_error().is_some_and(|e| e.is_unique_violation()) => {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if counts_towards_rating(&review.reviewer_role, false) {
+        adjust_mentor_rating(&mut tx, review.reviewee_id, review.rating, 1).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(Some(review_id))
+}
+
+pub async fn get_review(pool: &DbPool, review_id: u64) -> Result<Option<crate::models::SessionReview>, Box<dyn std::error::Error>> {
+    let review = sqlx::query_as!(
+        crate::models::SessionReview,
+        r#"SELECT id, session_id, reviewer_id, reviewee_id, reviewer_role, rating, comment, is_hidden as "is_hidden: bool",
+            hidden_by, hidden_reason, created_at, updated_at
+        FROM session_reviews WHERE id = ?"#,
+        review_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(review)
+}
+
+/// The row of a review locked for update, with what the aggregates need
+async fn lock_review(tx: &mut sqlx::Transaction<'_, MySql>, review_id: u64) -> Result<Option<(u64, String, u8, bool)>, Box<dyn std::error::Error>> {
+    let row = sqlx::query!(
+        r#"SELECT reviewee_id, reviewer_role, rating, is_hidden as "is_hidden: bool"
+        FROM session_reviews WHERE id = ? FOR UPDATE"#,
+        review_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    Ok(row.map(|row| (row.reviewee_id, row.reviewer_role, row.rating, row.is_hidden)))
+}
+
+/// Change a review's rating and comment, moving the mentor's aggregates with it; false if it doesn't exist
+pub async fn update_review(pool: &DbPool, review_id: u64, rating: u8, comment: Option<&str>) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    let Some((reviewee_id, reviewer_role, old_rating, is_hidden)) = lock_review(&mut tx, review_id).await? else {
+        tx.rollback().await?;
+        return Ok(false);
+    };
+
+    sqlx::query!(
+        "UPDATE session_reviews SET rating = ?, comment = ? WHERE id = ?",
+        rating,
+        comment,
+        review_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if counts_towards_rating(&reviewer_role, is_hidden) && old_rating != rating {
+        adjust_mentor_rating(&mut tx, reviewee_id, old_rating, -1).await?;
+        adjust_mentor_rating(&mut tx, reviewee_id, rating, 1).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Hide or show a review, taking it out of or back into the mentor's aggregates; false if it doesn't exist
+pub async fn set_review_hidden(pool: &DbPool, review_id: u64, hidden: bool, hidden_by: u64, reason: Option<&str>) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    let Some((reviewee_id, reviewer_role, rating, was_hidden)) = lock_review(&mut tx, review_id).await? else {
+        tx.rollback().await?;
+        return Ok(false);
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE session_reviews
+        SET is_hidden = ?, hidden_by = IF(?, ?, NULL), hidden_reason = IF(?, ?, NULL)
+        WHERE id = ?
+        "#,
+        hidden,
+        hidden,
+        hidden_by,
+        hidden,
+        reason,
+        review_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if was_hidden != hidden && reviewer_role == "mentee" {
+        let delta = if hidden { -1 } else { 1 };
+        adjust_mentor_rating(&mut tx, reviewee_id, rating, delta).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Mentee reviews of a mentor, newest first, with IDs below `before_id`
+pub async fn get_mentor_reviews(pool: &DbPool, mentor_id: u64, include_hidden: bool, before_id: u64, limit: u32) -> Result<Vec<crate::models::SessionReview>, Box<dyn std::error::Error>> {
+    let reviews = sqlx::query_as!(
+        crate::models::SessionReview,
+        r#"SELECT id, session_id, reviewer_id, reviewee_id, reviewer_role, rating, comment, is_hidden as "is_hidden: bool",
+            hidden_by, hidden_reason, created_at, updated_at
+        FROM session_reviews
+        WHERE reviewee_id = ? AND reviewer_role = 'mentee' AND (? OR NOT is_hidden) AND id < ?
+        ORDER BY id DESC LIMIT ?"#,
+        mentor_id,
+        include_hidden,
+        before_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(reviews)
+}
+
+pub async fn get_mentor_rating(pool: &DbPool, mentor_id: u64) -> Result<Option<crate::models::MentorRating>, Box<dyn std::error::Error>> {
+    let rating = sqlx::query_as!(
+        crate::models::MentorRating,
+        r#"SELECT mentor_id, review_count, rating_sum, rating_1_count, rating_2_count, rating_3_count, rating_4_count, rating_5_count
+        FROM mentor_ratings WHERE mentor_id = ?"#,
+        mentor_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(rating)
+}
+
+// Calendar feed operations
+
+/// Set the user's feed token, replacing any previous one
+pub async fn replace_calendar_feed(pool: &DbPool, user_id: u64, token_hash: &str) -> Result<(), Box<dyn std::error::Error>> {
+    sqlx::query!(
+        r#"
+        INSERT INTO calendar_feeds (user_id, token_hash) VALUES (?, ?)
+        ON DUPLICATE KEY UPDATE token_hash = VALUES(token_hash), created_at = CURRENT_TIMESTAMP
+        "#,
+        user_id,
+        token_hash
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove the user's feed token, returning whether they had one
+pub async fn delete_calendar_feed(pool: &DbPool, user_id: u64) -> Result<bool, Box<dyn std::error::Error>> {
+    let result = sqlx::query!("DELETE FROM calendar_feeds WHERE user_id = ?", user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// The user whose feed token hashes to `token_hash`
+pub async fn get_calendar_feed_user(pool: &DbPool, token_hash: &str) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    let user_id = sqlx::query_scalar!("SELECT user_id FROM calendar_feeds WHERE token_hash = ?", token_hash)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(user_id)
+}
+
 // Notification CRUD operations
-pub async fn create_notification(pool: &DbPool, notification: &crate::models::CreateNotification) -> Result<u64, Box<dyn std::error::Error>> {
+/// Insert a notification together with a pending push delivery for each of the recipient's devices,
+/// due at `push_at`. `None` stores it for the inbox only.
+pub async fn create_notification(
+    pool: &DbPool,
+    notification: &crate::models::CreateNotification,
+    push_at: Option<chrono::NaiveDateTime>,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
     let result = sqlx::query!(
         r#"
         INSERT INTO notifications (user_id, title, body, notification_type, data)
@@ -126,10 +1141,114 @@ pub async fn create_notification(pool: &DbPool, notification: &crate::models::Cr
         notification.notification_type,
         notification.data
     )
+    .execute(&mut *tx)
+    .await?;
+    let notification_id = result.last_insert_id();
+
+    if let Some(push_at) = push_at {
+        sqlx::query!(
+            r#"
+            INSERT INTO notification_deliveries (notification_id, device_token, device_type, provider, next_attempt_at)
+            SELECT ?, token, device_type, provider, ? FROM device_tokens WHERE user_id = ?
+            "#,
+            notification_id,
+            push_at,
+            notification.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(notification_id)
+}
+
+/// Claim up to `limit` pending deliveries due at `now`, hiding them from other workers until
+/// `lease_until` so a worker that dies mid-batch only delays them
+pub async fn claim_due_deliveries(
+    pool: &DbPool,
+    now: chrono::NaiveDateTime,
+    lease_until: chrono::NaiveDateTime,
+    limit: u32,
+) -> Result<Vec<crate::models::PendingDelivery>, Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    let deliveries = sqlx::query_as!(
+        crate::models::PendingDelivery,
+        r#"
+        SELECT d.id, d.notification_id, d.device_token, d.provider,
+            t.web_push_p256dh as "web_push_p256dh?", t.web_push_auth as "web_push_auth?",
+            n.title, n.body, n.notification_type, n.data, d.attempts
+        FROM notification_deliveries d
+        JOIN notifications n ON n.id = d.notification_id
+        LEFT JOIN device_tokens t ON t.token = d.device_token
+        WHERE d.status = 'pending' AND d.next_attempt_at <= ?
+        ORDER BY d.next_attempt_at
+        LIMIT ?
+        FOR UPDATE OF d SKIP LOCKED
+        "#,
+        now,
+        limit
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for delivery in &deliveries {
+        sqlx::query!(
+            "UPDATE notification_deliveries SET next_attempt_at = ? WHERE id = ?",
+            lease_until,
+            delivery.id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(deliveries)
+}
+
+/// Record the outcome of one attempt. `next_attempt_at` is only set when the delivery stays pending
+pub async fn record_delivery_attempt(
+    pool: &DbPool,
+    delivery_id: u64,
+    status: &str,
+    next_attempt_at: Option<chrono::NaiveDateTime>,
+    last_error: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    sqlx::query!(
+        r#"
+        UPDATE notification_deliveries
+        SET status = ?,
+            attempts = attempts + 1,
+            next_attempt_at = COALESCE(?, next_attempt_at),
+            last_error = ?,
+            sent_at = IF(? = 'sent', CURRENT_TIMESTAMP, sent_at)
+        WHERE id = ?
+        "#,
+        status,
+        next_attempt_at,
+        last_error,
+        status,
+        delivery_id
+    )
     .execute(pool)
     .await?;
 
-    Ok(result.last_insert_id())
+    Ok(())
+}
+
+pub async fn get_notification_deliveries(pool: &DbPool, notification_id: u64) -> Result<Vec<crate::models::NotificationDelivery>, Box<dyn std::error::Error>> {
+    let deliveries = sqlx::query_as!(
+        crate::models::NotificationDelivery,
+        r#"SELECT id, notification_id, device_type, provider, status, attempts, next_attempt_at,
+        last_error, sent_at, created_at, updated_at
+        FROM notification_deliveries WHERE notification_id = ? ORDER BY id"#,
+        notification_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deliveries)
 }
 
 pub async fn get_unread_notifications(pool: &DbPool, user_id: u64) -> Result<Vec<crate::models::Notification>, Box<dyn std::error::Error>> {
@@ -145,6 +1264,35 @@ pub async fn get_unread_notifications(pool: &DbPool, user_id: u64) -> Result<Vec
     Ok(notifications)
 }
 
+pub async fn get_notification_by_id(pool: &DbPool, notification_id: u64) -> Result<Option<crate::models::Notification>, Box<dyn std::error::Error>> {
+    let notification = sqlx::query_as!(
+        crate::models::Notification,
+        r#"SELECT id, user_id, title, body, notification_type, data, is_read as "is_read: bool", created_at 
+        FROM notifications WHERE id = ?"#,
+        notification_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(notification)
+}
+
+/// Notifications for `user_id` with an ID greater than `after_id`, oldest first
+pub async fn get_notifications_after(pool: &DbPool, user_id: u64, after_id: u64, limit: u32) -> Result<Vec<crate::models::Notification>, Box<dyn std::error::Error>> {
+    let notifications = sqlx::query_as!(
+        crate::models::Notification,
+        r#"SELECT id, user_id, title, body, notification_type, data, is_read as "is_read: bool", created_at 
+        FROM notifications WHERE user_id = ? AND id > ? ORDER BY id ASC LIMIT ?"#,
+        user_id,
+        after_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(notifications)
+}
+
 pub async fn mark_notification_read(pool: &DbPool, notification_id: u64) -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query!(
         r#"UPDATE notifications SET is_read = TRUE WHERE id = ?"#,
@@ -156,17 +1304,224 @@ pub async fn mark_notification_read(pool: &DbPool, notification_id: u64) -> Resu
     Ok(())
 }
 
+/// The user's notifications matching `filter` with an ID below `before_id`, newest first
+pub async fn list_notifications(
+    pool: &DbPool,
+    user_id: u64,
+    filter: &crate::models::NotificationFilter,
+    before_id: u64,
+    limit: u32,
+) -> Result<Vec<crate::models::Notification>, Box<dyn std::error::Error>> {
+    let notifications = sqlx::query_as!(
+        crate::models::Notification,
+        r#"SELECT id, user_id, title, body, notification_type, data, is_read as "is_read: bool", created_at
+        FROM notifications
+        WHERE user_id = ? AND id < ?
+          AND (? IS NULL OR is_read = ?)
+          AND (? IS NULL OR notification_type = ?)
+          AND (? IS NULL OR created_at >= ?)
+          AND (? IS NULL OR created_at < ?)
+        ORDER BY id DESC LIMIT ?"#,
+        user_id,
+        before_id,
+        filter.is_read,
+        filter.is_read,
+        filter.notification_type,
+        filter.notification_type,
+        filter.created_from,
+        filter.created_from,
+        filter.created_to,
+        filter.created_to,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(notifications)
+}
+
+pub async fn count_unread_notifications(pool: &DbPool, user_id: u64) -> Result<u64, Box<dyn std::error::Error>> {
+    let count = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = ? AND is_read = FALSE",
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count as u64)
+}
+
+/// Mark the given notifications of the user as read, ignoring IDs that aren't theirs.
+/// Returns how many were unread.
+pub async fn mark_notifications_read(pool: &DbPool, user_id: u64, notification_ids: &[u64]) -> Result<u64, Box<dyn std::error::Error>> {
+    if notification_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let mut query = sqlx::QueryBuilder::<sqlx::MySql>::new("UPDATE notifications SET is_read = TRUE WHERE user_id = ");
+    query.push_bind(user_id);
+    query.push(" AND is_read = FALSE AND id IN (");
+    let mut ids = query.separated(", ");
+    for notification_id in notification_ids {
+        ids.push_bind(*notification_id);
+    }
+    ids.push_unseparated(")");
+
+    let result = query.build().execute(pool).await?;
+    Ok(result.rows_affected())
+}
+
+/// Returns how many were unread
+pub async fn mark_all_notifications_read(pool: &DbPool, user_id: u64) -> Result<u64, Box<dyn std::error::Error>> {
+    let result = sqlx::query!(
+        "UPDATE notifications SET is_read = TRUE WHERE user_id = ? AND is_read = FALSE",
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete a notification along with its deliveries, returning whether it existed
+pub async fn delete_notification(pool: &DbPool, notification_id: u64) -> Result<bool, Box<dyn std::error::Error>> {
+    let result = sqlx::query!("DELETE FROM notifications WHERE id = ?", notification_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete up to `limit` read notifications created before `cutoff`, returning how many were deleted
+pub async fn delete_read_notifications(pool: &DbPool, cutoff: chrono::NaiveDateTime, limit: u32) -> Result<u64, Box<dyn std::error::Error>> {
+    let result = sqlx::query!(
+        "DELETE FROM notifications WHERE is_read = TRUE AND created_at < ? LIMIT ?",
+        cutoff,
+        limit
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Notification preferences
+
+/// The recipient's channel for `notification_type` and quiet hours; `None` if there is no such user
+pub async fn get_push_preferences(
+    pool: &DbPool,
+    user_id: u64,
+    notification_type: &str,
+) -> Result<Option<crate::models::PushPreferences>, Box<dyn std::error::Error>> {
+    let preferences = sqlx::query_as!(
+        crate::models::PushPreferences,
+        r#"
+        SELECT u.time_zone, p.channel as "channel?", s.quiet_hours_start as "quiet_hours_start?",
+            s.quiet_hours_end as "quiet_hours_end?", s.calls_bypass_quiet_hours as "calls_bypass_quiet_hours?: bool"
+        FROM users u
+        LEFT JOIN notification_preferences p ON p.user_id = u.id AND p.notification_type = ?
+        LEFT JOIN notification_settings s ON s.user_id = u.id
+        WHERE u.id = ?
+        "#,
+        notification_type,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(preferences)
+}
+
+/// Channels the user chose; types without a row use the default
+pub async fn get_notification_channels(pool: &DbPool, user_id: u64) -> Result<Vec<crate::models::NotificationChannel>, Box<dyn std::error::Error>> {
+    let channels = sqlx::query_as!(
+        crate::models::NotificationChannel,
+        "SELECT notification_type, channel FROM notification_preferences WHERE user_id = ?",
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(channels)
+}
+
+pub async fn get_notification_settings(pool: &DbPool, user_id: u64) -> Result<Option<crate::models::NotificationSettings>, Box<dyn std::error::Error>> {
+    let settings = sqlx::query_as!(
+        crate::models::NotificationSettings,
+        r#"SELECT user_id, quiet_hours_start, quiet_hours_end, calls_bypass_quiet_hours as "calls_bypass_quiet_hours: bool"
+        FROM notification_settings WHERE user_id = ?"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(settings)
+}
+
+/// Replace the user's quiet hours and all their per-type channels in one transaction
+pub async fn replace_notification_preferences(
+    pool: &DbPool,
+    settings: &crate::models::NotificationSettings,
+    channels: &[crate::models::NotificationChannel],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO notification_settings (user_id, quiet_hours_start, quiet_hours_end, calls_bypass_quiet_hours)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            quiet_hours_start = VALUES(quiet_hours_start),
+            quiet_hours_end = VALUES(quiet_hours_end),
+            calls_bypass_quiet_hours = VALUES(calls_bypass_quiet_hours)
+        "#,
+        settings.user_id,
+        settings.quiet_hours_start,
+        settings.quiet_hours_end,
+        settings.calls_bypass_quiet_hours
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!("DELETE FROM notification_preferences WHERE user_id = ?", settings.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for channel in channels {
+        sqlx::query!(
+            "INSERT INTO notification_preferences (user_id, notification_type, channel) VALUES (?, ?, ?)",
+            settings.user_id,
+            channel.notification_type,
+            channel.channel
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
 // Device Token CRUD operations
 pub async fn upsert_device_token(pool: &DbPool, token: &crate::models::CreateDeviceToken) -> Result<(), Box<dyn std::error::Error>> {
     sqlx::query!(
         r#"
-        INSERT INTO device_tokens (user_id, token, device_type)
-        VALUES (?, ?, ?)
-        ON DUPLICATE KEY UPDATE updated_at = CURRENT_TIMESTAMP
+        INSERT INTO device_tokens (user_id, token, device_type, provider, web_push_p256dh, web_push_auth)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            user_id = VALUES(user_id),
+            device_type = VALUES(device_type),
+            provider = VALUES(provider),
+            web_push_p256dh = VALUES(web_push_p256dh),
+            web_push_auth = VALUES(web_push_auth),
+            updated_at = CURRENT_TIMESTAMP
         "#,
         token.user_id,
         token.token,
-        token.device_type
+        token.device_type,
+        token.provider,
+        token.web_push_p256dh,
+        token.web_push_auth
     )
     .execute(pool)
     .await?;
@@ -174,9 +1529,11 @@ pub async fn upsert_device_token(pool: &DbPool, token: &crate::models::CreateDev
     Ok(())
 }
 
-pub async fn get_user_device_tokens(pool: &DbPool, user_id: u64) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let tokens = sqlx::query_scalar!(
-        r#"SELECT token FROM device_tokens WHERE user_id = ?"#,
+pub async fn get_device_tokens_by_user(pool: &DbPool, user_id: u64) -> Result<Vec<crate::models::DeviceToken>, Box<dyn std::error::Error>> {
+    let tokens = sqlx::query_as!(
+        crate::models::DeviceToken,
+        r#"SELECT id, user_id, token, device_type, provider, web_push_p256dh, web_push_auth, created_at, updated_at
+        FROM device_tokens WHERE user_id = ? ORDER BY id"#,
         user_id
     )
     .fetch_all(pool)
@@ -184,3 +1541,51 @@ pub async fn get_user_device_tokens(pool: &DbPool, user_id: u64) -> Result<Vec<S
 
     Ok(tokens)
 }
+
+/// Tokens subscribed to no role topic yet, or to another role's than their owner has now
+pub async fn get_unsynced_device_tokens(pool: &DbPool, limit: u32) -> Result<Vec<crate::models::TopicSubscription>, Box<dyn std::error::Error>> {
+    let tokens = sqlx::query_as!(
+        crate::models::TopicSubscription,
+        r#"
+        SELECT d.id, d.token, u.role, d.topic_role
+        FROM device_tokens d
+        JOIN users u ON u.id = d.user_id
+        WHERE d.provider = 'fcm' AND (d.topic_role IS NULL OR d.topic_role <> u.role)
+        ORDER BY d.id
+        LIMIT ?
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tokens)
+}
+
+/// Record the role topic a token was subscribed to
+pub async fn set_device_token_topic_role(pool: &DbPool, id: u64, role: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Keep updated_at, which tracks when the app last registered the token
+    sqlx::query!("UPDATE device_tokens SET topic_role = ?, updated_at = updated_at WHERE id = ?", role, id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Remove a token FCM reported as permanently invalid
+pub async fn delete_device_token(pool: &DbPool, token: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let result = sqlx::query!("DELETE FROM device_tokens WHERE token = ?", token)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove tokens not registered again since `cutoff`, returning how many were removed
+pub async fn delete_stale_device_tokens(pool: &DbPool, cutoff: chrono::NaiveDateTime) -> Result<u64, Box<dyn std::error::Error>> {
+    let result = sqlx::query!("DELETE FROM device_tokens WHERE updated_at < ?", cutoff)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -1,21 +1,339 @@
-use tonic::{transport::Server, Request, Response, Status};
+use tonic::{server::NamedService, transport::Server, Request, Response, Status};
+use std::pin::Pin;
 use std::sync::Arc;
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use crate::auth::CallerContext;
 use crate::AppState;
 
 pub mod pb {
     tonic::include_proto!("service");
+
+    /// Encoded descriptors for `service.proto`, served by gRPC reflection
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("service_descriptor");
 }
 
 use pb::link_with_mentor_server::{LinkWithMentor, LinkWithMentorServer};
 use pb::*;
 
-#[derive(Debug)]
 pub struct MyLinkWithMentor {
     state: Arc<AppState>,
 }
 
+/// Caller identity attached by `auth::AuthLayer`
+fn caller<T>(request: &Request<T>) -> Result<CallerContext, Status> {
+    request
+        .extensions()
+        .get::<CallerContext>()
+        .cloned()
+        .ok_or_else(|| Status::unauthenticated("Missing caller identity"))
+}
+
+impl From<crate::models::User> for UserResponse {
+    fn from(user: crate::models::User) -> Self {
+        Self {
+            id: user.id,
+            firebase_uid: user.firebase_uid,
+            email: user.email,
+            display_name: user.display_name,
+            photo_url: user.photo_url,
+            role: user.role,
+            created_at: crate::timestamp::format_utc(user.created_at),
+            time_zone: user.time_zone,
+        }
+    }
+}
+
+fn to_timestamp(at: chrono::NaiveDateTime) -> prost_types::Timestamp {
+    let at = at.and_utc();
+    prost_types::Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_nanos() as i32,
+    }
+}
+
+/// A request's instant as an RFC 3339 string, preferring the typed `Timestamp` when present
+fn scheduled_at_input(scheduled_time: Option<prost_types::Timestamp>, scheduled_at: String) -> Result<String, Status> {
+    let Some(ts) = scheduled_time else {
+        return Ok(scheduled_at);
+    };
+
+    chrono::DateTime::from_timestamp(ts.seconds, ts.nanos.try_into().unwrap_or(u32::MAX))
+        .map(|at| crate::timestamp::format_utc(at.naive_utc()))
+        .ok_or_else(|| Status::invalid_argument("scheduled_time is out of range"))
+}
+
+impl From<crate::api::SessionView> for SessionResponse {
+    fn from(view: crate::api::SessionView) -> Self {
+        let s = view.session;
+        Self {
+            id: s.id,
+            user_id: s.user_id,
+            mentor_id: s.mentor_id,
+            title: s.title,
+            description: s.description,
+            scheduled_at: crate::timestamp::format_utc(s.scheduled_at),
+            duration_minutes: s.duration_minutes,
+            status: s.status,
+            meeting_link: s.meeting_link,
+            cancelled_by: s.cancelled_by,
+            cancellation_reason: s.cancellation_reason,
+            scheduled_time: Some(to_timestamp(s.scheduled_at)),
+            scheduled_at_local: view.scheduled_at_local,
+            time_zone: view.time_zone,
+            series_id: s.series_id,
+            original_scheduled_at: s.original_scheduled_at.map(crate::timestamp::format_utc),
+        }
+    }
+}
+
+impl From<crate::models::Notification> for NotificationResponse {
+    fn from(n: crate::models::Notification) -> Self {
+        Self {
+            id: n.id,
+            user_id: n.user_id,
+            title: n.title,
+            body: n.body,
+            notification_type: n.notification_type,
+            data: n.data,
+            is_read: n.is_read,
+            created_at: crate::timestamp::format_utc(n.created_at),
+        }
+    }
+}
+
+impl From<send_notification_request::Payload> for crate::firebase::NotificationType {
+    fn from(payload: send_notification_request::Payload) -> Self {
+        use send_notification_request::Payload;
+        match payload {
+            Payload::Link(link) => Self::Link { url: link.url },
+            Payload::Image(image) => Self::Image { url: image.url },
+            Payload::Chat(chat) => Self::Chat { sender_id: chat.sender_id, chat_id: chat.chat_id },
+            Payload::Call(call) => Self::Call { caller_id: call.caller_id, call_id: call.call_id, is_video: call.is_video },
+        }
+    }
+}
+
+impl From<broadcast_notification_request::Payload> for crate::firebase::NotificationType {
+    fn from(payload: broadcast_notification_request::Payload) -> Self {
+        use broadcast_notification_request::Payload;
+        match payload {
+            Payload::Link(link) => Self::Link { url: link.url },
+            Payload::Image(image) => Self::Image { url: image.url },
+            Payload::Chat(chat) => Self::Chat { sender_id: chat.sender_id, chat_id: chat.chat_id },
+            Payload::Call(call) => Self::Call { caller_id: call.caller_id, call_id: call.call_id, is_video: call.is_video },
+        }
+    }
+}
+
+impl From<crate::broadcast::DeviceResult> for BroadcastDeviceResult {
+    fn from(result: crate::broadcast::DeviceResult) -> Self {
+        Self {
+            device_token_id: result.device_token_id,
+            user_id: result.user_id,
+            device_type: result.device_type,
+            sent: result.sent,
+            error: result.error.unwrap_or_default(),
+            token_removed: result.token_removed,
+        }
+    }
+}
+
+impl From<crate::api::NotificationPreferences> for NotificationPreferencesResponse {
+    fn from(preferences: crate::api::NotificationPreferences) -> Self {
+        Self {
+            channels: preferences
+                .channels
+                .into_iter()
+                .map(|channel| NotificationChannel {
+                    notification_type: channel.notification_type,
+                    channel: channel.channel,
+                })
+                .collect(),
+            quiet_hours_start: preferences.quiet_hours_start,
+            quiet_hours_end: preferences.quiet_hours_end,
+            calls_bypass_quiet_hours: preferences.calls_bypass_quiet_hours,
+            time_zone: preferences.time_zone,
+        }
+    }
+}
+
+impl From<crate::models::NotificationDelivery> for NotificationDelivery {
+    fn from(d: crate::models::NotificationDelivery) -> Self {
+        let pending = d.status == "pending";
+        Self {
+            id: d.id,
+            notification_id: d.notification_id,
+            device_type: d.device_type,
+            provider: d.provider,
+            status: d.status,
+            attempts: d.attempts,
+            next_attempt_at: pending.then(|| crate::timestamp::format_utc(d.next_attempt_at)),
+            last_error: d.last_error,
+            sent_at: d.sent_at.map(crate::timestamp::format_utc),
+            created_at: crate::timestamp::format_utc(d.created_at),
+        }
+    }
+}
+
+impl From<crate::api::MentorProfileDetails> for MentorProfileResponse {
+    fn from(details: crate::api::MentorProfileDetails) -> Self {
+        let profile = details.profile;
+        Self {
+            mentor_id: profile.user_id,
+            headline: profile.headline,
+            bio: profile.bio,
+            years_experience: profile.years_experience.into(),
+            languages: details.languages,
+            skills: details.skills,
+            hourly_rate_cents: profile.hourly_rate_cents,
+            currency: profile.currency,
+            verification_status: profile.verification_status,
+            updated_at: crate::timestamp::format_utc(profile.updated_at),
+        }
+    }
+}
+
+impl From<crate::models::MentorApplication> for MentorApplicationResponse {
+    fn from(application: crate::models::MentorApplication) -> Self {
+        Self {
+            id: application.id,
+            user_id: application.user_id,
+            motivation: application.motivation,
+            status: application.status,
+            reviewed_by: application.reviewed_by,
+            review_note: application.review_note,
+            created_at: crate::timestamp::format_utc(application.created_at),
+            reviewed_at: application.reviewed_at.map(crate::timestamp::format_utc),
+        }
+    }
+}
+
+impl From<crate::search::MentorDocument> for MentorSearchResult {
+    fn from(mentor: crate::search::MentorDocument) -> Self {
+        Self {
+            mentor_id: mentor.mentor_id,
+            display_name: mentor.display_name,
+            photo_url: mentor.photo_url,
+            headline: mentor.headline,
+            bio: mentor.bio,
+            skills: mentor.skills,
+            languages: mentor.languages,
+            hourly_rate_cents: mentor.hourly_rate_cents,
+            currency: mentor.currency,
+            verification_status: mentor.verification_status,
+            rating: mentor.rating,
+            review_count: mentor.review_count,
+            next_available_at: mentor.next_available_at.map(crate::timestamp::format_utc),
+        }
+    }
+}
+
+impl From<crate::models::SessionReview> for ReviewResponse {
+    fn from(review: crate::models::SessionReview) -> Self {
+        Self {
+            id: review.id,
+            session_id: review.session_id,
+            reviewer_id: review.reviewer_id,
+            reviewee_id: review.reviewee_id,
+            reviewer_role: review.reviewer_role,
+            rating: review.rating.into(),
+            comment: review.comment,
+            hidden: review.is_hidden,
+            hidden_reason: review.hidden_reason,
+            created_at: crate::timestamp::format_utc(review.created_at),
+            updated_at: crate::timestamp::format_utc(review.updated_at),
+        }
+    }
+}
+
+impl From<crate::api::MentorReviews> for MentorReviewsResponse {
+    fn from(page: crate::api::MentorReviews) -> Self {
+        Self {
+            mentor_id: page.mentor_id,
+            summary: Some(RatingSummary {
+                average: page.summary.average,
+                review_count: page.summary.review_count,
+                distribution: page.summary.distribution.to_vec(),
+            }),
+            reviews: page.reviews.into_iter().map(Into::into).collect(),
+            next_cursor: page.next_cursor.unwrap_or_default(),
+        }
+    }
+}
+
+fn mentor_profile_input(req: MentorProfileRequest) -> crate::api::MentorProfileInput {
+    crate::api::MentorProfileInput {
+        headline: req.headline,
+        bio: req.bio,
+        years_experience: req.years_experience,
+        hourly_rate_cents: req.hourly_rate_cents,
+        currency: req.currency,
+        languages: req.languages,
+        skills: req.skills,
+    }
+}
+
+impl From<crate::api::MentorAvailability> for AvailabilityResponse {
+    fn from(availability: crate::api::MentorAvailability) -> Self {
+        let time = |time: chrono::NaiveTime| time.format("%H:%M").to_string();
+
+        let weekly = availability
+            .weekly
+            .into_iter()
+            .map(|rule| WeeklyWindow {
+                weekday: rule.weekday.into(),
+                start_time: time(rule.start_time),
+                end_time: time(rule.end_time),
+            })
+            .collect();
+
+        // Exceptions arrive ordered by date; group each date's rows into one entry
+        let mut dates: Vec<DateAvailability> = Vec::new();
+        for exception in availability.dates {
+            let date = exception.date.to_string();
+            if dates.last().is_none_or(|last| last.date != date) {
+                dates.push(DateAvailability {
+                    date,
+                    blackout: false,
+                    windows: Vec::new(),
+                });
+            }
+            let entry = dates.last_mut().unwrap();
+
+            match (exception.start_time, exception.end_time) {
+                (Some(start), Some(end)) if !exception.is_blackout => entry.windows.push(TimeWindow {
+                    start_time: time(start),
+                    end_time: time(end),
+                }),
+                _ => entry.blackout = true,
+            }
+        }
+
+        Self {
+            mentor_id: availability.mentor_id,
+            time_zone: availability.time_zone,
+            weekly,
+            dates,
+        }
+    }
+}
+
+impl From<crate::api::NotificationEvent> for NotificationEvent {
+    fn from(event: crate::api::NotificationEvent) -> Self {
+        let event = match event {
+            crate::api::NotificationEvent::Notification(n) => notification_event::Event::Notification(n.into()),
+            crate::api::NotificationEvent::Heartbeat(at) => notification_event::Event::Heartbeat(Heartbeat {
+                server_time: crate::timestamp::format_utc(at),
+            }),
+        };
+        Self { event: Some(event) }
+    }
+}
+
 #[tonic::async_trait]
 impl LinkWithMentor for MyLinkWithMentor {
+    type SubscribeNotificationsStream = Pin<Box<dyn Stream<Item = Result<NotificationEvent, Status>> + Send>>;
+
     async fn ping(
         &self,
         request: Request<PingRequest>,
@@ -45,6 +363,7 @@ impl LinkWithMentor for MyLinkWithMentor {
             status: health.status,
             database: health.database,
             firebase: health.firebase,
+            firebase_error: health.firebase_error,
             uptime_seconds: health.uptime_seconds,
         }))
     }
@@ -63,6 +382,7 @@ impl LinkWithMentor for MyLinkWithMentor {
             total_users_created: snapshot.total_users_created,
             total_sessions_created: snapshot.total_sessions_created,
             total_notifications_sent: snapshot.total_notifications_sent,
+            permission_denied_requests: snapshot.permission_denied_requests,
         }))
     }
 
@@ -70,268 +390,671 @@ impl LinkWithMentor for MyLinkWithMentor {
         &self,
         request: Request<CreateUserRequest>,
     ) -> Result<Response<UserResponse>, Status> {
-        self.state.metrics.increment_requests();
-        
+        let caller = caller(&request)?;
         let req = request.into_inner();
-        
-        // Rate limiting check
-        if !self.state.rate_limiter.check_rate_limit(&req.firebase_uid) {
-            tracing::warn!("Rate limit exceeded for user: {}", req.firebase_uid);
-            self.state.metrics.increment_failed();
-            return Err(Status::resource_exhausted("Rate limit exceeded. Please try again later."));
-        }
-        
-        tracing::info!("Creating user: {}", req.email);
-        
-        let user = crate::models::CreateUser {
-            firebase_uid: req.firebase_uid,
+
+        let input = crate::api::NewUser {
             email: req.email,
             display_name: req.display_name,
             photo_url: req.photo_url,
             role: req.role,
+            time_zone: req.time_zone,
         };
 
-        let user_id = crate::db::create_user(&self.state.db, &user)
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to create user: {}", e);
-                self.state.metrics.increment_failed();
-                Status::internal(format!("Failed to create user: {}", e))
-            })?;
-
-        let created_user = crate::db::get_user_by_id(&self.state.db, user_id)
-            .await
-            .map_err(|e| {
-                self.state.metrics.increment_failed();
-                Status::internal(format!("Failed to get user: {}", e))
-            })?
-            .ok_or_else(|| {
-                self.state.metrics.increment_failed();
-                Status::not_found("User not found after creation")
-            })?;
-
-        tracing::info!("User created successfully: ID {}", created_user.id);
-        self.state.metrics.increment_successful();
-        self.state.metrics.increment_users_created();
-
-        Ok(Response::new(UserResponse {
-            id: created_user.id,
-            firebase_uid: created_user.firebase_uid,
-            email: created_user.email,
-            display_name: created_user.display_name,
-            photo_url: created_user.photo_url,
-            role: created_user.role,
-            created_at: created_user.created_at.to_string(),
-        }))
+        let user = crate::api::create_user(&self.state, &caller, input).await?;
+        Ok(Response::new(user.into()))
     }
 
     async fn get_user(
         &self,
         request: Request<GetUserRequest>,
     ) -> Result<Response<UserResponse>, Status> {
-        let req = request.into_inner();
+        let caller = caller(&request)?;
 
-        let user = match req.identifier {
-            Some(get_user_request::Identifier::UserId(id)) => {
-                crate::db::get_user_by_id(&self.state.db, id).await
-            }
-            Some(get_user_request::Identifier::FirebaseUid(uid)) => {
-                crate::db::get_user_by_firebase_uid(&self.state.db, &uid).await
-            }
+        let lookup = match request.into_inner().identifier {
+            Some(get_user_request::Identifier::UserId(id)) => crate::api::UserLookup::Id(id),
+            Some(get_user_request::Identifier::FirebaseUid(uid)) => crate::api::UserLookup::FirebaseUid(uid),
             None => return Err(Status::invalid_argument("No identifier provided")),
-        }
-        .map_err(|e| Status::internal(format!("Database error: {}", e)))?
-        .ok_or_else(|| Status::not_found("User not found"))?;
+        };
 
-        Ok(Response::new(UserResponse {
-            id: user.id,
-            firebase_uid: user.firebase_uid,
-            email: user.email,
-            display_name: user.display_name,
-            photo_url: user.photo_url,
-            role: user.role,
-            created_at: user.created_at.to_string(),
+        let user = crate::api::get_user(&self.state, &caller, lookup).await?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn set_time_zone(
+        &self,
+        request: Request<SetTimeZoneRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let input = crate::api::TimeZoneUpdate {
+            time_zone: request.into_inner().time_zone,
+        };
+
+        let user = crate::api::set_time_zone(&self.state, &caller, input).await?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn create_mentor_profile(
+        &self,
+        request: Request<MentorProfileRequest>,
+    ) -> Result<Response<MentorProfileResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+        let mentor_id = req.mentor_id;
+
+        let profile = crate::api::create_mentor_profile(&self.state, &caller, mentor_id, mentor_profile_input(req)).await?;
+        Ok(Response::new(profile.into()))
+    }
+
+    async fn update_mentor_profile(
+        &self,
+        request: Request<MentorProfileRequest>,
+    ) -> Result<Response<MentorProfileResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+        let mentor_id = req.mentor_id;
+
+        let profile = crate::api::update_mentor_profile(&self.state, &caller, mentor_id, mentor_profile_input(req)).await?;
+        Ok(Response::new(profile.into()))
+    }
+
+    async fn get_mentor_profile(
+        &self,
+        request: Request<GetMentorProfileRequest>,
+    ) -> Result<Response<MentorProfileResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let profile = crate::api::get_mentor_profile(&self.state, &caller, request.into_inner().mentor_id).await?;
+        Ok(Response::new(profile.into()))
+    }
+
+    async fn delete_mentor_profile(
+        &self,
+        request: Request<DeleteMentorProfileRequest>,
+    ) -> Result<Response<EmptyResponse>, Status> {
+        let caller = caller(&request)?;
+
+        crate::api::delete_mentor_profile(&self.state, &caller, request.into_inner().mentor_id).await?;
+        Ok(Response::new(EmptyResponse {}))
+    }
+
+    async fn set_mentor_verification(
+        &self,
+        request: Request<SetMentorVerificationRequest>,
+    ) -> Result<Response<MentorProfileResponse>, Status> {
+        let req = request.into_inner();
+
+        let input = crate::api::VerificationUpdate {
+            verification_status: req.verification_status,
+        };
+
+        let profile = crate::api::set_mentor_verification(&self.state, req.mentor_id, input).await?;
+        Ok(Response::new(profile.into()))
+    }
+
+    async fn apply_for_mentor(
+        &self,
+        request: Request<ApplyForMentorRequest>,
+    ) -> Result<Response<MentorApplicationResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let input = crate::api::NewMentorApplication {
+            motivation: request.into_inner().motivation,
+        };
+
+        let application = crate::api::apply_for_mentor(&self.state, &caller, input).await?;
+        Ok(Response::new(application.into()))
+    }
+
+    async fn list_mentor_applications(
+        &self,
+        request: Request<ListMentorApplicationsRequest>,
+    ) -> Result<Response<MentorApplicationListResponse>, Status> {
+        let applications = crate::api::list_mentor_applications(&self.state, &request.into_inner().status).await?;
+
+        Ok(Response::new(MentorApplicationListResponse {
+            applications: applications.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn review_mentor_application(
+        &self,
+        request: Request<ReviewMentorApplicationRequest>,
+    ) -> Result<Response<MentorApplicationResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::MentorApplicationReview {
+            approve: req.approve,
+            note: req.note,
+        };
+
+        let application = crate::api::review_mentor_application(&self.state, &caller, req.application_id, input).await?;
+        Ok(Response::new(application.into()))
+    }
+
+    async fn set_weekly_availability(
+        &self,
+        request: Request<SetWeeklyAvailabilityRequest>,
+    ) -> Result<Response<AvailabilityResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::WeeklyAvailability {
+            time_zone: Some(req.time_zone),
+            windows: req
+                .windows
+                .into_iter()
+                .map(|window| crate::api::WeeklyWindowInput {
+                    weekday: window.weekday,
+                    start_time: window.start_time,
+                    end_time: window.end_time,
+                })
+                .collect(),
+        };
+
+        let availability = crate::api::set_weekly_availability(&self.state, &caller, input).await?;
+        Ok(Response::new(availability.into()))
+    }
+
+    async fn set_date_availability(
+        &self,
+        request: Request<SetDateAvailabilityRequest>,
+    ) -> Result<Response<AvailabilityResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::DateAvailability {
+            date: req.date,
+            blackout: req.blackout,
+            windows: req
+                .windows
+                .into_iter()
+                .map(|window| crate::api::TimeWindowInput {
+                    start_time: window.start_time,
+                    end_time: window.end_time,
+                })
+                .collect(),
+        };
+
+        let availability = crate::api::set_date_availability(&self.state, &caller, input).await?;
+        Ok(Response::new(availability.into()))
+    }
+
+    async fn get_mentor_availability(
+        &self,
+        request: Request<GetMentorAvailabilityRequest>,
+    ) -> Result<Response<AvailabilityResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let availability = crate::api::get_availability(&self.state, &caller, request.into_inner().mentor_id).await?;
+        Ok(Response::new(availability.into()))
+    }
+
+    async fn list_bookable_slots(
+        &self,
+        request: Request<ListBookableSlotsRequest>,
+    ) -> Result<Response<SlotListResponse>, Status> {
+        let req = request.into_inner();
+
+        let query = crate::api::SlotQuery {
+            mentor_id: req.mentor_id,
+            from_date: req.from_date,
+            to_date: req.to_date,
+            slot_minutes: req.slot_minutes,
+        };
+
+        let bookable = crate::api::list_bookable_slots(&self.state, query).await?;
+
+        Ok(Response::new(SlotListResponse {
+            time_zone: bookable.time_zone,
+            slots: bookable
+                .slots
+                .into_iter()
+                .map(|slot| Slot {
+                    start_at: crate::timestamp::format_utc(slot.start_at),
+                    end_at: crate::timestamp::format_utc(slot.end_at),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn search_mentors(
+        &self,
+        request: Request<SearchMentorsRequest>,
+    ) -> Result<Response<SearchMentorsResponse>, Status> {
+        let req = request.into_inner();
+
+        let search = crate::api::MentorSearch {
+            query: req.query,
+            skills: req.skills,
+            languages: req.languages,
+            min_price_cents: req.min_price_cents,
+            max_price_cents: req.max_price_cents,
+            currency: req.currency,
+            min_rating: req.min_rating,
+            available_within_days: req.available_within_days,
+            sort: req.sort,
+            page_size: req.page_size,
+            cursor: Some(req.cursor),
+        };
+
+        let results = crate::api::search_mentors(&self.state, search).await?;
+
+        Ok(Response::new(SearchMentorsResponse {
+            mentors: results.mentors.into_iter().map(Into::into).collect(),
+            next_cursor: results.next_cursor.unwrap_or_default(),
         }))
     }
 
+    async fn submit_review(
+        &self,
+        request: Request<SubmitReviewRequest>,
+    ) -> Result<Response<ReviewResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::ReviewInput {
+            rating: req.rating,
+            comment: req.comment,
+        };
+        let review = crate::api::submit_review(&self.state, &caller, req.session_id, input).await?;
+
+        Ok(Response::new(review.into()))
+    }
+
+    async fn edit_review(
+        &self,
+        request: Request<EditReviewRequest>,
+    ) -> Result<Response<ReviewResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::ReviewInput {
+            rating: req.rating,
+            comment: req.comment,
+        };
+        let review = crate::api::edit_review(&self.state, &caller, req.review_id, input).await?;
+
+        Ok(Response::new(review.into()))
+    }
+
+    async fn list_mentor_reviews(
+        &self,
+        request: Request<ListMentorReviewsRequest>,
+    ) -> Result<Response<MentorReviewsResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let page =
+            crate::api::list_mentor_reviews(&self.state, &caller, req.mentor_id, req.page_size, Some(&req.cursor)).await?;
+
+        Ok(Response::new(page.into()))
+    }
+
+    async fn set_review_visibility(
+        &self,
+        request: Request<SetReviewVisibilityRequest>,
+    ) -> Result<Response<ReviewResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::ReviewVisibilityUpdate {
+            hidden: req.hidden,
+            reason: req.reason,
+        };
+        let review = crate::api::set_review_visibility(&self.state, &caller, req.review_id, input).await?;
+
+        Ok(Response::new(review.into()))
+    }
+
     async fn create_session(
         &self,
         request: Request<CreateSessionRequest>,
     ) -> Result<Response<SessionResponse>, Status> {
+        let caller = caller(&request)?;
         let req = request.into_inner();
 
-        let scheduled_at = chrono::NaiveDateTime::parse_from_str(&req.scheduled_at, "%Y-%m-%d %H:%M:%S")
-            .map_err(|e| Status::invalid_argument(format!("Invalid datetime format: {}", e)))?;
-
-        let session = crate::models::CreateSession {
-            user_id: req.user_id,
+        let input = crate::api::NewSession {
             mentor_id: req.mentor_id,
             title: req.title,
             description: req.description,
-            scheduled_at,
+            scheduled_at: scheduled_at_input(req.scheduled_time, req.scheduled_at)?,
             duration_minutes: req.duration_minutes,
             meeting_link: req.meeting_link,
+            recurrence: req.recurrence,
         };
 
-        let session_id = crate::db::create_session(&self.state.db, &session)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to create session: {}", e)))?;
-
-        Ok(Response::new(SessionResponse {
-            id: session_id,
-            user_id: session.user_id,
-            mentor_id: session.mentor_id,
-            title: session.title,
-            description: session.description,
-            scheduled_at: session.scheduled_at.to_string(),
-            duration_minutes: session.duration_minutes.unwrap_or(60),
-            status: "scheduled".to_string(),
-            meeting_link: session.meeting_link,
-        }))
+        let session = crate::api::create_session(&self.state, &caller, input).await?;
+        Ok(Response::new(crate::api::SessionView::new(&caller, session).into()))
     }
 
     async fn get_user_sessions(
         &self,
         request: Request<GetUserSessionsRequest>,
     ) -> Result<Response<SessionListResponse>, Status> {
-        let user_id = request.into_inner().user_id;
-
-        let sessions = crate::db::get_sessions_by_user(&self.state.db, user_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to get sessions: {}", e)))?;
+        let caller = caller(&request)?;
 
-        let session_responses: Vec<SessionResponse> = sessions
-            .into_iter()
-            .map(|s| SessionResponse {
-                id: s.id,
-                user_id: s.user_id,
-                mentor_id: s.mentor_id,
-                title: s.title,
-                description: s.description,
-                scheduled_at: s.scheduled_at.to_string(),
-                duration_minutes: s.duration_minutes,
-                status: s.status,
-                meeting_link: s.meeting_link,
-            })
-            .collect();
+        let sessions = crate::api::list_sessions(&self.state, &caller, request.into_inner().user_id).await?;
 
         Ok(Response::new(SessionListResponse {
-            sessions: session_responses,
+            sessions: sessions
+                .into_iter()
+                .map(|session| crate::api::SessionView::new(&caller, session).into())
+                .collect(),
         }))
     }
 
-    async fn send_notification(
+    async fn start_session(
         &self,
-        request: Request<SendNotificationRequest>,
-    ) -> Result<Response<NotificationResponse>, Status> {
+        request: Request<SessionActionRequest>,
+    ) -> Result<Response<SessionResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let session = crate::api::start_session(&self.state, &caller, request.into_inner().session_id).await?;
+        Ok(Response::new(crate::api::SessionView::new(&caller, session).into()))
+    }
+
+    async fn complete_session(
+        &self,
+        request: Request<SessionActionRequest>,
+    ) -> Result<Response<SessionResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let session = crate::api::complete_session(&self.state, &caller, request.into_inner().session_id).await?;
+        Ok(Response::new(crate::api::SessionView::new(&caller, session).into()))
+    }
+
+    async fn cancel_session(
+        &self,
+        request: Request<CancelSessionRequest>,
+    ) -> Result<Response<SessionResponse>, Status> {
+        let caller = caller(&request)?;
         let req = request.into_inner();
 
-        let notification = crate::models::CreateNotification {
-            user_id: req.user_id,
-            title: req.title.clone(),
-            body: req.body.clone(),
-            notification_type: req.notification_type.clone(),
-            data: req.data.clone(),
+        let input = crate::api::SessionCancellation {
+            reason: req.reason,
+            scope: req.scope,
         };
 
-        let notification_id = crate::db::create_notification(&self.state.db, &notification)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to create notification: {}", e)))?;
-
-        // Get user's device tokens and send via FCM
-        let tokens = crate::db::get_user_device_tokens(&self.state.db, req.user_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to get device tokens: {}", e)))?;
-
-        // Send to each device token
-        for token in tokens {
-            let notification_data = crate::firebase::NotificationData {
-                link: None,
-                image: None,
-                chat_id: None,
-                sender_id: None,
-                caller_id: None,
-                call_id: None,
-                is_video: None,
-            };
-
-            if let Err(e) = self.state.firebase
-                .send_notification(&token, &req.title, &req.body, notification_data)
-                .await
-            {
-                eprintln!("Failed to send FCM notification: {}", e);
-            }
-        }
+        let session = crate::api::cancel_session(&self.state, &caller, req.session_id, input).await?;
+        Ok(Response::new(crate::api::SessionView::new(&caller, session).into()))
+    }
+
+    async fn reschedule_session(
+        &self,
+        request: Request<RescheduleSessionRequest>,
+    ) -> Result<Response<SessionResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::SessionReschedule {
+            scheduled_at: scheduled_at_input(req.scheduled_time, req.scheduled_at)?,
+            reason: req.reason,
+            scope: req.scope,
+        };
+
+        let session = crate::api::reschedule_session(&self.state, &caller, req.session_id, input).await?;
+        Ok(Response::new(crate::api::SessionView::new(&caller, session).into()))
+    }
+
+    async fn export_session_invite(
+        &self,
+        request: Request<SessionActionRequest>,
+    ) -> Result<Response<CalendarResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let ics = crate::api::session_invite(&self.state, &caller, request.into_inner().session_id).await?;
+        Ok(Response::new(CalendarResponse { ics }))
+    }
+
+    async fn create_calendar_feed(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<CalendarFeedResponse>, Status> {
+        let caller = caller(&request)?;
 
-        Ok(Response::new(NotificationResponse {
-            id: notification_id,
+        let feed = crate::api::create_calendar_feed(&self.state, &caller).await?;
+        Ok(Response::new(CalendarFeedResponse { feed_url: feed.feed_url }))
+    }
+
+    async fn revoke_calendar_feed(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<EmptyResponse>, Status> {
+        let caller = caller(&request)?;
+
+        crate::api::revoke_calendar_feed(&self.state, &caller).await?;
+        Ok(Response::new(EmptyResponse {}))
+    }
+
+    async fn send_notification(
+        &self,
+        request: Request<SendNotificationRequest>,
+    ) -> Result<Response<NotificationResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::NewNotification {
             user_id: req.user_id,
             title: req.title,
             body: req.body,
-            notification_type: req.notification_type,
+            // proto3 sends an empty string when the field is unset
+            notification_type: if req.notification_type.is_empty() {
+                "standard".to_string()
+            } else {
+                req.notification_type
+            },
             data: req.data,
-            is_read: false,
-            created_at: chrono::Utc::now().naive_utc().to_string(),
-        }))
+            payload: req.payload.map(Into::into),
+        };
+
+        let notification = crate::api::send_notification(&self.state, &caller, input).await?;
+        Ok(Response::new(notification.into()))
     }
 
     async fn get_unread_notifications(
         &self,
         request: Request<GetUnreadNotificationsRequest>,
     ) -> Result<Response<NotificationListResponse>, Status> {
-        let user_id = request.into_inner().user_id;
+        let caller = caller(&request)?;
 
-        let notifications = crate::db::get_unread_notifications(&self.state.db, user_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to get notifications: {}", e)))?;
-
-        let notification_responses: Vec<NotificationResponse> = notifications
-            .into_iter()
-            .map(|n| NotificationResponse {
-                id: n.id,
-                user_id: n.user_id,
-                title: n.title,
-                body: n.body,
-                notification_type: n.notification_type,
-                data: n.data,
-                is_read: n.is_read,
-                created_at: n.created_at.to_string(),
-            })
-            .collect();
+        let notifications =
+            crate::api::unread_notifications(&self.state, &caller, request.into_inner().user_id).await?;
 
         Ok(Response::new(NotificationListResponse {
-            notifications: notification_responses,
+            notifications: notifications.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn broadcast_notification(
+        &self,
+        request: Request<BroadcastNotificationRequest>,
+    ) -> Result<Response<BroadcastNotificationResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let target = match req.target {
+            Some(broadcast_notification_request::Target::Topic(topic)) => crate::api::BroadcastTarget::Topic(topic),
+            Some(broadcast_notification_request::Target::Condition(condition)) => {
+                crate::api::BroadcastTarget::Condition(condition)
+            }
+            Some(broadcast_notification_request::Target::Users(users)) => crate::api::BroadcastTarget::UserIds(users.user_ids),
+            None => return Err(Status::invalid_argument("topic, condition or users is required")),
+        };
+        let input = crate::api::NewBroadcast {
+            target,
+            title: req.title,
+            body: req.body,
+            payload: req.payload.map(Into::into),
+        };
+
+        let result = crate::api::broadcast_notification(&self.state, &caller, input).await?;
+
+        Ok(Response::new(BroadcastNotificationResponse {
+            message_id: result.message_id.unwrap_or_default(),
+            devices: result.devices.into_iter().map(Into::into).collect(),
+            sent_count: result.sent_count,
+            failed_count: result.failed_count,
+            skipped_user_ids: result.skipped_user_ids,
         }))
     }
 
+    async fn list_notifications(
+        &self,
+        request: Request<ListNotificationsRequest>,
+    ) -> Result<Response<NotificationPageResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let query = crate::api::NotificationQuery {
+            is_read: req.is_read,
+            notification_type: Some(req.notification_type),
+            created_from: Some(req.created_from),
+            created_to: Some(req.created_to),
+            page_size: req.page_size,
+            cursor: Some(req.cursor),
+        };
+
+        let page = crate::api::list_notifications(&self.state, &caller, query).await?;
+
+        Ok(Response::new(NotificationPageResponse {
+            notifications: page.notifications.into_iter().map(Into::into).collect(),
+            next_cursor: page.next_cursor.unwrap_or_default(),
+            unread_count: page.unread_count,
+        }))
+    }
+
+    async fn mark_notifications_read(
+        &self,
+        request: Request<MarkNotificationsReadRequest>,
+    ) -> Result<Response<MarkNotificationsReadResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let input = crate::api::NotificationIds {
+            notification_ids: request.into_inner().notification_ids,
+        };
+        let updated = crate::api::mark_notifications_read(&self.state, &caller, input).await?;
+
+        Ok(Response::new(MarkNotificationsReadResponse { updated }))
+    }
+
+    async fn mark_all_notifications_read(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<MarkNotificationsReadResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let updated = crate::api::mark_all_notifications_read(&self.state, &caller).await?;
+
+        Ok(Response::new(MarkNotificationsReadResponse { updated }))
+    }
+
+    async fn delete_notification(
+        &self,
+        request: Request<DeleteNotificationRequest>,
+    ) -> Result<Response<EmptyResponse>, Status> {
+        let caller = caller(&request)?;
+
+        crate::api::delete_notification(&self.state, &caller, request.into_inner().notification_id).await?;
+
+        Ok(Response::new(EmptyResponse {}))
+    }
+
     async fn mark_notification_read(
         &self,
         request: Request<MarkNotificationReadRequest>,
     ) -> Result<Response<EmptyResponse>, Status> {
-        let notification_id = request.into_inner().notification_id;
+        let caller = caller(&request)?;
 
-        crate::db::mark_notification_read(&self.state.db, notification_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to mark notification as read: {}", e)))?;
+        crate::api::mark_notification_read(&self.state, &caller, request.into_inner().notification_id).await?;
 
         Ok(Response::new(EmptyResponse {}))
     }
 
+    async fn get_notification_deliveries(
+        &self,
+        request: Request<GetNotificationDeliveriesRequest>,
+    ) -> Result<Response<NotificationDeliveriesResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let deliveries =
+            crate::api::notification_deliveries(&self.state, &caller, request.into_inner().notification_id).await?;
+
+        Ok(Response::new(NotificationDeliveriesResponse {
+            deliveries: deliveries.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn subscribe_notifications(
+        &self,
+        request: Request<SubscribeNotificationsRequest>,
+    ) -> Result<Response<Self::SubscribeNotificationsStream>, Status> {
+        let caller = caller(&request)?;
+        let last_seen_id = request.into_inner().last_seen_notification_id;
+
+        let events = crate::api::subscribe_notifications(self.state.clone(), &caller, last_seen_id).await?;
+        let stream = ReceiverStream::new(events).map(|event| event.map(Into::into));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn get_notification_preferences(
+        &self,
+        request: Request<EmptyRequest>,
+    ) -> Result<Response<NotificationPreferencesResponse>, Status> {
+        let caller = caller(&request)?;
+
+        let preferences = crate::api::notification_preferences(&self.state, &caller).await?;
+        Ok(Response::new(preferences.into()))
+    }
+
+    async fn update_notification_preferences(
+        &self,
+        request: Request<UpdateNotificationPreferencesRequest>,
+    ) -> Result<Response<NotificationPreferencesResponse>, Status> {
+        let caller = caller(&request)?;
+        let req = request.into_inner();
+
+        let input = crate::api::NotificationPreferencesUpdate {
+            channels: req
+                .channels
+                .into_iter()
+                .map(|channel| crate::api::NotificationChannelInput {
+                    notification_type: channel.notification_type,
+                    channel: channel.channel,
+                })
+                .collect(),
+            quiet_hours_start: Some(req.quiet_hours_start),
+            quiet_hours_end: Some(req.quiet_hours_end),
+            calls_bypass_quiet_hours: req.calls_bypass_quiet_hours,
+        };
+
+        let preferences = crate::api::update_notification_preferences(&self.state, &caller, input).await?;
+        Ok(Response::new(preferences.into()))
+    }
+
     async fn register_device_token(
         &self,
         request: Request<RegisterDeviceTokenRequest>,
     ) -> Result<Response<EmptyResponse>, Status> {
+        let caller = caller(&request)?;
         let req = request.into_inner();
 
-        let token = crate::models::CreateDeviceToken {
-            user_id: req.user_id,
+        let input = crate::api::NewDeviceToken {
             token: req.token,
             device_type: req.device_type,
+            web_push: req.web_push.map(|subscription| crate::api::WebPushSubscription {
+                endpoint: subscription.endpoint,
+                keys: crate::api::WebPushKeys {
+                    p256dh: subscription.p256dh,
+                    auth: subscription.auth,
+                },
+            }),
         };
 
-        crate::db::upsert_device_token(&self.state.db, &token)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to register device token: {}", e)))?;
+        crate::api::register_device_token(&self.state, &caller, input).await?;
 
         Ok(Response::new(EmptyResponse {}))
     }
@@ -339,11 +1062,35 @@ impl LinkWithMentor for MyLinkWithMentor {
 
 pub async fn run(host: &str, port: u16, state: Arc<AppState>) -> Result<(), Box<dyn std::error::Error>> {
     let addr = format!("{}:{}", host, port).parse()?;
+    let auth_layer = crate::auth::AuthLayer::new(state.clone());
+
+    // Standard grpc.health.v1 service, starting as NOT_SERVING until the first check
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    let service_name = <LinkWithMentorServer<MyLinkWithMentor> as NamedService>::NAME;
+    health_reporter
+        .set_service_status(service_name, tonic_health::ServingStatus::NotServing)
+        .await;
+    tokio::spawn(crate::health::report_grpc_health(state.clone(), health_reporter, service_name));
+
+    // Reflection over both API versions, since older grpcurl builds only speak v1alpha
+    let reflection_v1 = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(pb::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1()?;
+    let reflection_v1alpha = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(pb::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1alpha()?;
+
     let service = MyLinkWithMentor { state };
 
     println!("gRPC server listening on {}", addr);
 
     Server::builder()
+        .layer(auth_layer)
+        .add_service(health_service)
+        .add_service(reflection_v1)
+        .add_service(reflection_v1alpha)
         .add_service(LinkWithMentorServer::new(service))
         .serve(addr)
         .await?;
@@ -1,5 +1,11 @@
 use crate::AppState;
 use std::sync::Arc;
+use std::time::Duration;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+/// How often the `grpc.health.v1.Health` statuses are re-evaluated
+const GRPC_HEALTH_INTERVAL: Duration = Duration::from_secs(5);
 
 /// Health check status
 #[derive(Debug, Clone)]
@@ -7,44 +13,55 @@ pub struct HealthStatus {
     pub status: String,
     pub database: bool,
     pub firebase: bool,
+    /// Why FCM can't currently authenticate, when `firebase` is false
+    pub firebase_error: Option<String>,
     pub uptime_seconds: u64,
 }
 
 impl HealthStatus {
     pub fn to_json(&self) -> String {
-        format!(
-            r#"{{
-  "status": "{}",
-  "database": {},
-  "firebase": {},
-  "uptime_seconds": {}
-}}"#,
-            self.status, self.database, self.firebase, self.uptime_seconds
-        )
+        serde_json::json!({
+            "status": self.status,
+            "database": self.database,
+            "firebase": self.firebase,
+            "firebase_error": self.firebase_error,
+            "uptime_seconds": self.uptime_seconds,
+        })
+        .to_string()
+    }
+
+    /// Healthy or degraded; only an unreachable database takes the server out of rotation
+    pub fn is_serving(&self) -> bool {
+        self.status != "unhealthy"
     }
 }
 
 /// Check system health
 pub async fn check_health(state: Arc<AppState>, start_time: std::time::Instant) -> HealthStatus {
     let db_healthy = check_database(&state.db).await;
-    let firebase_healthy = true; // Firebase client is initialized at startup
+    let firebase_error = state.firebase.token_error();
+    let firebase_healthy = firebase_error.is_none();
     
     let uptime = start_time.elapsed().as_secs();
-    
-    let status = if db_healthy && firebase_healthy {
-        "healthy"
-    } else {
-        "unhealthy"
-    };
 
     HealthStatus {
-        status: status.to_string(),
+        status: overall_status(db_healthy, firebase_healthy).to_string(),
         database: db_healthy,
         firebase: firebase_healthy,
+        firebase_error,
         uptime_seconds: uptime,
     }
 }
 
+/// An FCM outage hits every replica alike, so it degrades the server instead of failing it
+fn overall_status(database: bool, firebase: bool) -> &'static str {
+    match (database, firebase) {
+        (false, _) => "unhealthy",
+        (true, false) => "degraded",
+        (true, true) => "healthy",
+    }
+}
+
 /// Check database connectivity
 async fn check_database(pool: &crate::db::DbPool) -> bool {
     // Try a simple query
@@ -60,6 +77,37 @@ pub async fn check_readiness(state: Arc<AppState>) -> bool {
     check_database(&state.db).await
 }
 
+/// Keep the `grpc.health.v1.Health` statuses up to date. The overall server
+/// status ("") follows `check_health`, `service` follows `check_readiness`.
+/// Statuses are only published when they change, so Watch streams see transitions.
+pub async fn report_grpc_health(state: Arc<AppState>, reporter: HealthReporter, service: &'static str) {
+    let mut interval = tokio::time::interval(GRPC_HEALTH_INTERVAL);
+    let mut last = None;
+
+    loop {
+        interval.tick().await;
+
+        let healthy = check_health(state.clone(), state.start_time).await.is_serving();
+        let ready = check_readiness(state.clone()).await;
+        let current = (serving_status(healthy), serving_status(ready));
+
+        if last != Some(current) {
+            tracing::info!("gRPC health: server {}, {} {}", current.0, service, current.1);
+            reporter.set_service_status("", current.0).await;
+            reporter.set_service_status(service, current.1).await;
+            last = Some(current);
+        }
+    }
+}
+
+fn serving_status(ok: bool) -> ServingStatus {
+    if ok {
+        ServingStatus::Serving
+    } else {
+        ServingStatus::NotServing
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,6 +118,7 @@ mod tests {
             status: "healthy".to_string(),
             database: true,
             firebase: true,
+            firebase_error: None,
             uptime_seconds: 3600,
         };
 
@@ -77,4 +126,17 @@ mod tests {
         assert!(json.contains("healthy"));
         assert!(json.contains("3600"));
     }
+
+    #[test]
+    fn test_overall_status() {
+        assert_eq!(overall_status(true, true), "healthy");
+        assert_eq!(overall_status(true, false), "degraded");
+        assert_eq!(overall_status(false, true), "unhealthy");
+    }
+
+    #[test]
+    fn test_serving_status() {
+        assert_eq!(serving_status(true), ServingStatus::Serving);
+        assert_eq!(serving_status(false), ServingStatus::NotServing);
+    }
 }
@@ -1,14 +1,32 @@
+mod api;
 mod config;
 mod db;
 mod server;
+mod rest;
 mod grpc;
 mod firebase;
 mod cert;
 mod models;
 mod auth;
+mod availability;
+mod broadcast;
+mod calendar;
+mod mentor_profile;
+mod policy;
+mod recurrence;
+mod reminders;
+mod review;
+mod search;
+mod session_lifecycle;
+mod timestamp;
 mod rate_limit;
 mod metrics;
 mod health;
+mod notification_hub;
+mod notification_preferences;
+mod outbox;
+mod push;
+mod webpush;
 
 use config::Config;
 
@@ -16,9 +34,18 @@ use std::sync::Arc;
 
 pub struct AppState {
     pub firebase: firebase::FirebaseClient,
+    /// Native Web Push sender, when a VAPID key is configured
+    pub web_push: Option<webpush::WebPushClient>,
+    pub auth: auth::AuthMiddleware,
     pub db: db::DbPool,
     pub rate_limiter: rate_limit::RateLimiter,
     pub metrics: metrics::Metrics,
+    pub notification_hub: notification_hub::NotificationHub,
+    pub outbox: outbox::Outbox,
+    pub topics: broadcast::TopicSync,
+    pub search: search::SearchIndex,
+    /// See `Config::public_base_url`
+    pub public_base_url: String,
     pub start_time: std::time::Instant,
 }
 
@@ -42,7 +69,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let db = db::init(&config).await?;
 
-    let firebase = firebase::FirebaseClient::new()?;
+    let firebase = firebase::FirebaseClient::new(&config)?;
+
+    let web_push = config
+        .vapid_private_key
+        .as_deref()
+        .map(|key| webpush::WebPushClient::new(key, &config.vapid_subject, config.web_push_allowed_hosts.clone()))
+        .transpose()?;
+    match (&web_push, &config.firebase_web_push_cert) {
+        (None, _) => tracing::info!("Web Push disabled: VAPID_PRIVATE_KEY is not set"),
+        (Some(client), Some(cert)) if client.public_key() != cert.trim_end_matches('=') => {
+            tracing::warn!("VAPID_PRIVATE_KEY doesn't match FIREBASE_WEB_PUSH_CERT; push services will reject subscriptions made with the certificate");
+        }
+        (Some(_), _) => tracing::info!("Web Push enabled"),
+    }
+
+    let auth = auth::AuthMiddleware::new(&config);
     
     // Create rate limiter: 100 requests per minute per user
     let rate_limiter = rate_limit::RateLimiter::new(
@@ -52,16 +94,91 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Create metrics collector
     let metrics = metrics::Metrics::new();
+    let notification_hub = notification_hub::NotificationHub::new();
+    let (search, search_updates) =
+        search::SearchIndex::new(config.search_index_dir.as_deref().map(std::path::Path::new))?;
     let start_time = std::time::Instant::now();
     
     let app_state = Arc::new(AppState { 
         firebase, 
+        web_push,
+        auth,
         db,
         rate_limiter,
         metrics,
+        notification_hub,
+        outbox: outbox::Outbox::new(),
+        topics: broadcast::TopicSync::new(),
+        search,
+        public_base_url: config.public_base_url.clone(),
         start_time,
     });
 
+    // Keep the mentor search index in sync with the database
+    tokio::spawn(search::run_indexer(app_state.clone(), search_updates));
+
+    // Send push notifications written to the outbox, retrying failed deliveries
+    tokio::spawn(outbox::run_worker(app_state.clone()));
+
+    // Subscribe device tokens to the FCM topics of their owners' roles
+    tokio::spawn(broadcast::run_worker(app_state.clone()));
+
+    // Remind participants before their sessions start
+    tokio::spawn(reminders::run_scheduler(app_state.clone(), config.reminder_offsets.clone()));
+
+    // Expire device tokens the app hasn't registered again within the configured age
+    if config.device_token_max_age_days > 0 {
+        let max_age = chrono::Days::new(config.device_token_max_age_days.into());
+        let token_state = app_state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(6 * 60 * 60));
+            loop {
+                interval.tick().await;
+                let cutoff = chrono::Utc::now().naive_utc() - max_age;
+                match db::delete_stale_device_tokens(&token_state.db, cutoff).await.map_err(|e| e.to_string()) {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Expired {} stale device tokens", count),
+                    Err(e) => tracing::error!("Failed to expire device tokens: {}", e),
+                }
+            }
+        });
+    }
+
+    // Purge read notifications past the retention period, in batches to keep locks short
+    if config.notification_retention_days > 0 {
+        let retention = chrono::Days::new(config.notification_retention_days.into());
+        let retention_state = app_state.clone();
+        tokio::spawn(async move {
+            const BATCH_SIZE: u32 = 1000;
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(6 * 60 * 60));
+            loop {
+                interval.tick().await;
+                let cutoff = chrono::Utc::now().naive_utc() - retention;
+                let mut purged = 0;
+                loop {
+                    let result = db::delete_read_notifications(&retention_state.db, cutoff, BATCH_SIZE)
+                        .await
+                        .map_err(|e| e.to_string());
+                    match result {
+                        Ok(count) => {
+                            purged += count;
+                            if count < u64::from(BATCH_SIZE) {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to purge read notifications: {}", e);
+                            break;
+                        }
+                    }
+                }
+                if purged > 0 {
+                    tracing::info!("Purged {} read notifications", purged);
+                }
+            }
+        });
+    }
+
     // Spawn metrics reporter (every 60 seconds)
     let metrics_state = app_state.clone();
     tokio::spawn(async move {
@@ -0,0 +1,114 @@
+use tonic::Status;
+
+/// Values allowed by the `mentor_profiles.verification_status` ENUM
+pub const VERIFICATION_STATUSES: &[&str] = &["unverified", "pending", "verified", "rejected"];
+
+pub const MAX_HEADLINE_LEN: usize = 160;
+pub const MAX_SKILLS: usize = 20;
+pub const MAX_SKILL_LEN: usize = 64;
+pub const MAX_LANGUAGES: usize = 10;
+
+/// Trim, lowercase and de-duplicate skill tags, keeping their order
+pub fn normalize_skills(skills: &[String]) -> Result<Vec<String>, Status> {
+    let mut normalized: Vec<String> = Vec::new();
+
+    for skill in skills {
+        let skill = skill.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if skill.is_empty() {
+            return Err(Status::invalid_argument("Skills must not be empty"));
+        }
+        if skill.chars().count() > MAX_SKILL_LEN {
+            return Err(Status::invalid_argument(format!(
+                "Skill {:?} is longer than {} characters",
+                skill, MAX_SKILL_LEN
+            )));
+        }
+        if !normalized.contains(&skill) {
+            normalized.push(skill);
+        }
+    }
+
+    if normalized.len() > MAX_SKILLS {
+        return Err(Status::invalid_argument(format!("At most {} skills are allowed", MAX_SKILLS)));
+    }
+    Ok(normalized)
+}
+
+/// Lowercase and de-duplicate ISO 639-1/639-3 language codes such as `en` or `yue`
+pub fn normalize_languages(languages: &[String]) -> Result<Vec<String>, Status> {
+    let mut normalized: Vec<String> = Vec::new();
+
+    for language in languages {
+        let language = language.trim().to_ascii_lowercase();
+        if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_lowercase()) {
+            return Err(Status::invalid_argument(format!(
+                "Invalid language code {:?}, expected ISO 639 such as \"en\"",
+                language
+            )));
+        }
+        if !normalized.contains(&language) {
+            normalized.push(language);
+        }
+    }
+
+    if normalized.len() > MAX_LANGUAGES {
+        return Err(Status::invalid_argument(format!("At most {} languages are allowed", MAX_LANGUAGES)));
+    }
+    Ok(normalized)
+}
+
+/// An hourly rate needs an ISO 4217 currency and vice versa; returns the uppercased currency
+pub fn validate_rate(hourly_rate_cents: Option<u32>, currency: Option<&str>) -> Result<Option<String>, Status> {
+    match (hourly_rate_cents, currency.map(str::trim).filter(|currency| !currency.is_empty())) {
+        (None, None) => Ok(None),
+        (Some(_), Some(currency)) => {
+            if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(Status::invalid_argument(format!(
+                    "Invalid currency {:?}, expected an ISO 4217 code such as \"EUR\"",
+                    currency
+                )));
+            }
+            Ok(Some(currency.to_ascii_uppercase()))
+        }
+        (Some(_), None) => Err(Status::invalid_argument("hourly_rate_cents requires a currency")),
+        (None, Some(_)) => Err(Status::invalid_argument("currency requires hourly_rate_cents")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_normalize_skills() {
+        assert_eq!(
+            normalize_skills(&strings(&["  Rust ", "rust", "System   Design"])).unwrap(),
+            strings(&["rust", "system design"])
+        );
+        assert!(normalize_skills(&strings(&[" "])).is_err());
+        assert!(normalize_skills(&["x".repeat(MAX_SKILL_LEN + 1)]).is_err());
+        let too_many: Vec<String> = (0..=MAX_SKILLS).map(|i| format!("skill{}", i)).collect();
+        assert!(normalize_skills(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_normalize_languages() {
+        assert_eq!(normalize_languages(&strings(&["EN", "de", "en"])).unwrap(), strings(&["en", "de"]));
+        assert!(normalize_languages(&strings(&["english"])).is_err());
+        assert!(normalize_languages(&strings(&["e1"])).is_err());
+    }
+
+    #[test]
+    fn test_validate_rate() {
+        assert_eq!(validate_rate(None, None).unwrap(), None);
+        assert_eq!(validate_rate(None, Some("")).unwrap(), None);
+        assert_eq!(validate_rate(Some(5000), Some("eur")).unwrap(), Some("EUR".to_string()));
+        assert!(validate_rate(Some(5000), None).is_err());
+        assert!(validate_rate(None, Some("EUR")).is_err());
+        assert!(validate_rate(Some(5000), Some("EURO")).is_err());
+    }
+}
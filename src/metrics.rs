@@ -10,6 +10,7 @@ pub struct Metrics {
     pub total_users_created: Arc<AtomicU64>,
     pub total_sessions_created: Arc<AtomicU64>,
     pub total_notifications_sent: Arc<AtomicU64>,
+    pub permission_denied_requests: Arc<AtomicU64>,
 }
 
 impl Metrics {
@@ -21,6 +22,7 @@ impl Metrics {
             total_users_created: Arc::new(AtomicU64::new(0)),
             total_sessions_created: Arc::new(AtomicU64::new(0)),
             total_notifications_sent: Arc::new(AtomicU64::new(0)),
+            permission_denied_requests: Arc::new(AtomicU64::new(0)),
         }
     }
 
@@ -48,6 +50,10 @@ impl Metrics {
         self.total_notifications_sent.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn increment_permission_denied(&self) {
+        self.permission_denied_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn get_snapshot(&self) -> MetricsSnapshot {
         MetricsSnapshot {
             total_requests: self.total_requests.load(Ordering::Relaxed),
@@ -56,6 +62,7 @@ impl Metrics {
             total_users_created: self.total_users_created.load(Ordering::Relaxed),
             total_sessions_created: self.total_sessions_created.load(Ordering::Relaxed),
             total_notifications_sent: self.total_notifications_sent.load(Ordering::Relaxed),
+            permission_denied_requests: self.permission_denied_requests.load(Ordering::Relaxed),
         }
     }
 }
@@ -74,6 +81,7 @@ pub struct MetricsSnapshot {
     pub total_users_created: u64,
     pub total_sessions_created: u64,
     pub total_notifications_sent: u64,
+    pub permission_denied_requests: u64,
 }
 
 impl MetricsSnapshot {
@@ -93,7 +101,8 @@ impl MetricsSnapshot {
   "success_rate": {:.2},
   "total_users_created": {},
   "total_sessions_created": {},
-  "total_notifications_sent": {}
+  "total_notifications_sent": {},
+  "permission_denied_requests": {}
 }}"#,
             self.total_requests,
             self.successful_requests,
@@ -101,7 +110,8 @@ impl MetricsSnapshot {
             self.success_rate(),
             self.total_users_created,
             self.total_sessions_created,
-            self.total_notifications_sent
+            self.total_notifications_sent,
+            self.permission_denied_requests
         )
     }
 }
@@ -117,9 +127,11 @@ mod tests {
         metrics.increment_requests();
         metrics.increment_successful();
         metrics.increment_users_created();
+        metrics.increment_permission_denied();
         
         let snapshot = metrics.get_snapshot();
         assert_eq!(snapshot.total_requests, 1);
+        assert_eq!(snapshot.permission_denied_requests, 1);
         assert_eq!(snapshot.successful_requests, 1);
         assert_eq!(snapshot.total_users_created, 1);
         assert_eq!(snapshot.success_rate(), 100.0);
@@ -9,7 +9,10 @@ pub struct User {
     pub display_name: Option<String>,
     pub photo_url: Option<String>,
     pub role: String,
+    pub time_zone: String,
+    #[serde(with = "crate::timestamp::utc")]
     pub created_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
     pub updated_at: chrono::NaiveDateTime,
 }
 
@@ -19,7 +22,89 @@ pub struct CreateUser {
     pub email: String,
     pub display_name: Option<String>,
     pub photo_url: Option<String>,
-    pub role: Option<String>,
+    pub time_zone: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MentorProfile {
+    pub user_id: u64,
+    pub headline: String,
+    pub bio: Option<String>,
+    pub years_experience: u16,
+    pub hourly_rate_cents: Option<u32>,
+    pub currency: Option<String>, // ISO 4217
+    pub verification_status: String,
+    #[serde(with = "crate::timestamp::utc")]
+    pub created_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Profile fields written by create and update; languages and skills replace the existing ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMentorProfile {
+    pub user_id: u64,
+    pub headline: String,
+    pub bio: Option<String>,
+    pub years_experience: u16,
+    pub hourly_rate_cents: Option<u32>,
+    pub currency: Option<String>,
+    pub languages: Vec<String>,
+    pub skills: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MentorApplication {
+    pub id: u64,
+    pub user_id: u64,
+    pub motivation: String,
+    pub status: String,
+    pub reviewed_by: Option<u64>,
+    pub review_note: Option<String>,
+    #[serde(with = "crate::timestamp::utc")]
+    pub created_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc_option")]
+    pub reviewed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMentorApplication {
+    pub user_id: u64,
+    pub motivation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AvailabilityRule {
+    pub id: u64,
+    pub mentor_id: u64,
+    pub weekday: u8, // ISO weekday, 1 = Monday
+    pub start_time: chrono::NaiveTime,
+    pub end_time: chrono::NaiveTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAvailabilityRule {
+    pub weekday: u8,
+    pub start_time: chrono::NaiveTime,
+    pub end_time: chrono::NaiveTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AvailabilityException {
+    pub id: u64,
+    pub mentor_id: u64,
+    pub date: chrono::NaiveDate,
+    pub is_blackout: bool,
+    pub start_time: Option<chrono::NaiveTime>,
+    pub end_time: Option<chrono::NaiveTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAvailabilityException {
+    pub date: chrono::NaiveDate,
+    pub is_blackout: bool,
+    pub start_time: Option<chrono::NaiveTime>,
+    pub end_time: Option<chrono::NaiveTime>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -29,25 +114,111 @@ pub struct Session {
     pub mentor_id: u64,
     pub title: String,
     pub description: Option<String>,
-    pub scheduled_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
+    pub scheduled_at: chrono::NaiveDateTime, // UTC
     pub duration_minutes: i32,
     pub status: String,
     pub meeting_link: Option<String>,
+    pub cancelled_by: Option<u64>,
+    pub cancellation_reason: Option<String>,
+    pub series_id: Option<u64>,
+    /// Start given by the series rule; differs from `scheduled_at` once this occurrence is rescheduled
+    #[serde(with = "crate::timestamp::utc_option")]
+    pub original_scheduled_at: Option<chrono::NaiveDateTime>,
+    /// iCalendar `SEQUENCE`, bumped whenever calendars must replace their copy of the event
+    pub sequence: u32,
+    #[serde(with = "crate::timestamp::utc")]
     pub created_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
     pub updated_at: chrono::NaiveDateTime,
 }
 
+/// A recurring series whose occurrences are rows in `sessions`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SessionSeries {
+    pub id: u64,
+    pub user_id: u64,
+    pub mentor_id: u64,
+    pub recurrence_rule: String, // RFC 5545 RRULE value
+    pub time_zone: String,
+    #[serde(with = "crate::timestamp::utc")]
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSessionSeries {
+    pub recurrence_rule: String,
+    pub time_zone: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateSession {
     pub user_id: u64,
     pub mentor_id: u64,
     pub title: String,
     pub description: Option<String>,
-    pub scheduled_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
+    pub scheduled_at: chrono::NaiveDateTime, // UTC
     pub duration_minutes: Option<i32>,
     pub meeting_link: Option<String>,
 }
 
+/// A status transition applied to a session and recorded in `session_history`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStatusChange {
+    pub session_id: u64,
+    pub changed_by: u64,
+    pub from_status: String,
+    pub to_status: String,
+    pub reason: Option<String>,
+    #[serde(with = "crate::timestamp::utc_option")]
+    pub previous_scheduled_at: Option<chrono::NaiveDateTime>,
+    #[serde(with = "crate::timestamp::utc_option")]
+    pub new_scheduled_at: Option<chrono::NaiveDateTime>,
+}
+
+/// A participant's review of a completed session
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SessionReview {
+    pub id: u64,
+    pub session_id: u64,
+    pub reviewer_id: u64,
+    pub reviewee_id: u64,
+    pub reviewer_role: String, // mentee reviews count towards the mentor's rating
+    pub rating: u8,
+    pub comment: Option<String>,
+    pub is_hidden: bool,
+    pub hidden_by: Option<u64>,
+    pub hidden_reason: Option<String>,
+    #[serde(with = "crate::timestamp::utc")]
+    pub created_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSessionReview {
+    pub session_id: u64,
+    pub reviewer_id: u64,
+    pub reviewee_id: u64,
+    pub reviewer_role: String,
+    pub rating: u8,
+    pub comment: Option<String>,
+}
+
+/// Running totals over a mentor's visible mentee reviews
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MentorRating {
+    pub mentor_id: u64,
+    pub review_count: i32,
+    pub rating_sum: i32,
+    pub rating_1_count: i32,
+    pub rating_2_count: i32,
+    pub rating_3_count: i32,
+    pub rating_4_count: i32,
+    pub rating_5_count: i32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Notification {
     pub id: u64,
@@ -57,6 +228,7 @@ pub struct Notification {
     pub notification_type: String,
     pub data: Option<String>, // JSON string
     pub is_read: bool,
+    #[serde(with = "crate::timestamp::utc")]
     pub created_at: chrono::NaiveDateTime,
 }
 
@@ -69,19 +241,109 @@ pub struct CreateNotification {
     pub data: Option<String>,
 }
 
+/// Filters of a notification listing; unset fields don't filter
+#[derive(Debug, Clone, Default)]
+pub struct NotificationFilter {
+    pub is_read: Option<bool>,
+    pub notification_type: Option<String>,
+    /// Created at or after, UTC
+    pub created_from: Option<chrono::NaiveDateTime>,
+    /// Created before, UTC
+    pub created_to: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NotificationChannel {
+    pub notification_type: String,
+    pub channel: String, // push, in_app
+}
+
+/// Quiet hours are wall-clock times in the user's time zone; both are set or neither
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NotificationSettings {
+    pub user_id: u64,
+    pub quiet_hours_start: Option<chrono::NaiveTime>,
+    pub quiet_hours_end: Option<chrono::NaiveTime>,
+    pub calls_bypass_quiet_hours: bool,
+}
+
+/// What decides whether and when one notification is pushed to a user
+#[derive(Debug, Clone, FromRow)]
+pub struct PushPreferences {
+    pub time_zone: String,
+    pub channel: Option<String>,
+    pub quiet_hours_start: Option<chrono::NaiveTime>,
+    pub quiet_hours_end: Option<chrono::NaiveTime>,
+    pub calls_bypass_quiet_hours: Option<bool>,
+}
+
+/// Push delivery of a notification to one device; the token itself isn't exposed
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NotificationDelivery {
+    pub id: u64,
+    pub notification_id: u64,
+    pub device_type: String,
+    pub provider: String, // fcm, web_push
+    pub status: String, // pending, sent, failed
+    pub attempts: u32,
+    #[serde(with = "crate::timestamp::utc")]
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub last_error: Option<String>,
+    #[serde(with = "crate::timestamp::utc_option")]
+    pub sent_at: Option<chrono::NaiveDateTime>,
+    #[serde(with = "crate::timestamp::utc")]
+    pub created_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// A delivery claimed by the outbox worker, with what it needs to send it
+#[derive(Debug, Clone, FromRow)]
+pub struct PendingDelivery {
+    pub id: u64,
+    pub notification_id: u64,
+    pub device_token: String,
+    pub provider: String,
+    /// Keys of a Web Push subscription; `None` for FCM, or when the token was removed since
+    pub web_push_p256dh: Option<String>,
+    pub web_push_auth: Option<String>,
+    pub title: String,
+    pub body: String,
+    pub notification_type: String,
+    pub data: Option<String>,
+    pub attempts: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct DeviceToken {
     pub id: u64,
     pub user_id: u64,
     pub token: String,
     pub device_type: String,
+    pub provider: String, // fcm, web_push
+    pub web_push_p256dh: Option<String>,
+    pub web_push_auth: Option<String>,
+    #[serde(with = "crate::timestamp::utc")]
     pub created_at: chrono::NaiveDateTime,
+    #[serde(with = "crate::timestamp::utc")]
     pub updated_at: chrono::NaiveDateTime,
 }
 
+/// A device token whose topic subscriptions don't match its owner's role yet
+#[derive(Debug, Clone, FromRow)]
+pub struct TopicSubscription {
+    pub id: u64,
+    pub token: String,
+    pub role: String,
+    pub topic_role: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateDeviceToken {
     pub user_id: u64,
     pub token: String,
     pub device_type: String,
+    pub provider: String,
+    pub web_push_p256dh: Option<String>,
+    pub web_push_auth: Option<String>,
 }
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::models::Notification;
+
+/// Notifications buffered per subscriber before it is dropped as too slow
+pub const SUBSCRIBER_BUFFER: usize = 64;
+
+struct Subscriber {
+    id: u64,
+    sender: mpsc::Sender<Notification>,
+}
+
+/// In-process fan-out of newly persisted notifications, keyed by recipient user ID
+#[derive(Clone, Default)]
+pub struct NotificationHub {
+    subscribers: Arc<Mutex<HashMap<u64, Vec<Subscriber>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl NotificationHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a live subscriber for `user_id`
+    pub fn subscribe(&self, user_id: u64) -> Subscription {
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        self.subscribers
+            .lock()
+            .unwrap()
+            .entry(user_id)
+            .or_default()
+            .push(Subscriber { id, sender });
+
+        Subscription {
+            hub: self.clone(),
+            user_id,
+            id,
+            receiver,
+        }
+    }
+
+    /// Deliver a notification to every subscriber of its recipient, returning how many received it.
+    /// A subscriber whose buffer is full is dropped; its stream ends and the client resubscribes
+    /// from the last notification it saw.
+    pub fn publish(&self, notification: &Notification) -> usize {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let Some(user_subscribers) = subscribers.get_mut(&notification.user_id) else {
+            return 0;
+        };
+
+        let mut delivered = 0;
+        user_subscribers.retain(|subscriber| match subscriber.sender.try_send(notification.clone()) {
+            Ok(()) => {
+                delivered += 1;
+                true
+            }
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!(
+                    "Dropping slow notification subscriber for user {}",
+                    notification.user_id
+                );
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        });
+
+        if user_subscribers.is_empty() {
+            subscribers.remove(&notification.user_id);
+        }
+
+        delivered
+    }
+
+    /// Number of live subscribers for `user_id`
+    pub fn subscriber_count(&self, user_id: u64) -> usize {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .get(&user_id)
+            .map_or(0, Vec::len)
+    }
+
+    fn unsubscribe(&self, user_id: u64, id: u64) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if let Some(user_subscribers) = subscribers.get_mut(&user_id) {
+            user_subscribers.retain(|subscriber| subscriber.id != id);
+            if user_subscribers.is_empty() {
+                subscribers.remove(&user_id);
+            }
+        }
+    }
+}
+
+/// A live subscription; unregisters itself from the hub when dropped
+pub struct Subscription {
+    hub: NotificationHub,
+    user_id: u64,
+    id: u64,
+    receiver: mpsc::Receiver<Notification>,
+}
+
+impl Subscription {
+    /// Next notification, or `None` once the hub has dropped this subscriber for falling behind
+    pub async fn recv(&mut self) -> Option<Notification> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.hub.unsubscribe(self.user_id, self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(id: u64, user_id: u64) -> Notification {
+        Notification {
+            id,
+            user_id,
+            title: "Title".to_string(),
+            body: "Body".to_string(),
+            notification_type: "standard".to_string(),
+            data: None,
+            is_read: false,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_only_recipient() {
+        let hub = NotificationHub::new();
+        let mut first = hub.subscribe(1);
+        let mut second = hub.subscribe(1);
+        let _other = hub.subscribe(2);
+
+        assert_eq!(hub.publish(&notification(10, 1)), 2);
+        assert_eq!(first.recv().await.unwrap().id, 10);
+        assert_eq!(second.recv().await.unwrap().id, 10);
+        assert_eq!(hub.publish(&notification(11, 3)), 0);
+    }
+
+    #[test]
+    fn test_drop_unsubscribes() {
+        let hub = NotificationHub::new();
+        let subscription = hub.subscribe(1);
+        assert_eq!(hub.subscriber_count(1), 1);
+
+        drop(subscription);
+        assert_eq!(hub.subscriber_count(1), 0);
+        assert_eq!(hub.publish(&notification(10, 1)), 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_is_dropped() {
+        let hub = NotificationHub::new();
+        let mut subscription = hub.subscribe(1);
+
+        for id in 0..=SUBSCRIBER_BUFFER as u64 {
+            hub.publish(&notification(id, 1));
+        }
+        assert_eq!(hub.subscriber_count(1), 0);
+
+        for _ in 0..SUBSCRIBER_BUFFER {
+            assert!(subscription.recv().await.is_some());
+        }
+        assert!(subscription.recv().await.is_none());
+    }
+}
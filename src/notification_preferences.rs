@@ -0,0 +1,147 @@
+//! Whether and when a notification is pushed, from the recipient's channel
+//! for its type and their quiet hours.
+//!
+//! Notifications are always stored and streamed to live subscribers; these
+//! preferences only hold back the FCM push.
+
+use chrono::{Duration, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+
+use crate::models::PushPreferences;
+
+/// Values allowed by the `notification_preferences.channel` ENUM
+pub const CHANNELS: &[&str] = &["push", "in_app"];
+
+/// Channel of notification types the user hasn't chosen one for
+pub const DEFAULT_CHANNEL: &str = "push";
+
+/// When to push a notification of `notification_type`: `now`, the end of the user's quiet
+/// hours, or `None` for in-app only
+pub fn push_at(preferences: &PushPreferences, notification_type: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    if preferences.channel.as_deref().unwrap_or(DEFAULT_CHANNEL) != "push" {
+        return None;
+    }
+
+    let (Some(start), Some(end)) = (preferences.quiet_hours_start, preferences.quiet_hours_end) else {
+        return Some(now);
+    };
+    let time_zone: Tz = preferences.time_zone.parse().unwrap_or(Tz::UTC);
+    let Some(quiet_until) = quiet_hours_end(start, end, time_zone, now) else {
+        return Some(now);
+    };
+
+    if notification_type == "call" {
+        // A call pushed once quiet hours are over would ring for a call that ended long ago
+        return preferences.calls_bypass_quiet_hours.unwrap_or(true).then_some(now);
+    }
+    Some(quiet_until)
+}
+
+/// Like [`push_at`], for notifications that are pointless once `deadline` has passed, such as
+/// session reminders: a push held back past it isn't sent at all, leaving the notification in-app
+pub fn push_at_by(
+    preferences: &PushPreferences,
+    notification_type: &str,
+    now: NaiveDateTime,
+    deadline: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    push_at(preferences, notification_type, now).filter(|at| *at <= deadline.max(now))
+}
+
+/// If `now` falls within the quiet hours `[start, end)` in `time_zone`, the UTC instant they end.
+/// Quiet hours wrap past midnight when `start` is after `end`, e.g. 22:00 to 07:00.
+pub fn quiet_hours_end(start: NaiveTime, end: NaiveTime, time_zone: Tz, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let local = time_zone.from_utc_datetime(&now).naive_local();
+    let time = local.time();
+
+    let quiet = if start <= end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    };
+    if !quiet {
+        return None;
+    }
+
+    let end_date = if time < end { local.date() } else { local.date() + Duration::days(1) };
+    crate::availability::local_to_utc(time_zone, end_date.and_time(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preferences(channel: Option<&str>, quiet_hours: Option<(&str, &str)>) -> PushPreferences {
+        let time = |value| crate::availability::parse_time(value).unwrap();
+        PushPreferences {
+            time_zone: "Europe/Berlin".to_string(),
+            channel: channel.map(str::to_string),
+            quiet_hours_start: quiet_hours.map(|(start, _)| time(start)),
+            quiet_hours_end: quiet_hours.map(|(_, end)| time(end)),
+            calls_bypass_quiet_hours: None,
+        }
+    }
+
+    fn at(value: &str) -> NaiveDateTime {
+        crate::timestamp::parse(value).unwrap()
+    }
+
+    #[test]
+    fn test_quiet_hours_end() {
+        let time = |value| crate::availability::parse_time(value).unwrap();
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        let (night_start, night_end) = (time("22:00"), time("07:00"));
+
+        // 23:30 and 06:00 in Berlin (UTC+1 in December) are both within the night
+        assert_eq!(
+            quiet_hours_end(night_start, night_end, berlin, at("2025-12-01T22:30:00Z")),
+            Some(at("2025-12-02T06:00:00Z"))
+        );
+        assert_eq!(
+            quiet_hours_end(night_start, night_end, berlin, at("2025-12-02T05:00:00Z")),
+            Some(at("2025-12-02T06:00:00Z"))
+        );
+        assert_eq!(quiet_hours_end(night_start, night_end, berlin, at("2025-12-02T06:00:00Z")), None);
+        assert_eq!(quiet_hours_end(night_start, night_end, berlin, at("2025-12-02T12:00:00Z")), None);
+
+        // Within one day
+        let (lunch_start, lunch_end) = (time("12:00"), time("13:00"));
+        assert_eq!(
+            quiet_hours_end(lunch_start, lunch_end, Tz::UTC, at("2025-12-02T12:15:00Z")),
+            Some(at("2025-12-02T13:00:00Z"))
+        );
+        assert_eq!(quiet_hours_end(lunch_start, lunch_end, Tz::UTC, at("2025-12-02T11:59:00Z")), None);
+    }
+
+    #[test]
+    fn test_push_at() {
+        let night = at("2025-12-01T23:00:00Z");
+        let morning = at("2025-12-02T06:00:00Z");
+
+        assert_eq!(push_at(&preferences(None, None), "standard", night), Some(night));
+        assert_eq!(push_at(&preferences(Some("in_app"), None), "standard", night), None);
+        assert_eq!(push_at(&preferences(None, Some(("22:00", "07:00"))), "chat", night), Some(morning));
+        assert_eq!(push_at(&preferences(None, Some(("22:00", "07:00"))), "chat", morning), Some(morning));
+
+        // Calls ring through quiet hours unless the user turned that off, and are never deferred
+        let mut quiet = preferences(None, Some(("22:00", "07:00")));
+        assert_eq!(push_at(&quiet, "call", night), Some(night));
+        quiet.calls_bypass_quiet_hours = Some(false);
+        assert_eq!(push_at(&quiet, "call", night), None);
+        assert_eq!(push_at(&quiet, "standard", night), Some(morning));
+    }
+
+    #[test]
+    fn test_push_at_by() {
+        let night = at("2025-12-01T23:00:00Z");
+        let morning = at("2025-12-02T06:00:00Z");
+        let quiet = preferences(None, Some(("22:00", "07:00")));
+
+        // A reminder for a session starting before quiet hours end is kept in-app only
+        assert_eq!(push_at_by(&quiet, "standard", night, at("2025-12-01T23:15:00Z")), None);
+        assert_eq!(push_at_by(&quiet, "standard", night, at("2025-12-02T08:00:00Z")), Some(morning));
+        // A "starting now" reminder sent just after the start still goes out when allowed
+        assert_eq!(push_at_by(&preferences(None, None), "standard", night, at("2025-12-01T22:58:00Z")), Some(night));
+        assert_eq!(push_at_by(&preferences(Some("in_app"), None), "standard", night, morning), None);
+    }
+}
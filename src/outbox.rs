@@ -0,0 +1,179 @@
+//! Durable push delivery. A notification is stored together with one pending
+//! `notification_deliveries` row per device, and this worker sends them,
+//! retrying transient failures with exponential backoff.
+//!
+//! Deliveries are claimed with `SKIP LOCKED` and a lease, so several replicas
+//! can run the worker and a crashed one only delays its batch.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+use crate::firebase::NotificationType;
+use crate::models::PendingDelivery;
+use crate::push::{PushDevice, PushError};
+use crate::AppState;
+
+/// A delivery is marked failed after this many attempts
+pub const MAX_ATTEMPTS: u32 = 8;
+
+/// Delay before the first retry; doubled for every further attempt
+const BASE_DELAY: Duration = Duration::from_secs(5);
+
+const MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Fallback for wakeups missed by this replica, e.g. notifications created by another one
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Claimed deliveries are hidden from other workers this long
+const CLAIM_LEASE: Duration = Duration::from_secs(5 * 60);
+
+const BATCH_SIZE: u32 = 100;
+
+/// Wakes the worker as soon as new deliveries are written
+#[derive(Default)]
+pub struct Outbox {
+    pending: Notify,
+}
+
+impl Outbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wake(&self) {
+        self.pending.notify_one();
+    }
+}
+
+pub async fn run_worker(state: Arc<AppState>) {
+    loop {
+        match deliver_due(&state).await {
+            // A full batch means more may be due right away
+            Ok(claimed) if claimed == BATCH_SIZE as usize => continue,
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to deliver pending notifications: {}", e),
+        }
+
+        tokio::select! {
+            _ = state.outbox.pending.notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// Claim and send one batch of due deliveries, returning how many were claimed
+async fn deliver_due(state: &Arc<AppState>) -> Result<usize, String> {
+    let now = chrono::Utc::now().naive_utc();
+    let lease_until = now + chrono::Duration::from_std(CLAIM_LEASE).unwrap_or_default();
+    let deliveries = crate::db::claim_due_deliveries(&state.db, now, lease_until, BATCH_SIZE)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for delivery in &deliveries {
+        deliver(state, delivery).await?;
+    }
+    Ok(deliveries.len())
+}
+
+async fn deliver(state: &Arc<AppState>, delivery: &PendingDelivery) -> Result<(), String> {
+    // Payloads are validated before they are stored, so this only guards against hand-edited rows
+    let payload = NotificationType::from_stored(&delivery.notification_type, delivery.data.as_deref()).unwrap_or_else(|e| {
+        tracing::warn!("Sending notification {} as standard: {}", delivery.notification_id, e);
+        NotificationType::Standard
+    });
+    let result = match PushDevice::from_stored(
+        &delivery.provider,
+        &delivery.device_token,
+        delivery.web_push_p256dh.as_deref(),
+        delivery.web_push_auth.as_deref(),
+    ) {
+        Ok(device) => crate::push::send(state, device, &delivery.title, &delivery.body, &payload).await,
+        Err(e) => Err(e),
+    };
+
+    let (status, next_attempt_at, error) = match result {
+        Ok(()) => {
+            state.metrics.increment_notifications_sent();
+            ("sent", None, None)
+        }
+        Err(e) => {
+            if e.is_token_invalid() {
+                tracing::info!("Removing device token of notification {}: {}", delivery.notification_id, e);
+                crate::db::delete_device_token(&state.db, &delivery.device_token)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            match retry_delay(&e, delivery.attempts + 1, rand::random()) {
+                Some(delay) => {
+                    tracing::debug!("Retrying delivery {} in {:?}: {}", delivery.id, delay, e);
+                    let next_attempt_at = chrono::Utc::now().naive_utc() + chrono::Duration::from_std(delay).unwrap_or_default();
+                    ("pending", Some(next_attempt_at), Some(e.to_string()))
+                }
+                None => {
+                    tracing::warn!("Giving up on delivery {} of notification {}: {}", delivery.id, delivery.notification_id, e);
+                    ("failed", None, Some(e.to_string()))
+                }
+            }
+        }
+    };
+
+    crate::db::record_delivery_attempt(&state.db, delivery.id, status, next_attempt_at, error.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// How long to wait before retrying after the `attempts`-th attempt failed with `error`, or `None`
+/// to give up. `jitter` in `[0, 1)` spreads the upper half of the backoff so retries don't align.
+fn retry_delay(error: &PushError, attempts: u32, jitter: f64) -> Option<Duration> {
+    if !error.is_retryable() || error.is_token_invalid() || attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+
+    let backoff = BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_DELAY);
+    let delay = backoff / 2 + backoff.mul_f64(jitter.clamp(0.0, 1.0) / 2.0);
+    Some(delay.max(error.retry_after().unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firebase::FcmError;
+    use crate::webpush::WebPushError;
+
+    fn unavailable(retry_after: Option<u64>) -> PushError {
+        FcmError::Unavailable { retry_after: retry_after.map(Duration::from_secs) }.into()
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        let error = unavailable(None);
+        assert_eq!(retry_delay(&error, 1, 0.0), Some(Duration::from_millis(2500)));
+        assert_eq!(retry_delay(&error, 1, 1.0), Some(Duration::from_secs(5)));
+        assert_eq!(retry_delay(&error, 3, 0.0), Some(Duration::from_secs(10)));
+        assert_eq!(retry_delay(&error, 3, 0.5), Some(Duration::from_secs(15)));
+
+        let other = FcmError::Other("boom".to_string()).into();
+        assert_eq!(retry_delay(&other, MAX_ATTEMPTS - 1, 1.0), Some(Duration::from_secs(320)));
+    }
+
+    #[test]
+    fn test_retry_delay_honours_retry_after() {
+        assert_eq!(retry_delay(&unavailable(Some(120)), 1, 0.5), Some(Duration::from_secs(120)));
+        let quota = FcmError::QuotaExceeded { retry_after: Some(Duration::from_secs(1)) }.into();
+        assert_eq!(retry_delay(&quota, 2, 0.0), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_retry_delay_gives_up() {
+        assert_eq!(retry_delay(&unavailable(None), MAX_ATTEMPTS, 0.0), None);
+        assert_eq!(retry_delay(&FcmError::Unregistered.into(), 1, 0.0), None);
+        let payload = FcmError::InvalidArgument { message: "bad".to_string(), token_invalid: false }.into();
+        assert_eq!(retry_delay(&payload, 1, 0.0), None);
+        assert_eq!(retry_delay(&WebPushError::Gone.into(), 1, 0.0), None);
+        assert_eq!(retry_delay(&WebPushError::PayloadTooLarge.into(), 1, 0.0), None);
+    }
+}
@@ -0,0 +1,246 @@
+use tonic::Status;
+
+use crate::auth::{check_role, CallerContext};
+
+/// Who may call a method once their token has been verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Any verified token, even before the caller has a `users` row
+    Authenticated,
+    /// Registered callers whose `users.role` is listed (admin always passes)
+    Roles(&'static [&'static str]),
+}
+
+const ANY_ROLE: Access = Access::Roles(&["user", "mentor", "admin"]);
+const MENTOR: Access = Access::Roles(&["mentor"]);
+const ADMIN: Access = Access::Roles(&["admin"]);
+
+/// Access policy for every `LinkWithMentor` method, keyed by gRPC path.
+/// Methods missing from this table are denied.
+pub const METHOD_POLICIES: &[(&str, Access)] = &[
+    ("/service.LinkWithMentor/Ping", Access::Authenticated),
+    ("/service.LinkWithMentor/HealthCheck", Access::Authenticated),
+    ("/service.LinkWithMentor/GetMetrics", ADMIN),
+    ("/service.LinkWithMentor/CreateUser", Access::Authenticated),
+    ("/service.LinkWithMentor/GetUser", ANY_ROLE),
+    ("/service.LinkWithMentor/SetTimeZone", ANY_ROLE),
+    ("/service.LinkWithMentor/CreateSession", ANY_ROLE),
+    ("/service.LinkWithMentor/GetUserSessions", ANY_ROLE),
+    ("/service.LinkWithMentor/CreateMentorProfile", MENTOR),
+    ("/service.LinkWithMentor/UpdateMentorProfile", MENTOR),
+    ("/service.LinkWithMentor/GetMentorProfile", ANY_ROLE),
+    ("/service.LinkWithMentor/DeleteMentorProfile", MENTOR),
+    ("/service.LinkWithMentor/SetMentorVerification", ADMIN),
+    ("/service.LinkWithMentor/ApplyForMentor", Access::Roles(&["user"])),
+    ("/service.LinkWithMentor/ListMentorApplications", ADMIN),
+    ("/service.LinkWithMentor/ReviewMentorApplication", ADMIN),
+    ("/service.LinkWithMentor/SetWeeklyAvailability", MENTOR),
+    ("/service.LinkWithMentor/SetDateAvailability", MENTOR),
+    ("/service.LinkWithMentor/GetMentorAvailability", ANY_ROLE),
+    ("/service.LinkWithMentor/ListBookableSlots", ANY_ROLE),
+    ("/service.LinkWithMentor/SearchMentors", ANY_ROLE),
+    ("/service.LinkWithMentor/SubmitReview", ANY_ROLE),
+    ("/service.LinkWithMentor/EditReview", ANY_ROLE),
+    ("/service.LinkWithMentor/ListMentorReviews", ANY_ROLE),
+    ("/service.LinkWithMentor/SetReviewVisibility", ADMIN),
+    ("/service.LinkWithMentor/StartSession", ANY_ROLE),
+    ("/service.LinkWithMentor/CompleteSession", ANY_ROLE),
+    ("/service.LinkWithMentor/CancelSession", ANY_ROLE),
+    ("/service.LinkWithMentor/RescheduleSession", ANY_ROLE),
+    ("/service.LinkWithMentor/ExportSessionInvite", ANY_ROLE),
+    ("/service.LinkWithMentor/CreateCalendarFeed", ANY_ROLE),
+    ("/service.LinkWithMentor/RevokeCalendarFeed", ANY_ROLE),
+    ("/service.LinkWithMentor/SendNotification", MENTOR),
+    ("/service.LinkWithMentor/GetUnreadNotifications", ANY_ROLE),
+    ("/service.LinkWithMentor/BroadcastNotification", ADMIN),
+    ("/service.LinkWithMentor/ListNotifications", ANY_ROLE),
+    ("/service.LinkWithMentor/MarkNotificationRead", ANY_ROLE),
+    ("/service.LinkWithMentor/MarkNotificationsRead", ANY_ROLE),
+    ("/service.LinkWithMentor/MarkAllNotificationsRead", ANY_ROLE),
+    ("/service.LinkWithMentor/DeleteNotification", ANY_ROLE),
+    ("/service.LinkWithMentor/GetNotificationDeliveries", ANY_ROLE),
+    ("/service.LinkWithMentor/SubscribeNotifications", ANY_ROLE),
+    ("/service.LinkWithMentor/GetNotificationPreferences", ANY_ROLE),
+    ("/service.LinkWithMentor/UpdateNotificationPreferences", ANY_ROLE),
+    ("/service.LinkWithMentor/RegisterDeviceToken", ANY_ROLE),
+];
+
+pub fn access_for(path: &str) -> Option<Access> {
+    METHOD_POLICIES
+        .iter()
+        .find(|(method, _)| *method == path)
+        .map(|(_, access)| *access)
+}
+
+/// Check the caller against the policy table entry for `path`
+pub fn authorize_method(path: &str, caller: &CallerContext) -> Result<(), Status> {
+    let access = access_for(path)
+        .ok_or_else(|| Status::permission_denied(format!("No access policy for {}", path)))?;
+
+    let roles = match access {
+        Access::Authenticated => return Ok(()),
+        Access::Roles(roles) => roles,
+    };
+
+    caller.user_id()?;
+    let role = caller_role(caller);
+
+    if roles.iter().any(|required| check_role(role, required).is_ok()) {
+        Ok(())
+    } else {
+        Err(Status::permission_denied(format!(
+            "{} requires role {}, caller has role {}",
+            path.rsplit('/').next().unwrap_or(path),
+            roles.join(" or "),
+            role
+        )))
+    }
+}
+
+/// Allow access to another user's data only for that user or an admin
+pub fn require_owner(caller: &CallerContext, owner_id: u64, resource: &str) -> Result<(), Status> {
+    if caller.user_id()? == owner_id || is_admin(caller) {
+        Ok(())
+    } else {
+        Err(Status::permission_denied(format!("{} belongs to another user", resource)))
+    }
+}
+
+/// Mentor accounts are visible to everyone, other accounts to themselves, mentors and admins
+pub fn require_user_visible(caller: &CallerContext, user: &crate::models::User) -> Result<(), Status> {
+    if user.role == "mentor" || check_role(caller_role(caller), "mentor").is_ok() {
+        return Ok(());
+    }
+    require_owner(caller, user.id, "User")
+}
+
+/// Allow session changes only for its participants (just the mentor when `mentor_only`) or an admin
+pub fn require_session_participant(
+    caller: &CallerContext,
+    session: &crate::models::Session,
+    mentor_only: bool,
+) -> Result<(), Status> {
+    let caller_id = caller.user_id()?;
+    let participant = caller_id == session.mentor_id || (!mentor_only && caller_id == session.user_id);
+
+    if participant || is_admin(caller) {
+        Ok(())
+    } else if mentor_only && caller_id == session.user_id {
+        Err(Status::permission_denied("Only the session's mentor may do this"))
+    } else {
+        Err(Status::permission_denied("Session belongs to other users"))
+    }
+}
+
+pub fn is_admin(caller: &CallerContext) -> bool {
+    check_role(caller_role(caller), "admin").is_ok()
+}
+
+fn caller_role(caller: &CallerContext) -> &str {
+    caller.user.as_ref().map(|user| user.role.as_str()).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthenticatedUser;
+
+    fn caller(id: u64, role: Option<&str>) -> CallerContext {
+        let now = chrono::Utc::now().naive_utc();
+        CallerContext {
+            identity: AuthenticatedUser {
+                uid: format!("uid{}", id),
+                email: None,
+                email_verified: false,
+                auth_time: 0,
+                custom_claims: serde_json::Map::new(),
+            },
+            user: role.map(|role| crate::models::User {
+                id,
+                firebase_uid: format!("uid{}", id),
+                email: format!("user{}@example.com", id),
+                display_name: None,
+                photo_url: None,
+                role: role.to_string(),
+                time_zone: "UTC".to_string(),
+                created_at: now,
+                updated_at: now,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_every_rpc_has_a_policy() {
+        let proto = include_str!("../proto/service.proto");
+        let service = proto
+            .split("service LinkWithMentor {")
+            .nth(1)
+            .and_then(|rest| rest.split('}').next())
+            .unwrap();
+
+        for line in service.lines() {
+            if let Some(rpc) = line.trim().strip_prefix("rpc ") {
+                let name = rpc.split([' ', '(']).next().unwrap();
+                let path = format!("/service.LinkWithMentor/{}", name);
+                assert!(access_for(&path).is_some(), "missing policy for {}", path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_authorize_method() {
+        let metrics = "/service.LinkWithMentor/GetMetrics";
+        assert!(authorize_method(metrics, &caller(1, Some("admin"))).is_ok());
+        assert_eq!(
+            authorize_method(metrics, &caller(1, Some("mentor"))).unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+
+        let send = "/service.LinkWithMentor/SendNotification";
+        assert!(authorize_method(send, &caller(1, Some("mentor"))).is_ok());
+        assert!(authorize_method(send, &caller(1, Some("user"))).is_err());
+
+        let create = "/service.LinkWithMentor/CreateUser";
+        assert!(authorize_method(create, &caller(1, None)).is_ok());
+        assert!(authorize_method("/service.LinkWithMentor/GetUser", &caller(1, None)).is_err());
+
+        assert!(authorize_method("/service.LinkWithMentor/Unknown", &caller(1, Some("admin"))).is_err());
+    }
+
+    #[test]
+    fn test_require_owner() {
+        assert!(require_owner(&caller(1, Some("user")), 1, "Notification").is_ok());
+        assert!(require_owner(&caller(1, Some("user")), 2, "Notification").is_err());
+        assert!(require_owner(&caller(1, Some("mentor")), 2, "Notification").is_err());
+        assert!(require_owner(&caller(1, Some("admin")), 2, "Notification").is_ok());
+    }
+
+    #[test]
+    fn test_require_session_participant() {
+        let now = chrono::Utc::now().naive_utc();
+        let session = crate::models::Session {
+            id: 1,
+            user_id: 1,
+            mentor_id: 2,
+            title: "Intro".to_string(),
+            description: None,
+            scheduled_at: now,
+            duration_minutes: 60,
+            status: "scheduled".to_string(),
+            meeting_link: None,
+            cancelled_by: None,
+            cancellation_reason: None,
+            series_id: None,
+            original_scheduled_at: None,
+            sequence: 0,
+            created_at: now,
+            updated_at: now,
+        };
+
+        assert!(require_session_participant(&caller(1, Some("user")), &session, false).is_ok());
+        assert!(require_session_participant(&caller(1, Some("user")), &session, true).is_err());
+        assert!(require_session_participant(&caller(2, Some("mentor")), &session, true).is_ok());
+        assert!(require_session_participant(&caller(3, Some("mentor")), &session, false).is_err());
+        assert!(require_session_participant(&caller(3, Some("admin")), &session, true).is_ok());
+    }
+}
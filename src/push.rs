@@ -0,0 +1,107 @@
+//! The push providers a device can be registered with: FCM for app tokens and
+//! FCM web tokens, or native Web Push for a browser's `PushSubscription`. The
+//! outbox and broadcasts send through `send`, whatever the provider.
+
+use crate::firebase::{FcmError, NotificationType};
+use crate::webpush::{Subscription, WebPushError};
+use crate::AppState;
+
+/// Values allowed by the `device_tokens.provider` ENUM
+pub const PROVIDERS: &[&str] = &["fcm", "web_push"];
+
+/// A stored device token, resolved to what its provider needs
+#[derive(Debug, Clone, Copy)]
+pub enum PushDevice<'a> {
+    Fcm { token: &'a str },
+    /// The token is the subscription's endpoint
+    WebPush(Subscription<'a>),
+}
+
+impl<'a> PushDevice<'a> {
+    /// From the `device_tokens` columns; the Web Push keys are NULL for FCM tokens
+    pub fn from_stored(
+        provider: &str,
+        token: &'a str,
+        p256dh: Option<&'a str>,
+        auth: Option<&'a str>,
+    ) -> Result<Self, PushError> {
+        match (provider, p256dh, auth) {
+            ("web_push", Some(p256dh), Some(auth)) => Ok(PushDevice::WebPush(Subscription { endpoint: token, p256dh, auth })),
+            ("web_push", _, _) => Err(WebPushError::InvalidSubscription("keys are missing".to_string()).into()),
+            _ => Ok(PushDevice::Fcm { token }),
+        }
+    }
+}
+
+/// Why a push failed, from whichever provider sent it
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushError {
+    Fcm(FcmError),
+    WebPush(WebPushError),
+}
+
+impl PushError {
+    /// Whether the device token will never work again and should be removed
+    pub fn is_token_invalid(&self) -> bool {
+        match self {
+            PushError::Fcm(e) => e.is_token_invalid(),
+            PushError::WebPush(e) => e.is_token_invalid(),
+        }
+    }
+
+    /// Whether the same message may succeed if sent again later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PushError::Fcm(e) => e.is_retryable(),
+            PushError::WebPush(e) => e.is_retryable(),
+        }
+    }
+
+    /// How long the provider asked us to wait before retrying
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            PushError::Fcm(e) => e.retry_after(),
+            PushError::WebPush(e) => e.retry_after(),
+        }
+    }
+}
+
+impl From<FcmError> for PushError {
+    fn from(e: FcmError) -> Self {
+        PushError::Fcm(e)
+    }
+}
+
+impl From<WebPushError> for PushError {
+    fn from(e: WebPushError) -> Self {
+        PushError::WebPush(e)
+    }
+}
+
+impl std::fmt::Display for PushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushError::Fcm(e) => write!(f, "{}", e),
+            PushError::WebPush(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PushError {}
+
+/// Send one notification to a device through its provider
+pub async fn send(
+    state: &AppState,
+    device: PushDevice<'_>,
+    title: &str,
+    body: &str,
+    payload: &NotificationType,
+) -> Result<(), PushError> {
+    match device {
+        PushDevice::Fcm { token } => Ok(state.firebase.send_notification(token, title, body, payload).await?),
+        PushDevice::WebPush(subscription) => {
+            let client = state.web_push.as_ref().ok_or(WebPushError::NotConfigured)?;
+            Ok(client.send_notification(subscription, title, body, payload).await?)
+        }
+    }
+}
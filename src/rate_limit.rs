@@ -24,7 +24,7 @@ impl RateLimiter {
         let mut requests = self.requests.lock().unwrap();
         let now = Instant::now();
         
-        let user_requests = requests.entry(identifier.to_string()).or_insert_with(Vec::new);
+        let user_requests = requests.entry(identifier.to_string()).or_default();
         
         // Remove old requests outside the window
         user_requests.retain(|&time| now.duration_since(time) < self.window);
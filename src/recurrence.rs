@@ -0,0 +1,303 @@
+//! Weekly recurrence rules, a subset of RFC 5545 `RRULE`.
+//!
+//! Supported parts are `FREQ=WEEKLY` (required), `INTERVAL`, `BYDAY` with plain
+//! weekdays, `WKST=MO` and exactly one of `COUNT` or `UNTIL`. Occurrences keep
+//! their wall-clock time in the series' time zone across daylight-saving changes.
+
+use std::fmt;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use tonic::Status;
+
+/// Most occurrences a single series may have
+pub const MAX_OCCURRENCES: usize = 52;
+
+/// Most weeks between occurrences
+pub const MAX_INTERVAL: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrenceEnd {
+    Count(u32),
+    /// Last local date that may have an occurrence (`UNTIL=20251231`)
+    UntilDate(NaiveDate),
+    /// Last UTC instant an occurrence may start at (`UNTIL=20251231T180000Z`)
+    UntilInstant(NaiveDateTime),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    /// Weeks between occurrences, 1 for weekly and 2 for biweekly
+    pub interval: u32,
+    /// Days of the week with an occurrence, Monday first; empty means the first occurrence's weekday
+    pub by_day: Vec<Weekday>,
+    pub end: RecurrenceEnd,
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    Some(match value {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+impl RecurrenceRule {
+    /// Parse an `RRULE` value such as `FREQ=WEEKLY;BYDAY=TU;COUNT=10`, with or without the `RRULE:` prefix
+    pub fn parse(value: &str) -> Result<Self, Status> {
+        let invalid = |message: String| Status::invalid_argument(format!("Invalid recurrence rule: {}", message));
+
+        let value = value.trim();
+        let value = value.strip_prefix("RRULE:").unwrap_or(value);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut count = None;
+        let mut until = None;
+
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected KEY=VALUE, got {:?}", part)))?;
+
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => frequency = Some(value.to_ascii_uppercase()),
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| (1..=MAX_INTERVAL).contains(interval))
+                        .ok_or_else(|| invalid(format!("INTERVAL must be between 1 and {}", MAX_INTERVAL)))?;
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let weekday = parse_weekday(&day.to_ascii_uppercase())
+                            .ok_or_else(|| invalid(format!("unsupported BYDAY value {:?}", day)))?;
+                        if !by_day.contains(&weekday) {
+                            by_day.push(weekday);
+                        }
+                    }
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|count| (1..=MAX_OCCURRENCES as u32).contains(count))
+                            .ok_or_else(|| invalid(format!("COUNT must be between 1 and {}", MAX_OCCURRENCES)))?,
+                    );
+                }
+                "UNTIL" => {
+                    until = Some(if let Some(instant) = value.strip_suffix('Z') {
+                        RecurrenceEnd::UntilInstant(
+                            NaiveDateTime::parse_from_str(instant, "%Y%m%dT%H%M%S")
+                                .map_err(|_| invalid(format!("bad UNTIL {:?}", value)))?,
+                        )
+                    } else {
+                        RecurrenceEnd::UntilDate(
+                            NaiveDate::parse_from_str(value, "%Y%m%d")
+                                .map_err(|_| invalid(format!("UNTIL must be YYYYMMDD or YYYYMMDDTHHMMSSZ, got {:?}", value)))?,
+                        )
+                    });
+                }
+                // Weeks start on Monday, which is also the RFC 5545 default
+                "WKST" if value.eq_ignore_ascii_case("MO") => {}
+                other => return Err(invalid(format!("{}={} is not supported", other, value))),
+            }
+        }
+
+        if frequency.as_deref() != Some("WEEKLY") {
+            return Err(invalid("only FREQ=WEEKLY is supported".to_string()));
+        }
+        by_day.sort_by_key(|weekday| weekday.num_days_from_monday());
+
+        let end = match (count, until) {
+            (Some(count), None) => RecurrenceEnd::Count(count),
+            (None, Some(until)) => until,
+            (Some(_), Some(_)) => return Err(invalid("COUNT and UNTIL can't both be set".to_string())),
+            (None, None) => return Err(invalid("COUNT or UNTIL is required".to_string())),
+        };
+
+        Ok(Self { interval, by_day, end })
+    }
+
+    /// Start times in UTC of every occurrence, the first being `first`, with wall-clock times kept in `time_zone`
+    pub fn occurrences(&self, first: NaiveDateTime, time_zone: Tz) -> Result<Vec<NaiveDateTime>, Status> {
+        let local_first = time_zone.from_utc_datetime(&first).naive_local();
+        let first_date = local_first.date();
+
+        let by_day = if self.by_day.is_empty() { vec![first_date.weekday()] } else { self.by_day.clone() };
+        if !by_day.contains(&first_date.weekday()) {
+            return Err(Status::invalid_argument(format!(
+                "The first session is on a {}, which BYDAY doesn't include",
+                first_date.weekday()
+            )));
+        }
+
+        let week_start = first_date - Duration::days(first_date.weekday().num_days_from_monday().into());
+        let mut occurrences = Vec::new();
+
+        for week in (0..).step_by(self.interval as usize) {
+            for weekday in &by_day {
+                let date = week_start + Duration::weeks(week) + Duration::days(weekday.num_days_from_monday().into());
+                if date < first_date {
+                    continue;
+                }
+
+                let at = if date == first_date {
+                    first
+                } else {
+                    crate::availability::local_to_utc(time_zone, date.and_time(local_first.time()))
+                        .ok_or_else(|| Status::internal("Failed to resolve occurrence time"))?
+                };
+                let done = match self.end {
+                    RecurrenceEnd::Count(count) => occurrences.len() >= count as usize,
+                    RecurrenceEnd::UntilDate(until) => date > until,
+                    RecurrenceEnd::UntilInstant(until) => at > until,
+                };
+                if done {
+                    return Ok(occurrences);
+                }
+                if occurrences.len() == MAX_OCCURRENCES {
+                    return Err(Status::invalid_argument(format!(
+                        "A series can have at most {} sessions",
+                        MAX_OCCURRENCES
+                    )));
+                }
+
+                occurrences.push(at);
+            }
+        }
+
+        unreachable!("the loop returns once the series ends or grows too long")
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    /// The canonical `RRULE` value, without the `RRULE:` prefix
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ=WEEKLY")?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<_> = self.by_day.iter().map(|weekday| weekday_code(*weekday)).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        match self.end {
+            RecurrenceEnd::Count(count) => write!(f, ";COUNT={}", count),
+            RecurrenceEnd::UntilDate(date) => write!(f, ";UNTIL={}", date.format("%Y%m%d")),
+            RecurrenceEnd::UntilInstant(at) => write!(f, ";UNTIL={}Z", at.format("%Y%m%dT%H%M%S")),
+        }
+    }
+}
+
+/// Move a UTC instant by `delta` of wall-clock time in `time_zone`, so a session
+/// moved from 18:00 to 19:00 stays at 19:00 local time across daylight-saving changes
+pub fn shift_wall_clock(at: NaiveDateTime, delta: Duration, time_zone: Tz) -> NaiveDateTime {
+    let local = time_zone.from_utc_datetime(&at).naive_local() + delta;
+    crate::availability::local_to_utc(time_zone, local).unwrap_or(at + delta)
+}
+
+/// The wall-clock difference in `time_zone` between two UTC instants
+pub fn wall_clock_delta(from: NaiveDateTime, to: NaiveDateTime, time_zone: Tz) -> Duration {
+    time_zone.from_utc_datetime(&to).naive_local() - time_zone.from_utc_datetime(&from).naive_local()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> NaiveDateTime {
+        crate::timestamp::parse(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let rule = RecurrenceRule::parse("RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TH,TU;COUNT=10").unwrap();
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.by_day, vec![Weekday::Tue, Weekday::Thu]);
+        assert_eq!(rule.end, RecurrenceEnd::Count(10));
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;COUNT=10");
+
+        let rule = RecurrenceRule::parse("FREQ=WEEKLY;UNTIL=20251231T180000Z").unwrap();
+        assert_eq!(rule.end, RecurrenceEnd::UntilInstant(utc("2025-12-31T18:00:00Z")));
+        assert_eq!(RecurrenceRule::parse(&rule.to_string()).unwrap(), rule);
+
+        for invalid in [
+            "FREQ=DAILY;COUNT=3",
+            "FREQ=WEEKLY",
+            "FREQ=WEEKLY;COUNT=3;UNTIL=20251231",
+            "FREQ=WEEKLY;COUNT=0",
+            "FREQ=WEEKLY;COUNT=53",
+            "FREQ=WEEKLY;INTERVAL=5;COUNT=3",
+            "FREQ=WEEKLY;BYDAY=1TU;COUNT=3",
+            "FREQ=WEEKLY;BYMONTH=1;COUNT=3",
+        ] {
+            assert!(RecurrenceRule::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_weekly_occurrences_keep_local_time_across_dst() {
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        let rule = RecurrenceRule::parse("FREQ=WEEKLY;COUNT=3").unwrap();
+
+        // Tuesdays at 18:00 in Berlin; clocks go back on 2025-10-26
+        let occurrences = rule.occurrences(utc("2025-10-21T16:00:00Z"), berlin).unwrap();
+        assert_eq!(
+            occurrences,
+            vec![utc("2025-10-21T16:00:00Z"), utc("2025-10-28T17:00:00Z"), utc("2025-11-04T17:00:00Z")]
+        );
+    }
+
+    #[test]
+    fn test_biweekly_by_day_until() {
+        let rule = RecurrenceRule::parse("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;UNTIL=20251113").unwrap();
+
+        let occurrences = rule.occurrences(utc("2025-10-21T18:00:00Z"), Tz::UTC).unwrap();
+        assert_eq!(
+            occurrences,
+            vec![
+                utc("2025-10-21T18:00:00Z"),
+                utc("2025-10-23T18:00:00Z"),
+                utc("2025-11-04T18:00:00Z"),
+                utc("2025-11-06T18:00:00Z"),
+            ]
+        );
+
+        // Starting on a Wednesday doesn't match BYDAY
+        assert!(rule.occurrences(utc("2025-10-22T18:00:00Z"), Tz::UTC).is_err());
+        // An UNTIL far in the future would produce too many sessions
+        let long = RecurrenceRule::parse("FREQ=WEEKLY;UNTIL=20301231").unwrap();
+        assert!(long.occurrences(utc("2025-10-21T18:00:00Z"), Tz::UTC).is_err());
+    }
+
+    #[test]
+    fn test_shift_wall_clock() {
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        let delta = wall_clock_delta(utc("2025-10-21T16:00:00Z"), utc("2025-10-22T17:00:00Z"), berlin);
+        assert_eq!(delta, Duration::hours(25));
+
+        // 18:00 on Tuesday after the clock change moves to 19:00 on Wednesday, still local time
+        assert_eq!(shift_wall_clock(utc("2025-10-28T17:00:00Z"), delta, berlin), utc("2025-10-29T18:00:00Z"));
+    }
+}
@@ -0,0 +1,192 @@
+//! Background scheduler that reminds both participants before a session
+//! starts and again when it starts.
+//!
+//! Every reminder is claimed by inserting a `session_reminders` row before it
+//! is sent, so restarts and other replicas never send it twice.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+
+use crate::models::Session;
+use crate::AppState;
+
+/// Used when `REMINDER_OFFSETS` is unset
+pub const DEFAULT_OFFSETS: &str = "24h,15m";
+
+/// Offsets are capped so the due-session queries stay cheap
+pub const MAX_OFFSET_MINUTES: u32 = 7 * 24 * 60;
+
+/// How often the scheduler looks for due reminders
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A "starting now" ping is still sent this long after the start, e.g. after a restart
+const START_GRACE_MINUTES: i64 = 5;
+
+/// Most sessions handled per offset and poll; the rest are picked up by the next poll
+const BATCH_SIZE: u32 = 500;
+
+/// Parse comma-separated lead times such as `24h,15m` or `2d` into minutes before the start
+pub fn parse_offsets(value: &str) -> Result<Vec<u32>, String> {
+    let mut offsets = Vec::new();
+    for item in value.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let unit_at = item.len() - item.chars().last().map_or(0, char::len_utf8);
+        let (amount, unit) = item.split_at(unit_at);
+        let multiplier = match unit {
+            "m" => 1,
+            "h" => 60,
+            "d" => 24 * 60,
+            _ => return Err(format!("Invalid reminder offset {:?}, expected a number with m, h or d", item)),
+        };
+        let minutes = amount
+            .parse::<u32>()
+            .ok()
+            .and_then(|amount| amount.checked_mul(multiplier))
+            .filter(|minutes| (1..=MAX_OFFSET_MINUTES).contains(minutes))
+            .ok_or_else(|| format!("Reminder offset {:?} must be between 1m and {}d", item, MAX_OFFSET_MINUTES / (24 * 60)))?;
+        offsets.push(minutes);
+    }
+    Ok(offsets)
+}
+
+/// Send reminders `offsets` minutes before each scheduled session, plus one when it starts
+pub async fn run_scheduler(state: Arc<AppState>, mut offsets: Vec<u32>) {
+    offsets.push(0);
+    offsets.sort_unstable();
+    offsets.dedup();
+
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        poll.tick().await;
+        if let Err(e) = send_due_reminders(&state, &offsets).await {
+            tracing::error!("Failed to send session reminders: {}", e);
+        }
+    }
+}
+
+async fn send_due_reminders(state: &Arc<AppState>, offsets: &[u32]) -> Result<(), String> {
+    let now = chrono::Utc::now().naive_utc();
+
+    // Offsets are ascending, so each session's list starts with the reminder closest to its start
+    let mut due: BTreeMap<u64, (Session, Vec<u32>)> = BTreeMap::new();
+    for &offset in offsets {
+        let (from, to) = due_window(now, offset);
+        let sessions = crate::db::get_sessions_due_for_reminder(&state.db, offset, from, to, BATCH_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        for session in sessions {
+            due.entry(session.id).or_insert_with(|| (session, Vec::new())).1.push(offset);
+        }
+    }
+
+    for (session, offsets) in due.into_values() {
+        remind(state, &session, &offsets, now).await?;
+    }
+    Ok(())
+}
+
+/// Send the closest due reminder, recording the earlier ones it supersedes as skipped
+/// so a session booked at short notice doesn't get a burst of them
+async fn remind(state: &Arc<AppState>, session: &Session, offsets: &[u32], now: NaiveDateTime) -> Result<(), String> {
+    let Some((&offset, superseded)) = offsets.split_first() else {
+        return Ok(());
+    };
+
+    let claimed = crate::db::claim_session_reminder(&state.db, session.id, session.scheduled_at, offset, false)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !claimed {
+        return Ok(());
+    }
+    for &skipped in superseded {
+        crate::db::claim_session_reminder(&state.db, session.id, session.scheduled_at, skipped, true)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let (title, body) = reminder_message(&session.title, session.scheduled_at - now);
+    for recipient in [session.user_id, session.mentor_id] {
+        let notification = crate::models::CreateNotification {
+            user_id: recipient,
+            title: title.clone(),
+            body: body.clone(),
+            notification_type: "standard".to_string(),
+            data: Some(serde_json::json!({ "session_id": session.id, "reminder_minutes": offset }).to_string()),
+        };
+        // A reminder pushed once quiet hours end could arrive after the session started
+        if let Err(status) = crate::api::deliver_notification_by(state, notification, Some(session.scheduled_at)).await {
+            tracing::warn!("Failed to remind user {} of session {}: {}", recipient, session.id, status.message());
+        }
+    }
+
+    tracing::debug!("Sent {}-minute reminder for session {}", offset, session.id);
+    Ok(())
+}
+
+/// Sessions starting in `(from, to]` are due for the reminder `offset` minutes before their start
+fn due_window(now: NaiveDateTime, offset: u32) -> (NaiveDateTime, NaiveDateTime) {
+    if offset == 0 {
+        (now - chrono::Duration::minutes(START_GRACE_MINUTES), now)
+    } else {
+        (now, now + chrono::Duration::minutes(offset.into()))
+    }
+}
+
+/// Title and body for a reminder sent `starts_in` before the session
+fn reminder_message(session_title: &str, starts_in: chrono::Duration) -> (String, String) {
+    if starts_in <= chrono::Duration::zero() {
+        return ("Session starting now".to_string(), format!("{} is starting now", session_title));
+    }
+
+    // Round up so a reminder sent a few seconds late still reads "15 minutes"
+    let minutes = (starts_in.num_seconds() + 59) / 60;
+    let lead = match minutes {
+        1 => "1 minute".to_string(),
+        2..90 => format!("{} minutes", minutes),
+        90..2880 => format!("{} hours", (minutes + 30) / 60),
+        _ => format!("{} days", (minutes + 720) / 1440),
+    };
+    ("Upcoming session".to_string(), format!("{} starts in {}", session_title, lead))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_offsets() {
+        assert_eq!(parse_offsets(DEFAULT_OFFSETS).unwrap(), vec![1440, 15]);
+        assert_eq!(parse_offsets(" 2d , 90m ").unwrap(), vec![2880, 90]);
+        assert!(parse_offsets("").unwrap().is_empty());
+        assert!(parse_offsets("15").is_err());
+        assert!(parse_offsets("0m").is_err());
+        assert!(parse_offsets("8d").is_err());
+        assert!(parse_offsets("é").is_err());
+    }
+
+    #[test]
+    fn test_due_window() {
+        let now = crate::timestamp::parse("2025-12-01T10:00:00Z").unwrap();
+
+        let (from, to) = due_window(now, 15);
+        assert_eq!((from, to), (now, crate::timestamp::parse("2025-12-01T10:15:00Z").unwrap()));
+
+        let (from, to) = due_window(now, 0);
+        assert_eq!((from, to), (crate::timestamp::parse("2025-12-01T09:55:00Z").unwrap(), now));
+    }
+
+    #[test]
+    fn test_reminder_message() {
+        let message = |seconds| reminder_message("Career chat", chrono::Duration::seconds(seconds)).1;
+
+        assert_eq!(message(0), "Career chat is starting now");
+        assert_eq!(message(60), "Career chat starts in 1 minute");
+        assert_eq!(message(15 * 60 - 20), "Career chat starts in 15 minutes");
+        assert_eq!(message(24 * 3600), "Career chat starts in 24 hours");
+        assert_eq!(message(3 * 24 * 3600), "Career chat starts in 3 days");
+    }
+}
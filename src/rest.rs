@@ -0,0 +1,762 @@
+//! REST/JSON routes under `/v1`, served by the HTTP/3 listener.
+//!
+//! Each route is authorized against the policy of its gRPC counterpart and
+//! delegates to the same `api` functions, so both transports behave alike.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::{Method, Request, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tonic::{Code, Status};
+use tonic_types::StatusExt;
+
+use crate::api::SessionView;
+use crate::auth::CallerContext;
+use crate::server::HttpResponse;
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    CreateUser,
+    /// User ID 0 means the caller (`/v1/users/me`)
+    GetUser(u64),
+    SetTimeZone,
+    ListSessions(u64),
+    CreateSession,
+    StartSession(u64),
+    CompleteSession(u64),
+    CancelSession(u64),
+    RescheduleSession(u64),
+    SendNotification,
+    BroadcastNotification,
+    ListUnreadNotifications(u64),
+    ListNotifications,
+    MarkNotificationRead(u64),
+    MarkNotificationsRead,
+    MarkAllNotificationsRead,
+    DeleteNotification(u64),
+    ListNotificationDeliveries(u64),
+    GetNotificationPreferences,
+    UpdateNotificationPreferences,
+    RegisterDeviceToken,
+    /// Mentor ID 0 means the caller (`/v1/mentors/me/profile`)
+    CreateMentorProfile(u64),
+    UpdateMentorProfile(u64),
+    GetMentorProfile(u64),
+    DeleteMentorProfile(u64),
+    SetMentorVerification(u64),
+    ApplyForMentor,
+    ListMentorApplications,
+    ReviewMentorApplication(u64),
+    SetWeeklyAvailability,
+    SetDateAvailability,
+    /// Mentor ID 0 means the caller (`/v1/mentors/me/availability`)
+    GetMentorAvailability(u64),
+    ListBookableSlots(u64),
+    SearchMentors,
+    SubmitReview(u64),
+    EditReview(u64),
+    /// Mentor ID 0 means the caller (`/v1/mentors/me/reviews`)
+    ListMentorReviews(u64),
+    SetReviewVisibility(u64),
+    ExportSessionInvite(u64),
+    CreateCalendarFeed,
+    RevokeCalendarFeed,
+}
+
+impl Route {
+    /// The gRPC method whose access policy this route shares
+    fn policy_method(self) -> &'static str {
+        match self {
+            Route::CreateUser => "/service.LinkWithMentor/CreateUser",
+            Route::GetUser(_) => "/service.LinkWithMentor/GetUser",
+            Route::SetTimeZone => "/service.LinkWithMentor/SetTimeZone",
+            Route::ListSessions(_) => "/service.LinkWithMentor/GetUserSessions",
+            Route::CreateSession => "/service.LinkWithMentor/CreateSession",
+            Route::StartSession(_) => "/service.LinkWithMentor/StartSession",
+            Route::CompleteSession(_) => "/service.LinkWithMentor/CompleteSession",
+            Route::CancelSession(_) => "/service.LinkWithMentor/CancelSession",
+            Route::RescheduleSession(_) => "/service.LinkWithMentor/RescheduleSession",
+            Route::SendNotification => "/service.LinkWithMentor/SendNotification",
+            Route::BroadcastNotification => "/service.LinkWithMentor/BroadcastNotification",
+            Route::ListUnreadNotifications(_) => "/service.LinkWithMentor/GetUnreadNotifications",
+            Route::ListNotifications => "/service.LinkWithMentor/ListNotifications",
+            Route::MarkNotificationRead(_) => "/service.LinkWithMentor/MarkNotificationRead",
+            Route::MarkNotificationsRead => "/service.LinkWithMentor/MarkNotificationsRead",
+            Route::MarkAllNotificationsRead => "/service.LinkWithMentor/MarkAllNotificationsRead",
+            Route::DeleteNotification(_) => "/service.LinkWithMentor/DeleteNotification",
+            Route::ListNotificationDeliveries(_) => "/service.LinkWithMentor/GetNotificationDeliveries",
+            Route::GetNotificationPreferences => "/service.LinkWithMentor/GetNotificationPreferences",
+            Route::UpdateNotificationPreferences => "/service.LinkWithMentor/UpdateNotificationPreferences",
+            Route::RegisterDeviceToken => "/service.LinkWithMentor/RegisterDeviceToken",
+            Route::CreateMentorProfile(_) => "/service.LinkWithMentor/CreateMentorProfile",
+            Route::UpdateMentorProfile(_) => "/service.LinkWithMentor/UpdateMentorProfile",
+            Route::GetMentorProfile(_) => "/service.LinkWithMentor/GetMentorProfile",
+            Route::DeleteMentorProfile(_) => "/service.LinkWithMentor/DeleteMentorProfile",
+            Route::SetMentorVerification(_) => "/service.LinkWithMentor/SetMentorVerification",
+            Route::ApplyForMentor => "/service.LinkWithMentor/ApplyForMentor",
+            Route::ListMentorApplications => "/service.LinkWithMentor/ListMentorApplications",
+            Route::ReviewMentorApplication(_) => "/service.LinkWithMentor/ReviewMentorApplication",
+            Route::SetWeeklyAvailability => "/service.LinkWithMentor/SetWeeklyAvailability",
+            Route::SetDateAvailability => "/service.LinkWithMentor/SetDateAvailability",
+            Route::GetMentorAvailability(_) => "/service.LinkWithMentor/GetMentorAvailability",
+            Route::ListBookableSlots(_) => "/service.LinkWithMentor/ListBookableSlots",
+            Route::SearchMentors => "/service.LinkWithMentor/SearchMentors",
+            Route::SubmitReview(_) => "/service.LinkWithMentor/SubmitReview",
+            Route::EditReview(_) => "/service.LinkWithMentor/EditReview",
+            Route::ListMentorReviews(_) => "/service.LinkWithMentor/ListMentorReviews",
+            Route::SetReviewVisibility(_) => "/service.LinkWithMentor/SetReviewVisibility",
+            Route::ExportSessionInvite(_) => "/service.LinkWithMentor/ExportSessionInvite",
+            Route::CreateCalendarFeed => "/service.LinkWithMentor/CreateCalendarFeed",
+            Route::RevokeCalendarFeed => "/service.LinkWithMentor/RevokeCalendarFeed",
+        }
+    }
+}
+
+/// Handle a request whose path started with `/v1`; `segments` excludes the prefix
+pub async fn handle(req: &Request<()>, body: Bytes, state: &Arc<AppState>, segments: &[&str]) -> HttpResponse {
+    let route = match parse_route(req.method(), segments) {
+        Ok(route) => route,
+        Err(response) => return response,
+    };
+
+    let caller = match crate::auth::authorize_call(state, route.policy_method(), req.headers()).await {
+        Ok(Some(caller)) => caller,
+        Ok(None) => return status_response(&Status::unauthenticated("Missing caller identity")),
+        Err(status) => return status_response(&status),
+    };
+
+    let query = req.uri().query().unwrap_or_default();
+    match dispatch(route, body, query, state, &caller).await {
+        Ok(response) => response,
+        Err(status) => status_response(&status),
+    }
+}
+
+fn parse_route(method: &Method, segments: &[&str]) -> Result<Route, HttpResponse> {
+    let route = match (method, segments) {
+        (&Method::POST, ["users"]) => Route::CreateUser,
+        (&Method::GET, ["users", id]) => Route::GetUser(parse_user_id(id)?),
+        (&Method::PUT, ["users", "me", "time-zone"]) => Route::SetTimeZone,
+        (&Method::GET, ["users", id, "sessions"]) => Route::ListSessions(parse_user_id(id)?),
+        (&Method::GET, ["users", id, "notifications", "unread"]) => {
+            Route::ListUnreadNotifications(parse_user_id(id)?)
+        }
+        (&Method::POST, ["sessions"]) => Route::CreateSession,
+        (&Method::POST, ["sessions", id, "start"]) => Route::StartSession(parse_id(id)?),
+        (&Method::POST, ["sessions", id, "complete"]) => Route::CompleteSession(parse_id(id)?),
+        (&Method::POST, ["sessions", id, "cancel"]) => Route::CancelSession(parse_id(id)?),
+        (&Method::POST, ["sessions", id, "reschedule"]) => Route::RescheduleSession(parse_id(id)?),
+        (&Method::POST, ["notifications"]) => Route::SendNotification,
+        (&Method::GET, ["notifications"]) => Route::ListNotifications,
+        (&Method::POST, ["notifications", "broadcast"]) => Route::BroadcastNotification,
+        (&Method::POST, ["notifications", "read"]) => Route::MarkNotificationsRead,
+        (&Method::POST, ["notifications", "read-all"]) => Route::MarkAllNotificationsRead,
+        (&Method::DELETE, ["notifications", id]) => Route::DeleteNotification(parse_id(id)?),
+        (&Method::POST, ["notifications", id, "read"]) => Route::MarkNotificationRead(parse_id(id)?),
+        (&Method::GET, ["notifications", id, "deliveries"]) => Route::ListNotificationDeliveries(parse_id(id)?),
+        (&Method::GET, ["notification-preferences"]) => Route::GetNotificationPreferences,
+        (&Method::PUT, ["notification-preferences"]) => Route::UpdateNotificationPreferences,
+        (&Method::POST, ["device-tokens"]) => Route::RegisterDeviceToken,
+        (&Method::POST, ["mentors", id, "profile"]) => Route::CreateMentorProfile(parse_user_id(id)?),
+        (&Method::PUT, ["mentors", id, "profile"]) => Route::UpdateMentorProfile(parse_user_id(id)?),
+        (&Method::GET, ["mentors", id, "profile"]) => Route::GetMentorProfile(parse_user_id(id)?),
+        (&Method::DELETE, ["mentors", id, "profile"]) => Route::DeleteMentorProfile(parse_user_id(id)?),
+        (&Method::PUT, ["mentors", id, "verification"]) => Route::SetMentorVerification(parse_id(id)?),
+        (&Method::POST, ["mentor-applications"]) => Route::ApplyForMentor,
+        (&Method::GET, ["mentor-applications"]) => Route::ListMentorApplications,
+        (&Method::POST, ["mentor-applications", id, "review"]) => Route::ReviewMentorApplication(parse_id(id)?),
+        (&Method::PUT, ["availability", "weekly"]) => Route::SetWeeklyAvailability,
+        (&Method::PUT, ["availability", "dates"]) => Route::SetDateAvailability,
+        (&Method::GET, ["mentors", id, "availability"]) => Route::GetMentorAvailability(parse_user_id(id)?),
+        (&Method::GET, ["mentors", id, "slots"]) => Route::ListBookableSlots(parse_id(id)?),
+        (&Method::GET, ["mentors", "search"]) => Route::SearchMentors,
+        (&Method::POST, ["sessions", id, "reviews"]) => Route::SubmitReview(parse_id(id)?),
+        (&Method::PUT, ["reviews", id]) => Route::EditReview(parse_id(id)?),
+        (&Method::GET, ["mentors", id, "reviews"]) => Route::ListMentorReviews(parse_user_id(id)?),
+        (&Method::PUT, ["reviews", id, "visibility"]) => Route::SetReviewVisibility(parse_id(id)?),
+        (&Method::GET, ["sessions", id, "invite.ics"]) => Route::ExportSessionInvite(parse_id(id)?),
+        (&Method::POST, ["calendar-feed"]) => Route::CreateCalendarFeed,
+        (&Method::DELETE, ["calendar-feed"]) => Route::RevokeCalendarFeed,
+        (
+            _,
+            ["users"]
+            | ["sessions"]
+            | ["sessions", _, "start" | "complete" | "cancel" | "reschedule" | "reviews"]
+            | ["notifications", "read" | "read-all" | "broadcast"]
+            | ["notifications", _, "read"]
+            | ["device-tokens"]
+            | ["mentor-applications", _, "review"],
+        ) => {
+            return Err(HttpResponse::method_not_allowed("POST"));
+        }
+        (_, ["mentors", _, "profile"]) => {
+            return Err(HttpResponse::method_not_allowed("GET, POST, PUT, DELETE"));
+        }
+        (_, ["mentor-applications"]) => {
+            return Err(HttpResponse::method_not_allowed("GET, POST"));
+        }
+        (_, ["calendar-feed"]) => {
+            return Err(HttpResponse::method_not_allowed("POST, DELETE"));
+        }
+        (_, ["notifications"]) => {
+            return Err(HttpResponse::method_not_allowed("GET, POST"));
+        }
+        (_, ["notifications", _]) => {
+            return Err(HttpResponse::method_not_allowed("DELETE"));
+        }
+        (_, ["notification-preferences"]) => {
+            return Err(HttpResponse::method_not_allowed("GET, PUT"));
+        }
+        (
+            _,
+            ["availability", "weekly" | "dates"]
+            | ["users", "me", "time-zone"]
+            | ["mentors", _, "verification"]
+            | ["reviews", _]
+            | ["reviews", _, "visibility"],
+        ) => {
+            return Err(HttpResponse::method_not_allowed("PUT"));
+        }
+        (
+            _,
+            ["users", _]
+            | ["users", _, "sessions"]
+            | ["users", _, "notifications", "unread"]
+            | ["notifications", _, "deliveries"]
+            | ["mentors", _, "availability" | "slots" | "reviews"]
+            | ["mentors", "search"]
+            | ["sessions", _, "invite.ics"],
+        ) => {
+            return Err(HttpResponse::method_not_allowed("GET"));
+        }
+        _ => return Err(HttpResponse::error(StatusCode::NOT_FOUND, "Not found")),
+    };
+
+    Ok(route)
+}
+
+fn parse_id(segment: &str) -> Result<u64, HttpResponse> {
+    segment
+        .parse()
+        .map_err(|_| HttpResponse::error(StatusCode::BAD_REQUEST, &format!("Invalid ID: {}", segment)))
+}
+
+fn parse_user_id(segment: &str) -> Result<u64, HttpResponse> {
+    if segment == "me" {
+        return Ok(0);
+    }
+    parse_id(segment)
+}
+
+async fn dispatch(
+    route: Route,
+    body: Bytes,
+    query: &str,
+    state: &Arc<AppState>,
+    caller: &CallerContext,
+) -> Result<HttpResponse, Status> {
+    match route {
+        Route::CreateUser => {
+            let user = crate::api::create_user(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::CREATED, &user)
+        }
+        Route::GetUser(user_id) => {
+            let user_id = if user_id == 0 { caller.user_id()? } else { user_id };
+            let user = crate::api::get_user(state, caller, crate::api::UserLookup::Id(user_id)).await?;
+            json_response(StatusCode::OK, &user)
+        }
+        Route::SetTimeZone => {
+            let user = crate::api::set_time_zone(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &user)
+        }
+        Route::ListSessions(user_id) => {
+            let sessions: Vec<_> = crate::api::list_sessions(state, caller, user_id)
+                .await?
+                .into_iter()
+                .map(|session| SessionView::new(caller, session))
+                .collect();
+            json_response(StatusCode::OK, &serde_json::json!({ "sessions": sessions }))
+        }
+        Route::CreateSession => {
+            let session = crate::api::create_session(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::CREATED, &SessionView::new(caller, session))
+        }
+        Route::StartSession(session_id) => {
+            let session = crate::api::start_session(state, caller, session_id).await?;
+            json_response(StatusCode::OK, &SessionView::new(caller, session))
+        }
+        Route::CompleteSession(session_id) => {
+            let session = crate::api::complete_session(state, caller, session_id).await?;
+            json_response(StatusCode::OK, &SessionView::new(caller, session))
+        }
+        Route::CancelSession(session_id) => {
+            // The reason is optional, so an empty body is accepted
+            let input = if body.is_empty() { Default::default() } else { parse_json(&body)? };
+            let session = crate::api::cancel_session(state, caller, session_id, input).await?;
+            json_response(StatusCode::OK, &SessionView::new(caller, session))
+        }
+        Route::RescheduleSession(session_id) => {
+            let session = crate::api::reschedule_session(state, caller, session_id, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &SessionView::new(caller, session))
+        }
+        Route::SendNotification => {
+            let notification = crate::api::send_notification(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::CREATED, &notification)
+        }
+        Route::ListUnreadNotifications(user_id) => {
+            let notifications = crate::api::unread_notifications(state, caller, user_id).await?;
+            json_response(StatusCode::OK, &serde_json::json!({ "notifications": notifications }))
+        }
+        Route::BroadcastNotification => {
+            let result = crate::api::broadcast_notification(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &result)
+        }
+        Route::ListNotifications => {
+            let notification_query = crate::api::NotificationQuery {
+                is_read: parsed_param(query, "is_read")?,
+                notification_type: decoded_param(query, "type")?,
+                created_from: decoded_param(query, "from")?,
+                created_to: decoded_param(query, "to")?,
+                page_size: parsed_param(query, "page_size")?.unwrap_or_default(),
+                cursor: decoded_param(query, "cursor")?,
+            };
+
+            let page = crate::api::list_notifications(state, caller, notification_query).await?;
+            json_response(StatusCode::OK, &page)
+        }
+        Route::MarkNotificationsRead => {
+            let updated = crate::api::mark_notifications_read(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &serde_json::json!({ "updated": updated }))
+        }
+        Route::MarkAllNotificationsRead => {
+            let updated = crate::api::mark_all_notifications_read(state, caller).await?;
+            json_response(StatusCode::OK, &serde_json::json!({ "updated": updated }))
+        }
+        Route::DeleteNotification(notification_id) => {
+            crate::api::delete_notification(state, caller, notification_id).await?;
+            Ok(HttpResponse::empty(StatusCode::NO_CONTENT))
+        }
+        Route::MarkNotificationRead(notification_id) => {
+            crate::api::mark_notification_read(state, caller, notification_id).await?;
+            Ok(HttpResponse::empty(StatusCode::NO_CONTENT))
+        }
+        Route::ListNotificationDeliveries(notification_id) => {
+            let deliveries = crate::api::notification_deliveries(state, caller, notification_id).await?;
+            json_response(StatusCode::OK, &serde_json::json!({ "deliveries": deliveries }))
+        }
+        Route::GetNotificationPreferences => {
+            let preferences = crate::api::notification_preferences(state, caller).await?;
+            json_response(StatusCode::OK, &preferences)
+        }
+        Route::UpdateNotificationPreferences => {
+            let preferences = crate::api::update_notification_preferences(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &preferences)
+        }
+        Route::RegisterDeviceToken => {
+            crate::api::register_device_token(state, caller, parse_json(&body)?).await?;
+            Ok(HttpResponse::empty(StatusCode::NO_CONTENT))
+        }
+        Route::CreateMentorProfile(mentor_id) => {
+            let profile = crate::api::create_mentor_profile(state, caller, mentor_id, parse_json(&body)?).await?;
+            json_response(StatusCode::CREATED, &profile)
+        }
+        Route::UpdateMentorProfile(mentor_id) => {
+            let profile = crate::api::update_mentor_profile(state, caller, mentor_id, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &profile)
+        }
+        Route::GetMentorProfile(mentor_id) => {
+            let profile = crate::api::get_mentor_profile(state, caller, mentor_id).await?;
+            json_response(StatusCode::OK, &profile)
+        }
+        Route::DeleteMentorProfile(mentor_id) => {
+            crate::api::delete_mentor_profile(state, caller, mentor_id).await?;
+            Ok(HttpResponse::empty(StatusCode::NO_CONTENT))
+        }
+        Route::SetMentorVerification(mentor_id) => {
+            let profile = crate::api::set_mentor_verification(state, mentor_id, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &profile)
+        }
+        Route::ApplyForMentor => {
+            let application = crate::api::apply_for_mentor(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::CREATED, &application)
+        }
+        Route::ListMentorApplications => {
+            let status = query_param(query, "status").unwrap_or_default();
+            let applications = crate::api::list_mentor_applications(state, status).await?;
+            json_response(StatusCode::OK, &serde_json::json!({ "applications": applications }))
+        }
+        Route::ReviewMentorApplication(application_id) => {
+            let application =
+                crate::api::review_mentor_application(state, caller, application_id, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &application)
+        }
+        Route::SetWeeklyAvailability => {
+            let availability = crate::api::set_weekly_availability(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &availability)
+        }
+        Route::SetDateAvailability => {
+            let availability = crate::api::set_date_availability(state, caller, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &availability)
+        }
+        Route::GetMentorAvailability(mentor_id) => {
+            let availability = crate::api::get_availability(state, caller, mentor_id).await?;
+            json_response(StatusCode::OK, &availability)
+        }
+        Route::ListBookableSlots(mentor_id) => {
+            let slot_query = crate::api::SlotQuery {
+                mentor_id,
+                from_date: required_param(query, "from")?.to_string(),
+                to_date: required_param(query, "to")?.to_string(),
+                slot_minutes: parsed_param(query, "slot_minutes")?,
+            };
+
+            let slots = crate::api::list_bookable_slots(state, slot_query).await?;
+            json_response(StatusCode::OK, &slots)
+        }
+        Route::SubmitReview(session_id) => {
+            let review = crate::api::submit_review(state, caller, session_id, parse_json(&body)?).await?;
+            json_response(StatusCode::CREATED, &review)
+        }
+        Route::EditReview(review_id) => {
+            let review = crate::api::edit_review(state, caller, review_id, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &review)
+        }
+        Route::ListMentorReviews(mentor_id) => {
+            let page_size = parsed_param(query, "page_size")?.unwrap_or_default();
+            let cursor = decoded_param(query, "cursor")?;
+            let reviews = crate::api::list_mentor_reviews(state, caller, mentor_id, page_size, cursor.as_deref()).await?;
+            json_response(StatusCode::OK, &reviews)
+        }
+        Route::SetReviewVisibility(review_id) => {
+            let review = crate::api::set_review_visibility(state, caller, review_id, parse_json(&body)?).await?;
+            json_response(StatusCode::OK, &review)
+        }
+        Route::SearchMentors => {
+            let search = crate::api::MentorSearch {
+                query: decoded_param(query, "q")?.unwrap_or_default(),
+                skills: list_param(query, "skills")?,
+                languages: list_param(query, "languages")?,
+                min_price_cents: parsed_param(query, "min_price_cents")?,
+                max_price_cents: parsed_param(query, "max_price_cents")?,
+                currency: decoded_param(query, "currency")?,
+                min_rating: parsed_param(query, "min_rating")?,
+                available_within_days: parsed_param(query, "available_within_days")?,
+                sort: decoded_param(query, "sort")?.unwrap_or_default(),
+                page_size: parsed_param(query, "page_size")?.unwrap_or_default(),
+                cursor: decoded_param(query, "cursor")?,
+            };
+
+            let results = crate::api::search_mentors(state, search).await?;
+            json_response(StatusCode::OK, &results)
+        }
+        Route::ExportSessionInvite(session_id) => {
+            let ics = crate::api::session_invite(state, caller, session_id).await?;
+            Ok(HttpResponse::calendar(ics, Some(&format!("session-{}.ics", session_id))))
+        }
+        Route::CreateCalendarFeed => {
+            let feed = crate::api::create_calendar_feed(state, caller).await?;
+            json_response(StatusCode::CREATED, &feed)
+        }
+        Route::RevokeCalendarFeed => {
+            crate::api::revoke_calendar_feed(state, caller).await?;
+            Ok(HttpResponse::empty(StatusCode::NO_CONTENT))
+        }
+    }
+}
+
+/// `GET /calendar/{token}.ics`, authenticated by the feed token rather than a bearer token
+pub async fn calendar_feed(state: &AppState, token: &str) -> HttpResponse {
+    match crate::api::calendar_feed(state, token).await {
+        Ok(ics) => HttpResponse::calendar(ics, None),
+        Err(status) => status_response(&status),
+    }
+}
+
+/// Value of `name` in a query string; parameters used here never need percent-decoding
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn required_param<'a>(query: &'a str, name: &str) -> Result<&'a str, Status> {
+    query_param(query, name).ok_or_else(|| Status::invalid_argument(format!("Missing query parameter: {}", name)))
+}
+
+/// Percent-decoded value of `name`, with `+` read as a space
+fn decoded_param(query: &str, name: &str) -> Result<Option<String>, Status> {
+    query_param(query, name).map(percent_decode).transpose()
+}
+
+/// Comma-separated values of `name`, e.g. `skills=rust,system%20design`
+fn list_param(query: &str, name: &str) -> Result<Vec<String>, Status> {
+    Ok(decoded_param(query, name)?
+        .map(|value| value.split(',').filter(|item| !item.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default())
+}
+
+fn parsed_param<T: std::str::FromStr>(query: &str, name: &str) -> Result<Option<T>, Status> {
+    query_param(query, name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| Status::invalid_argument(format!("Invalid {}: {}", name, value)))
+        })
+        .transpose()
+}
+
+fn percent_decode(value: &str) -> Result<String, Status> {
+    let invalid = || Status::invalid_argument(format!("Invalid percent-encoding: {}", value));
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .ok_or_else(invalid)?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, Status> {
+    serde_json::from_slice(body).map_err(|e| Status::invalid_argument(format!("Invalid JSON body: {}", e)))
+}
+
+fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Result<HttpResponse, Status> {
+    let body = serde_json::to_vec(value)
+        .map_err(|e| Status::internal(format!("Failed to serialize response: {}", e)))?;
+    Ok(HttpResponse::json(status, body))
+}
+
+/// Error body for a failed call; `PreconditionFailure` details are listed under `violations`
+fn status_response(status: &Status) -> HttpResponse {
+    let Some(failure) = status.get_details_precondition_failure() else {
+        return HttpResponse::error(http_status(status.code()), status.message());
+    };
+
+    let violations: Vec<_> = failure
+        .violations
+        .iter()
+        .map(|violation| {
+            serde_json::json!({
+                "type": violation.r#type,
+                "subject": violation.subject,
+                "description": violation.description,
+            })
+        })
+        .collect();
+    let body = serde_json::json!({ "error": status.message(), "violations": violations });
+
+    HttpResponse::json(http_status(status.code()), body.to_string())
+}
+
+/// Map gRPC status codes to HTTP the same way grpc-gateway does
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => StatusCode::BAD_REQUEST,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_route() {
+        assert_eq!(parse_route(&Method::POST, &["users"]).unwrap(), Route::CreateUser);
+        assert_eq!(parse_route(&Method::GET, &["users", "me"]).unwrap(), Route::GetUser(0));
+        assert_eq!(parse_route(&Method::GET, &["users", "42", "sessions"]).unwrap(), Route::ListSessions(42));
+        assert_eq!(
+            parse_route(&Method::POST, &["notifications", "7", "read"]).unwrap(),
+            Route::MarkNotificationRead(7)
+        );
+        assert_eq!(
+            parse_route(&Method::GET, &["notifications", "7", "deliveries"]).unwrap(),
+            Route::ListNotificationDeliveries(7)
+        );
+        assert_eq!(parse_route(&Method::GET, &["notifications"]).unwrap(), Route::ListNotifications);
+        assert_eq!(
+            parse_route(&Method::POST, &["notifications", "broadcast"]).unwrap(),
+            Route::BroadcastNotification
+        );
+        assert_eq!(parse_route(&Method::POST, &["notifications", "read"]).unwrap(), Route::MarkNotificationsRead);
+        assert_eq!(
+            parse_route(&Method::POST, &["notifications", "read-all"]).unwrap(),
+            Route::MarkAllNotificationsRead
+        );
+        assert_eq!(parse_route(&Method::DELETE, &["notifications", "7"]).unwrap(), Route::DeleteNotification(7));
+        assert_eq!(
+            parse_route(&Method::GET, &["notifications", "read"]).unwrap_err().status,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+
+        assert_eq!(
+            parse_route(&Method::GET, &["users", "abc"]).unwrap_err().status,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            parse_route(&Method::DELETE, &["users", "42"]).unwrap_err().status,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            parse_route(&Method::POST, &["sessions", "3", "cancel"]).unwrap(),
+            Route::CancelSession(3)
+        );
+        assert_eq!(
+            parse_route(&Method::GET, &["sessions", "3", "start"]).unwrap_err().status,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            parse_route(&Method::GET, &["mentors", "me", "availability"]).unwrap(),
+            Route::GetMentorAvailability(0)
+        );
+        assert_eq!(
+            parse_route(&Method::DELETE, &["mentors", "me", "profile"]).unwrap(),
+            Route::DeleteMentorProfile(0)
+        );
+        assert_eq!(parse_route(&Method::GET, &["mentors", "search"]).unwrap(), Route::SearchMentors);
+        assert_eq!(
+            parse_route(&Method::POST, &["sessions", "3", "reviews"]).unwrap(),
+            Route::SubmitReview(3)
+        );
+        assert_eq!(
+            parse_route(&Method::GET, &["mentors", "me", "reviews"]).unwrap(),
+            Route::ListMentorReviews(0)
+        );
+        assert_eq!(
+            parse_route(&Method::DELETE, &["reviews", "4"]).unwrap_err().status,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            parse_route(&Method::POST, &["mentor-applications", "5", "review"]).unwrap(),
+            Route::ReviewMentorApplication(5)
+        );
+        assert_eq!(
+            parse_route(&Method::PATCH, &["mentors", "2", "profile"]).unwrap_err().status,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            parse_route(&Method::POST, &["availability", "weekly"]).unwrap_err().status,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            parse_route(&Method::GET, &["sessions", "3", "invite.ics"]).unwrap(),
+            Route::ExportSessionInvite(3)
+        );
+        assert_eq!(parse_route(&Method::DELETE, &["calendar-feed"]).unwrap(), Route::RevokeCalendarFeed);
+        assert_eq!(
+            parse_route(&Method::POST, &["notifications", "7", "deliveries"]).unwrap_err().status,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            parse_route(&Method::PUT, &["notification-preferences"]).unwrap(),
+            Route::UpdateNotificationPreferences
+        );
+        assert_eq!(parse_route(&Method::GET, &["nope"]).unwrap_err().status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_query_param() {
+        let query = "from=2025-01-06&to=2025-01-12&slot_minutes=30";
+        assert_eq!(query_param(query, "from"), Some("2025-01-06"));
+        assert_eq!(query_param(query, "slot_minutes"), Some("30"));
+        assert_eq!(query_param(query, "mentor"), None);
+        assert_eq!(required_param("", "to").unwrap_err().code(), Code::InvalidArgument);
+        assert_eq!(parsed_param::<i32>(query, "slot_minutes").unwrap(), Some(30));
+        assert!(parsed_param::<i32>(query, "from").is_err());
+    }
+
+    #[test]
+    fn test_decoded_params() {
+        let query = "q=rust+%C3%A9l%C3%A8ve&skills=rust,system%20design&cursor=%zz";
+        assert_eq!(decoded_param(query, "q").unwrap().as_deref(), Some("rust élève"));
+        assert_eq!(list_param(query, "skills").unwrap(), vec!["rust", "system design"]);
+        assert!(list_param(query, "languages").unwrap().is_empty());
+        assert!(decoded_param(query, "cursor").is_err());
+    }
+
+    #[test]
+    fn test_http_status() {
+        assert_eq!(http_status(Code::InvalidArgument), StatusCode::BAD_REQUEST);
+        assert_eq!(http_status(Code::PermissionDenied), StatusCode::FORBIDDEN);
+        assert_eq!(http_status(Code::ResourceExhausted), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(http_status(Code::Internal), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_routes_use_known_policies() {
+        let routes = [
+            Route::CreateUser,
+            Route::GetUser(0),
+            Route::SetTimeZone,
+            Route::ListSessions(0),
+            Route::CreateSession,
+            Route::StartSession(1),
+            Route::CompleteSession(1),
+            Route::CancelSession(1),
+            Route::RescheduleSession(1),
+            Route::SendNotification,
+            Route::BroadcastNotification,
+            Route::ListUnreadNotifications(0),
+            Route::ListNotifications,
+            Route::MarkNotificationRead(1),
+            Route::MarkNotificationsRead,
+            Route::MarkAllNotificationsRead,
+            Route::DeleteNotification(1),
+            Route::ListNotificationDeliveries(1),
+            Route::GetNotificationPreferences,
+            Route::UpdateNotificationPreferences,
+            Route::RegisterDeviceToken,
+            Route::CreateMentorProfile(0),
+            Route::UpdateMentorProfile(0),
+            Route::GetMentorProfile(0),
+            Route::DeleteMentorProfile(0),
+            Route::SetMentorVerification(1),
+            Route::ApplyForMentor,
+            Route::ListMentorApplications,
+            Route::ReviewMentorApplication(1),
+            Route::SetWeeklyAvailability,
+            Route::SetDateAvailability,
+            Route::GetMentorAvailability(0),
+            Route::ListBookableSlots(1),
+            Route::SearchMentors,
+            Route::SubmitReview(1),
+            Route::EditReview(1),
+            Route::ListMentorReviews(0),
+            Route::SetReviewVisibility(1),
+            Route::ExportSessionInvite(1),
+            Route::CreateCalendarFeed,
+            Route::RevokeCalendarFeed,
+        ];
+        for route in routes {
+            assert!(crate::policy::access_for(route.policy_method()).is_some(), "{:?}", route);
+        }
+    }
+}
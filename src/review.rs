@@ -0,0 +1,132 @@
+use serde::Serialize;
+use tonic::Status;
+
+use crate::models::MentorRating;
+
+/// Values allowed by the `session_reviews.reviewer_role` ENUM
+pub const REVIEWER_ROLES: &[&str] = &["mentee", "mentor"];
+
+pub const MAX_COMMENT_LEN: usize = 2000;
+
+/// How long after submitting a reviewer may still edit their review
+pub const EDIT_WINDOW_DAYS: i64 = 7;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// A mentor's reputation from the visible reviews their mentees left
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RatingSummary {
+    /// `None` until the mentor has a visible review
+    pub average: Option<f64>,
+    pub review_count: u32,
+    /// Number of 1 to 5 star reviews, in that order
+    pub distribution: [u32; 5],
+}
+
+impl From<Option<MentorRating>> for RatingSummary {
+    fn from(rating: Option<MentorRating>) -> Self {
+        let Some(rating) = rating else {
+            return Self {
+                average: None,
+                review_count: 0,
+                distribution: [0; 5],
+            };
+        };
+
+        let count = |value: i32| value.max(0) as u32;
+        let review_count = count(rating.review_count);
+        Self {
+            average: (review_count > 0).then(|| f64::from(rating.rating_sum) / f64::from(review_count)),
+            review_count,
+            distribution: [
+                count(rating.rating_1_count),
+                count(rating.rating_2_count),
+                count(rating.rating_3_count),
+                count(rating.rating_4_count),
+                count(rating.rating_5_count),
+            ],
+        }
+    }
+}
+
+pub fn validate_rating(rating: u32) -> Result<u8, Status> {
+    match u8::try_from(rating) {
+        Ok(rating @ 1..=5) => Ok(rating),
+        _ => Err(Status::invalid_argument("rating must be between 1 and 5")),
+    }
+}
+
+/// Trim a review comment, treating a blank one as no comment
+pub fn normalize_comment(comment: Option<String>) -> Result<Option<String>, Status> {
+    let Some(comment) = comment.map(|comment| comment.trim().to_string()).filter(|comment| !comment.is_empty()) else {
+        return Ok(None);
+    };
+    if comment.chars().count() > MAX_COMMENT_LEN {
+        return Err(Status::invalid_argument(format!(
+            "comment must be at most {} characters",
+            MAX_COMMENT_LEN
+        )));
+    }
+    Ok(Some(comment))
+}
+
+/// Reviews can be edited for [`EDIT_WINDOW_DAYS`] after they were submitted
+pub fn ensure_editable(created_at: chrono::NaiveDateTime, now: chrono::NaiveDateTime) -> Result<(), Status> {
+    if now - created_at > chrono::Duration::days(EDIT_WINDOW_DAYS) {
+        return Err(Status::failed_precondition(format!(
+            "Reviews can only be edited within {} days of submitting them",
+            EDIT_WINDOW_DAYS
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_rating() {
+        assert_eq!(validate_rating(1).unwrap(), 1);
+        assert_eq!(validate_rating(5).unwrap(), 5);
+        assert!(validate_rating(0).is_err());
+        assert!(validate_rating(6).is_err());
+        assert!(validate_rating(257).is_err());
+    }
+
+    #[test]
+    fn test_normalize_comment() {
+        assert_eq!(normalize_comment(None).unwrap(), None);
+        assert_eq!(normalize_comment(Some("  ".into())).unwrap(), None);
+        assert_eq!(normalize_comment(Some(" Great ".into())).unwrap(), Some("Great".to_string()));
+        assert!(normalize_comment(Some("x".repeat(MAX_COMMENT_LEN + 1))).is_err());
+    }
+
+    #[test]
+    fn test_ensure_editable() {
+        let created_at = chrono::Utc::now().naive_utc();
+        assert!(ensure_editable(created_at, created_at + chrono::Duration::days(EDIT_WINDOW_DAYS)).is_ok());
+        let late = created_at + chrono::Duration::days(EDIT_WINDOW_DAYS) + chrono::Duration::seconds(1);
+        assert_eq!(ensure_editable(created_at, late).unwrap_err().code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[test]
+    fn test_rating_summary() {
+        assert_eq!(RatingSummary::from(None).average, None);
+
+        let summary = RatingSummary::from(Some(MentorRating {
+            mentor_id: 2,
+            review_count: 4,
+            rating_sum: 17,
+            rating_1_count: 0,
+            rating_2_count: 0,
+            rating_3_count: 1,
+            rating_4_count: 1,
+            rating_5_count: 2,
+        }));
+        assert_eq!(summary.average, Some(4.25));
+        assert_eq!(summary.review_count, 4);
+        assert_eq!(summary.distribution, [0, 0, 1, 1, 2]);
+    }
+}
@@ -0,0 +1,629 @@
+//! Mentor search backed by an embedded tantivy index.
+//!
+//! MySQL stays the source of truth. Writes that change what a mentor looks
+//! like in search queue the mentor's ID, and [`run_indexer`] rebuilds those
+//! documents. A periodic full rebuild picks up anything missed and keeps
+//! `next_available_at` current as time passes.
+
+use std::collections::BTreeSet;
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{AllQuery, BooleanQuery, ConstScoreQuery, Occur, Query, QueryParser, RangeQuery, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::{DocAddress, Index, IndexReader, IndexWriter, ReloadPolicy, Score, TantivyDocument, Term};
+use tokio::sync::mpsc;
+use tonic::Status;
+
+use crate::AppState;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Matches considered per relevance-sorted query; pages past this many results are not
+/// reachable. Rating and price sorts rank every match on the index's fast fields instead.
+pub const MAX_SEARCH_HITS: usize = 1000;
+
+/// How often every mentor document is rebuilt from MySQL
+const FULL_REBUILD_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+const WRITER_MEMORY_BYTES: usize = 50_000_000;
+
+/// Everything search knows about a mentor, stored whole in the index so hits need no database reads
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MentorDocument {
+    pub mentor_id: u64,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    pub headline: Option<String>,
+    pub bio: Option<String>,
+    pub skills: Vec<String>,
+    pub languages: Vec<String>,
+    pub hourly_rate_cents: Option<u32>,
+    pub currency: Option<String>,
+    pub verification_status: Option<String>,
+    pub rating: Option<f64>,
+    pub review_count: u32,
+    /// Start of the mentor's first open slot, if any within the availability horizon
+    #[serde(with = "crate::timestamp::utc_option")]
+    pub next_available_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Relevance,
+    /// Highest rated first, unrated mentors last
+    Rating,
+    /// Cheapest first, mentors without a published rate last
+    Price,
+}
+
+impl SortOrder {
+    fn cursor_tag(self) -> char {
+        match self {
+            SortOrder::Relevance => 'r',
+            SortOrder::Rating => 's',
+            SortOrder::Price => 'p',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MentorQuery {
+    /// Full-text query over name, headline, bio and skills; empty matches every mentor
+    pub text: String,
+    /// Mentors must have every one of these skills
+    pub skills: Vec<String>,
+    /// Mentors must speak at least one of these languages
+    pub languages: Vec<String>,
+    pub min_price_cents: Option<u32>,
+    pub max_price_cents: Option<u32>,
+    pub currency: Option<String>,
+    pub min_rating: Option<f64>,
+    /// Only mentors with an open slot starting before this instant
+    pub available_before: Option<NaiveDateTime>,
+    pub sort: SortOrder,
+    pub limit: u32,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    pub mentors: Vec<MentorDocument>,
+    /// Pass back as `cursor` for the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+struct Fields {
+    mentor_id: Field,
+    display_name: Field,
+    headline: Field,
+    bio: Field,
+    skills_text: Field,
+    skill: Field,
+    language: Field,
+    hourly_rate_cents: Field,
+    currency: Field,
+    rating: Field,
+    next_available_at: Field,
+    source: Field,
+}
+
+fn schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        mentor_id: builder.add_u64_field("mentor_id", INDEXED | FAST),
+        display_name: builder.add_text_field("display_name", TEXT),
+        headline: builder.add_text_field("headline", TEXT),
+        bio: builder.add_text_field("bio", TEXT),
+        skills_text: builder.add_text_field("skills_text", TEXT),
+        skill: builder.add_text_field("skill", STRING),
+        language: builder.add_text_field("language", STRING),
+        hourly_rate_cents: builder.add_u64_field("hourly_rate_cents", INDEXED | FAST),
+        currency: builder.add_text_field("currency", STRING),
+        rating: builder.add_f64_field("rating", INDEXED | FAST),
+        next_available_at: builder.add_i64_field("next_available_at", INDEXED | FAST),
+        source: builder.add_text_field("source", STORED),
+    };
+    (builder.build(), fields)
+}
+
+/// The mentor search index plus the queue of mentors waiting to be reindexed
+pub struct SearchIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    fields: Fields,
+    updates: mpsc::UnboundedSender<u64>,
+}
+
+impl SearchIndex {
+    /// Open or create the index in `dir`, or in memory when `dir` is `None`.
+    /// The returned receiver must be handed to [`run_indexer`].
+    pub fn new(dir: Option<&Path>) -> Result<(Self, mpsc::UnboundedReceiver<u64>), Box<dyn std::error::Error>> {
+        let (schema, fields) = schema();
+        let index = match dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)?;
+                Index::open_or_create(MmapDirectory::open(dir)?, schema)?
+            }
+            None => Index::create_in_ram(schema),
+        };
+
+        let writer = index.writer(WRITER_MEMORY_BYTES)?;
+        let reader = index.reader_builder().reload_policy(ReloadPolicy::Manual).try_into()?;
+        let (updates, receiver) = mpsc::unbounded_channel();
+
+        Ok((
+            Self {
+                index,
+                reader,
+                writer: Mutex::new(writer),
+                fields,
+                updates,
+            },
+            receiver,
+        ))
+    }
+
+    /// Ask the indexer to rebuild one mentor's document after a write that affects it
+    pub fn queue_mentor(&self, mentor_id: u64) {
+        // Only fails once the indexer has stopped, and the next full rebuild would not run either
+        let _ = self.updates.send(mentor_id);
+    }
+
+    fn to_document(&self, mentor: &MentorDocument) -> Result<TantivyDocument, serde_json::Error> {
+        let fields = self.fields;
+        let mut document = TantivyDocument::new();
+
+        document.add_u64(fields.mentor_id, mentor.mentor_id);
+        for (field, text) in [
+            (fields.display_name, &mentor.display_name),
+            (fields.headline, &mentor.headline),
+            (fields.bio, &mentor.bio),
+        ] {
+            if let Some(text) = text {
+                document.add_text(field, text);
+            }
+        }
+        for skill in &mentor.skills {
+            document.add_text(fields.skills_text, skill);
+            document.add_text(fields.skill, skill);
+        }
+        for language in &mentor.languages {
+            document.add_text(fields.language, language);
+        }
+        if let Some(cents) = mentor.hourly_rate_cents {
+            document.add_u64(fields.hourly_rate_cents, cents.into());
+        }
+        if let Some(currency) = &mentor.currency {
+            document.add_text(fields.currency, currency);
+        }
+        if let Some(rating) = mentor.rating {
+            document.add_f64(fields.rating, rating);
+        }
+        if let Some(at) = mentor.next_available_at {
+            document.add_i64(fields.next_available_at, at.and_utc().timestamp());
+        }
+        document.add_text(fields.source, serde_json::to_string(mentor)?);
+
+        Ok(document)
+    }
+
+    /// Replace the documents of `mentor_ids`; IDs without a document in `mentors` are removed
+    pub fn update(&self, mentor_ids: &[u64], mentors: &[MentorDocument]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = self.writer.lock().unwrap();
+        for mentor_id in mentor_ids {
+            writer.delete_term(Term::from_field_u64(self.fields.mentor_id, *mentor_id));
+        }
+        for mentor in mentors {
+            writer.add_document(self.to_document(mentor)?)?;
+        }
+        writer.commit()?;
+        drop(writer);
+
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Replace the whole index with `mentors`
+    pub fn replace_all(&self, mentors: &[MentorDocument]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.delete_all_documents()?;
+        for mentor in mentors {
+            writer.add_document(self.to_document(mentor)?)?;
+        }
+        writer.commit()?;
+        drop(writer);
+
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    pub fn search(&self, query: &MentorQuery) -> Result<SearchPage, Status> {
+        let limit = match query.limit {
+            0 => DEFAULT_PAGE_SIZE,
+            limit => limit.min(MAX_PAGE_SIZE),
+        } as usize;
+        let after = query.cursor.as_deref().map(|cursor| decode_cursor(cursor, query.sort)).transpose()?;
+
+        let searcher = self.reader.searcher();
+        let mentor_query = self.build_query(query)?;
+        let failed = |e: tantivy::TantivyError| Status::internal(format!("Search failed: {}", e));
+
+        let mut ranked = Vec::new();
+        match query.sort {
+            SortOrder::Relevance => {
+                let hits = searcher.search(&mentor_query, &TopDocs::with_limit(MAX_SEARCH_HITS)).map_err(failed)?;
+                for (score, address) in hits {
+                    let mentor = self.load(&searcher, address)?;
+                    ranked.push((sort_key(query.sort, score, &mentor), mentor));
+                }
+            }
+            SortOrder::Rating | SortOrder::Price => {
+                // Ranked in the collector, so the order holds however many mentors match
+                let sort = query.sort;
+                let collector = TopDocs::with_limit(limit + 1).custom_score(move |segment: &tantivy::SegmentReader| {
+                    let fast = segment.fast_fields();
+                    let mentor_ids = fast.column_opt::<u64>("mentor_id").ok().flatten();
+                    let rates = fast.column_opt::<u64>("hourly_rate_cents").ok().flatten();
+                    let ratings = fast.column_opt::<f64>("rating").ok().flatten();
+                    move |doc: tantivy::DocId| {
+                        let mentor_id = mentor_ids.as_ref().and_then(|column| column.first(doc)).unwrap_or_default();
+                        let rate = rates.as_ref().and_then(|column| column.first(doc)).and_then(|cents| u32::try_from(cents).ok());
+                        let rating = ratings.as_ref().and_then(|column| column.first(doc));
+                        let key = (primary_key(sort, 0.0, rating, rate), mentor_id);
+                        // Higher collector scores come first; results up to the cursor score lowest
+                        is_after(key, after).then_some(std::cmp::Reverse(key))
+                    }
+                });
+                for (key, address) in searcher.search(&mentor_query, &collector).map_err(failed)? {
+                    if key.is_some() {
+                        let mentor = self.load(&searcher, address)?;
+                        ranked.push((sort_key(query.sort, 0.0, &mentor), mentor));
+                    }
+                }
+            }
+        }
+        ranked.sort_by(|(a, _), (b, _)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut page: Vec<_> = ranked
+            .into_iter()
+            .filter(|(key, _)| is_after(*key, after))
+            .take(limit + 1)
+            .collect();
+
+        let next_cursor = if page.len() > limit {
+            page.truncate(limit);
+            page.last().map(|(key, _)| encode_cursor(query.sort, *key))
+        } else {
+            None
+        };
+
+        Ok(SearchPage {
+            mentors: page.into_iter().map(|(_, mentor)| mentor).collect(),
+            next_cursor,
+        })
+    }
+
+    fn build_query(&self, query: &MentorQuery) -> Result<BooleanQuery, Status> {
+        let fields = self.fields;
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+
+        let text = query.text.trim();
+        if text.is_empty() {
+            clauses.push((Occur::Must, Box::new(AllQuery)));
+        } else {
+            let mut parser = QueryParser::for_index(
+                &self.index,
+                vec![fields.display_name, fields.headline, fields.bio, fields.skills_text],
+            );
+            parser.set_field_boost(fields.display_name, 2.0);
+            parser.set_field_boost(fields.headline, 1.5);
+            parser.set_conjunction_by_default();
+            // Lenient parsing: user input with stray quotes or colons still searches
+            let (text_query, _) = parser.parse_query_lenient(text);
+            clauses.push((Occur::Must, text_query));
+        }
+
+        // Filters only narrow the results and must not change relevance
+        let mut filter = |query: Box<dyn Query>| clauses.push((Occur::Must, Box::new(ConstScoreQuery::new(query, 0.0))));
+
+        for skill in &query.skills {
+            filter(term_query(Term::from_field_text(fields.skill, &skill.trim().to_lowercase())));
+        }
+
+        if !query.languages.is_empty() {
+            let languages = query
+                .languages
+                .iter()
+                .map(|language| {
+                    let term = Term::from_field_text(fields.language, &language.trim().to_ascii_lowercase());
+                    (Occur::Should, term_query(term))
+                })
+                .collect();
+            filter(Box::new(BooleanQuery::new(languages)));
+        }
+
+        if query.min_price_cents.is_some() || query.max_price_cents.is_some() {
+            let Some(currency) = query.currency.as_deref().filter(|currency| !currency.is_empty()) else {
+                return Err(Status::invalid_argument("A price range requires a currency"));
+            };
+            filter(term_query(Term::from_field_text(fields.currency, &currency.to_ascii_uppercase())));
+
+            let bound = |cents: Option<u32>| match cents {
+                Some(cents) => Bound::Included(Term::from_field_u64(fields.hourly_rate_cents, cents.into())),
+                None => Bound::Unbounded,
+            };
+            filter(Box::new(RangeQuery::new(bound(query.min_price_cents), bound(query.max_price_cents))));
+        } else if let Some(currency) = query.currency.as_deref().filter(|currency| !currency.is_empty()) {
+            filter(term_query(Term::from_field_text(fields.currency, &currency.to_ascii_uppercase())));
+        }
+
+        if let Some(min_rating) = query.min_rating {
+            filter(Box::new(RangeQuery::new(
+                Bound::Included(Term::from_field_f64(fields.rating, min_rating)),
+                Bound::Unbounded,
+            )));
+        }
+
+        if let Some(before) = query.available_before {
+            filter(Box::new(RangeQuery::new(
+                Bound::Unbounded,
+                Bound::Excluded(Term::from_field_i64(fields.next_available_at, before.and_utc().timestamp())),
+            )));
+        }
+
+        Ok(BooleanQuery::new(clauses))
+    }
+
+    fn load(&self, searcher: &tantivy::Searcher, address: DocAddress) -> Result<MentorDocument, Status> {
+        let document: TantivyDocument = searcher
+            .doc(address)
+            .map_err(|e| Status::internal(format!("Failed to read search document: {}", e)))?;
+        let source = document
+            .get_first(self.fields.source)
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| Status::internal("Search document has no source"))?;
+
+        serde_json::from_str(source).map_err(|e| Status::internal(format!("Corrupt search document: {}", e)))
+    }
+}
+
+fn term_query(term: Term) -> Box<dyn Query> {
+    Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+}
+
+/// Ascending sort key: the primary order for `sort`, then the mentor ID as a tie-breaker
+fn sort_key(sort: SortOrder, score: Score, mentor: &MentorDocument) -> (f64, u64) {
+    (primary_key(sort, score, mentor.rating, mentor.hourly_rate_cents), mentor.mentor_id)
+}
+
+fn primary_key(sort: SortOrder, score: Score, rating: Option<f64>, hourly_rate_cents: Option<u32>) -> f64 {
+    match sort {
+        SortOrder::Relevance => -f64::from(score),
+        SortOrder::Rating => rating.map_or(f64::INFINITY, |rating| -rating),
+        SortOrder::Price => hourly_rate_cents.map_or(f64::INFINITY, f64::from),
+    }
+}
+
+/// Whether a result with sort key `key` belongs after the cursor's `after` key
+fn is_after(key: (f64, u64), after: Option<(f64, u64)>) -> bool {
+    after.is_none_or(|after| key.0.total_cmp(&after.0).then(key.1.cmp(&after.1)).is_gt())
+}
+
+/// Cursors hold the sort key of the last result, tagged with the sort order they belong to
+fn encode_cursor(sort: SortOrder, (primary, mentor_id): (f64, u64)) -> String {
+    format!("{}{:016x}{:016x}", sort.cursor_tag(), primary.to_bits(), mentor_id)
+}
+
+fn decode_cursor(cursor: &str, sort: SortOrder) -> Result<(f64, u64), Status> {
+    let invalid = || Status::invalid_argument("Invalid cursor");
+
+    let rest = cursor.strip_prefix(sort.cursor_tag()).ok_or_else(invalid)?;
+    if rest.len() != 32 || !rest.is_ascii() {
+        return Err(invalid());
+    }
+    let primary = u64::from_str_radix(&rest[..16], 16).map_err(|_| invalid())?;
+    let mentor_id = u64::from_str_radix(&rest[16..], 16).map_err(|_| invalid())?;
+
+    Ok((f64::from_bits(primary), mentor_id))
+}
+
+/// Keep the index in sync: a full rebuild at start and every [`FULL_REBUILD_INTERVAL`],
+/// plus batched rebuilds of mentors queued through [`SearchIndex::queue_mentor`]
+pub async fn run_indexer(state: Arc<AppState>, mut updates: mpsc::UnboundedReceiver<u64>) {
+    let mut rebuild = tokio::time::interval(FULL_REBUILD_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = rebuild.tick() => {
+                if let Err(e) = rebuild_all(&state).await {
+                    tracing::error!("Failed to rebuild mentor search index: {}", e);
+                }
+            }
+            Some(mentor_id) = updates.recv() => {
+                let mut mentor_ids = BTreeSet::from([mentor_id]);
+                while let Ok(mentor_id) = updates.try_recv() {
+                    mentor_ids.insert(mentor_id);
+                }
+                if let Err(e) = reindex(&state, mentor_ids.into_iter().collect()).await {
+                    tracing::error!("Failed to update mentor search index: {}", e);
+                }
+            }
+        }
+    }
+}
+
+async fn rebuild_all(state: &Arc<AppState>) -> Result<(), String> {
+    let mentor_ids = crate::db::get_mentor_ids(&state.db).await.map_err(|e| e.to_string())?;
+
+    let mut mentors = Vec::with_capacity(mentor_ids.len());
+    for mentor_id in mentor_ids {
+        if let Some(mentor) = crate::api::mentor_search_document(state, mentor_id)
+            .await
+            .map_err(|status| status.message().to_string())?
+        {
+            mentors.push(mentor);
+        }
+    }
+
+    let count = mentors.len();
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || state.search.replace_all(&mentors).map_err(|e| e.to_string()))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    tracing::info!("Rebuilt mentor search index with {} mentors", count);
+    Ok(())
+}
+
+async fn reindex(state: &Arc<AppState>, mentor_ids: Vec<u64>) -> Result<(), String> {
+    let mut mentors = Vec::with_capacity(mentor_ids.len());
+    for mentor_id in &mentor_ids {
+        if let Some(mentor) = crate::api::mentor_search_document(state, *mentor_id)
+            .await
+            .map_err(|status| status.message().to_string())?
+        {
+            mentors.push(mentor);
+        }
+    }
+
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || state.search.update(&mentor_ids, &mentors).map_err(|e| e.to_string()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mentor(mentor_id: u64, name: &str, skills: &[&str], rate: Option<u32>, rating: Option<f64>) -> MentorDocument {
+        MentorDocument {
+            mentor_id,
+            display_name: Some(name.to_string()),
+            photo_url: None,
+            headline: Some(format!("{} headline", name)),
+            bio: None,
+            skills: skills.iter().map(|skill| skill.to_string()).collect(),
+            languages: vec!["en".to_string()],
+            hourly_rate_cents: rate,
+            currency: rate.map(|_| "EUR".to_string()),
+            verification_status: None,
+            rating,
+            review_count: 0,
+            next_available_at: None,
+        }
+    }
+
+    fn index(mentors: &[MentorDocument]) -> SearchIndex {
+        let (index, _) = SearchIndex::new(None).unwrap();
+        index.replace_all(mentors).unwrap();
+        index
+    }
+
+    fn ids(page: &SearchPage) -> Vec<u64> {
+        page.mentors.iter().map(|mentor| mentor.mentor_id).collect()
+    }
+
+    #[test]
+    fn test_price_and_rating_sorts_cover_every_match() {
+        // Later mentors are cheaper and better rated, so an arbitrary first MAX_SEARCH_HITS would miss them
+        let count = MAX_SEARCH_HITS as u64 + 50;
+        let mentors: Vec<_> = (1..=count)
+            .map(|id| mentor(id, "Mentor", &[], Some((count + 1 - id) as u32 * 100), Some(id as f64 / count as f64 * 5.0)))
+            .collect();
+        let index = index(&mentors);
+
+        let cheapest = index.search(&MentorQuery { sort: SortOrder::Price, limit: 3, ..Default::default() }).unwrap();
+        assert_eq!(ids(&cheapest), vec![count, count - 1, count - 2]);
+        let next = index
+            .search(&MentorQuery { sort: SortOrder::Price, limit: 3, cursor: cheapest.next_cursor, ..Default::default() })
+            .unwrap();
+        assert_eq!(ids(&next), vec![count - 3, count - 4, count - 5]);
+
+        let best = index.search(&MentorQuery { sort: SortOrder::Rating, limit: 2, ..Default::default() }).unwrap();
+        assert_eq!(ids(&best), vec![count, count - 1]);
+    }
+
+    #[test]
+    fn test_text_search_and_filters() {
+        let index = index(&[
+            mentor(1, "Ada Lovelace", &["rust", "system design"], Some(8000), Some(4.8)),
+            mentor(2, "Grace Hopper", &["cobol"], Some(5000), Some(4.2)),
+            mentor(3, "Alan Turing", &["rust"], None, None),
+        ]);
+
+        let search = |query: MentorQuery| ids(&index.search(&query).unwrap());
+
+        assert_eq!(search(MentorQuery { text: "grace".into(), ..Default::default() }), vec![2]);
+        assert_eq!(
+            search(MentorQuery { skills: vec!["Rust".into()], ..Default::default() }).len(),
+            2
+        );
+        assert_eq!(
+            search(MentorQuery {
+                min_price_cents: Some(6000),
+                currency: Some("eur".into()),
+                ..Default::default()
+            }),
+            vec![1]
+        );
+        assert_eq!(
+            search(MentorQuery { min_rating: Some(4.5), ..Default::default() }),
+            vec![1]
+        );
+        assert!(index
+            .search(&MentorQuery { max_price_cents: Some(1), ..Default::default() })
+            .is_err());
+    }
+
+    #[test]
+    fn test_sort_and_cursor_pagination() {
+        let index = index(&[
+            mentor(1, "One", &[], Some(9000), Some(3.0)),
+            mentor(2, "Two", &[], Some(3000), None),
+            mentor(3, "Three", &[], None, Some(5.0)),
+            mentor(4, "Four", &[], Some(3000), Some(4.0)),
+        ]);
+
+        let by_price = MentorQuery { sort: SortOrder::Price, limit: 2, ..Default::default() };
+        let first = index.search(&by_price).unwrap();
+        assert_eq!(ids(&first), vec![2, 4]);
+
+        let second = index
+            .search(&MentorQuery { cursor: first.next_cursor.clone(), ..by_price.clone() })
+            .unwrap();
+        assert_eq!(ids(&second), vec![1, 3]);
+        assert!(second.next_cursor.is_none());
+
+        let by_rating = index.search(&MentorQuery { sort: SortOrder::Rating, ..Default::default() }).unwrap();
+        assert_eq!(ids(&by_rating), vec![3, 4, 1, 2]);
+
+        // A cursor only works with the sort order that produced it
+        assert!(index
+            .search(&MentorQuery { cursor: first.next_cursor, ..Default::default() })
+            .is_err());
+    }
+
+    #[test]
+    fn test_update_replaces_and_removes() {
+        let index = index(&[mentor(1, "Ada", &[], None, None), mentor(2, "Grace", &[], None, None)]);
+
+        index.update(&[1, 2], &[mentor(1, "Barbara", &[], None, None)]).unwrap();
+
+        let page = index.search(&MentorQuery::default()).unwrap();
+        assert_eq!(ids(&page), vec![1]);
+        assert_eq!(page.mentors[0].display_name.as_deref(), Some("Barbara"));
+    }
+}
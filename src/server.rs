@@ -1,61 +1,260 @@
-use tokio::net::UdpSocket;
-use tokio_quiche::QuicListener;
-use quiche::Config;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use h3::server::{RequestResolver, RequestStream};
+use http::{header, HeaderName, HeaderValue, Method, Request, Response, StatusCode};
+use quinn::rustls;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
 use crate::AppState;
 
-pub async fn run(host: &str, port: u16, _state: Arc<AppState>) -> Result<(), Box<dyn std::error::Error>> {
-    let addr = format!("{}:{}", host, port);
-    let socket = UdpSocket::bind(&addr).await?;
+/// Largest request body the HTTP/3 listener will buffer
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+type H3Stream = RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>;
+
+/// A fully buffered response produced by a route handler
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    pub body: Bytes,
+}
+
+impl HttpResponse {
+    pub fn json(status: StatusCode, body: impl Into<Bytes>) -> Self {
+        Self {
+            status,
+            headers: vec![(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+            body: body.into(),
+        }
+    }
+
+    pub fn text(status: StatusCode, body: impl Into<Bytes>) -> Self {
+        Self {
+            status,
+            headers: vec![(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"))],
+            body: body.into(),
+        }
+    }
+
+    /// An iCalendar document; `filename` makes browsers download it instead of displaying it
+    pub fn calendar(ics: impl Into<Bytes>, filename: Option<&str>) -> Self {
+        let mut headers = vec![(header::CONTENT_TYPE, HeaderValue::from_static("text/calendar; charset=utf-8"))];
+        if let Some(value) = filename.and_then(|name| {
+            HeaderValue::from_str(&format!("attachment; filename=\"{}\"", name)).ok()
+        }) {
+            headers.push((header::CONTENT_DISPOSITION, value));
+        }
+
+        Self {
+            status: StatusCode::OK,
+            headers,
+            body: ics.into(),
+        }
+    }
+
+    /// JSON error body in the shape `{"error": "..."}`
+    pub fn error(status: StatusCode, message: &str) -> Self {
+        Self::json(status, serde_json::json!({ "error": message }).to_string())
+    }
+
+    pub fn empty(status: StatusCode) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Bytes::new(),
+        }
+    }
+
+    pub fn method_not_allowed(allow: &'static str) -> Self {
+        let mut response = Self::error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+        response.headers.push((header::ALLOW, HeaderValue::from_static(allow)));
+        response
+    }
+}
+
+pub async fn run(host: &str, port: u16, state: Arc<AppState>) -> Result<(), Box<dyn std::error::Error>> {
+    let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
+    let endpoint = quinn::Endpoint::server(server_config()?, addr)?;
     println!("HTTP/3 server listening on {}", addr);
 
-    let mut config = Config::new(quiche::PROTOCOL_VERSION)?;
-    config.set_application_protos(&[b"h3"])?;
-    config.set_max_idle_timeout(5000);
-    config.set_max_recv_udp_payload_size(1350);
-    config.set_initial_max_data(10_000_000);
-    config.set_initial_max_stream_data_bidi_local(1_000_000);
-    config.set_initial_max_stream_data_bidi_remote(1_000_000);
-    config.set_initial_max_streams_bidi(100);
-    config.set_initial_max_streams_uni(100);
-    config.set_disable_active_migration(true);
-
-    config.load_cert_chain_from_pem_file("cert.crt")?;
-    config.load_priv_key_from_pem_file("cert.key")?;
-
-    // Create the listener
-    let mut listener = QuicListener::new(socket, config);
-    
-    loop {
-        // Accept a new connection
-        let mut connection = match listener.accept().await {
-            Ok(conn) => conn,
-            Err(_) => continue,
-        };
+    while let Some(incoming) = endpoint.accept().await {
+        let state = state.clone();
 
-        let state = _state.clone();
-        
         tokio::spawn(async move {
-            println!("New connection established");
-            
-            // Simple loop to handle streams
-            loop {
-                match connection.stream_recv().await {
-                    Ok((mut stream_id, mut data)) => {
-                        println!("Received stream {} with {} bytes", stream_id, data.len());
-                        // Echo back or send a response
-                        // For HTTP/3, this would be much more complex (headers, frames, etc.)
-                        // Here we just do a raw QUIC echo for demonstration of the transport
-                        
-                        // Example: Send "Hello from LinkWithMentor"
-                        let response = b"Hello from LinkWithMentor HTTP/3 (Raw QUIC)";
-                        if let Err(e) = connection.stream_send(stream_id, response, true).await {
-                             eprintln!("Failed to send response: {}", e);
-                        }
-                    },
-                    Err(_) => break, // Connection closed or error
-                }
+            if let Err(e) = handle_connection(incoming, state).await {
+                tracing::debug!("HTTP/3 connection closed with error: {}", e);
             }
         });
     }
+
+    Ok(())
+}
+
+fn server_config() -> Result<quinn::ServerConfig, Box<dyn std::error::Error>> {
+    let certs = CertificateDer::pem_file_iter("cert.crt")?.collect::<Result<Vec<_>, _>>()?;
+    let key = PrivateKeyDer::from_pem_file("cert.key")?;
+
+    let mut tls = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+
+    let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(tls)?;
+    let mut config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+
+    let mut transport = quinn::TransportConfig::default();
+    transport.max_idle_timeout(Some(Duration::from_millis(5000).try_into()?));
+    transport.max_concurrent_bidi_streams(100u32.into());
+    transport.max_concurrent_uni_streams(100u32.into());
+    config.transport_config(Arc::new(transport));
+    config.migration(false);
+
+    Ok(config)
+}
+
+async fn handle_connection(
+    incoming: quinn::Incoming,
+    state: Arc<AppState>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let connection = incoming.await?;
+    tracing::debug!("New HTTP/3 connection from {}", connection.remote_address());
+
+    let mut h3_conn = h3::server::Connection::new(h3_quinn::Connection::new(connection)).await?;
+
+    loop {
+        match h3_conn.accept().await {
+            Ok(Some(resolver)) => {
+                let state = state.clone();
+
+                tokio::spawn(async move {
+                    if let Err(e) = handle_request(resolver, state).await {
+                        tracing::warn!("Failed to handle HTTP/3 request: {}", e);
+                    }
+                });
+            }
+            Ok(None) => break,
+            Err(e) if e.is_h3_no_error() => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_request(
+    resolver: RequestResolver<h3_quinn::Connection, Bytes>,
+    state: Arc<AppState>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (req, mut stream) = resolver.resolve_request().await?;
+
+    let response = match read_body(&mut stream).await {
+        Ok(body) => route(&req, body, &state).await,
+        Err(response) => response,
+    };
+
+    tracing::debug!("{} {} -> {}", req.method(), req.uri().path(), response.status.as_u16());
+
+    let mut builder = Response::builder().status(response.status);
+    for (name, value) in response.headers {
+        builder = builder.header(name, value);
+    }
+
+    stream.send_response(builder.body(())?).await?;
+    if !response.body.is_empty() && req.method() != Method::HEAD {
+        stream.send_data(response.body).await?;
+    }
+    stream.finish().await?;
+
+    Ok(())
+}
+
+async fn read_body(stream: &mut H3Stream) -> Result<Bytes, HttpResponse> {
+    let mut body = BytesMut::new();
+
+    loop {
+        let chunk = stream
+            .recv_data()
+            .await
+            .map_err(|_| HttpResponse::error(StatusCode::BAD_REQUEST, "Failed to read request body"))?;
+
+        let Some(chunk) = chunk else {
+            break;
+        };
+
+        if body.len() + bytes::Buf::remaining(&chunk) > MAX_BODY_BYTES {
+            return Err(HttpResponse::error(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large"));
+        }
+        body.put(chunk);
+    }
+
+    Ok(body.freeze())
+}
+
+/// Split a request path into its non-empty segments
+fn path_segments(path: &str) -> Vec<&str> {
+    path.split('/').filter(|segment| !segment.is_empty()).collect()
+}
+
+/// Dispatch a request to its handler by method and path
+async fn route(req: &Request<()>, body: Bytes, state: &Arc<AppState>) -> HttpResponse {
+    let segments = path_segments(req.uri().path());
+    let readable = matches!(*req.method(), Method::GET | Method::HEAD);
+
+    match (readable, segments.as_slice()) {
+        (true, []) => HttpResponse::text(StatusCode::OK, "LinkWithMentor HTTP/3 API"),
+        (true, ["health"]) => {
+            let health = crate::health::check_health(state.clone(), state.start_time).await;
+            let status = if health.is_serving() {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            HttpResponse::json(status, health.to_json())
+        }
+        (true, ["ready"]) => {
+            if crate::health::check_readiness(state.clone()).await {
+                HttpResponse::json(StatusCode::OK, r#"{"ready": true}"#)
+            } else {
+                HttpResponse::json(StatusCode::SERVICE_UNAVAILABLE, r#"{"ready": false}"#)
+            }
+        }
+        // Calendar apps can't send a bearer token, so the secret in the URL authenticates the feed
+        (true, ["calendar", file]) => match file.strip_suffix(".ics") {
+            Some(token) => crate::rest::calendar_feed(state, token).await,
+            None => HttpResponse::error(StatusCode::NOT_FOUND, "Not found"),
+        },
+        (false, [] | ["health"] | ["ready"] | ["calendar", _]) => HttpResponse::method_not_allowed("GET, HEAD"),
+        (_, ["v1", rest @ ..]) => crate::rest::handle(req, body, state, rest).await,
+        _ => HttpResponse::error(StatusCode::NOT_FOUND, "Not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_segments() {
+        assert!(path_segments("/").is_empty());
+        assert_eq!(path_segments("/health"), vec!["health"]);
+        assert_eq!(path_segments("//v1/users/42/"), vec!["v1", "users", "42"]);
+    }
+
+    #[test]
+    fn test_error_response() {
+        let response = HttpResponse::error(StatusCode::NOT_FOUND, r#"No "such" thing"#);
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(body["error"], r#"No "such" thing"#);
+
+        let response = HttpResponse::method_not_allowed("GET, HEAD");
+        assert!(response.headers.iter().any(|(name, value)| name == header::ALLOW && value == "GET, HEAD"));
+    }
 }
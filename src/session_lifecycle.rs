@@ -0,0 +1,102 @@
+use tonic::Status;
+
+/// A requested change to a session's status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionAction {
+    Start,
+    Complete,
+    Cancel,
+    Reschedule,
+}
+
+impl SessionAction {
+    fn verb(self) -> &'static str {
+        match self {
+            SessionAction::Start => "start",
+            SessionAction::Complete => "complete",
+            SessionAction::Cancel => "cancel",
+            SessionAction::Reschedule => "reschedule",
+        }
+    }
+
+    /// Status the session moves to, or FAILED_PRECONDITION when the action is not legal from `current`
+    pub fn next_status(self, current: &str) -> Result<&'static str, Status> {
+        match (self, current) {
+            (SessionAction::Start, "scheduled") => Ok("ongoing"),
+            (SessionAction::Complete, "ongoing") => Ok("completed"),
+            (SessionAction::Cancel, "scheduled") => Ok("cancelled"),
+            (SessionAction::Reschedule, "scheduled") => Ok("scheduled"),
+            _ => Err(Status::failed_precondition(format!(
+                "Cannot {} a session that is {}",
+                self.verb(),
+                current
+            ))),
+        }
+    }
+
+    /// Whether only the session's mentor (or an admin) may perform the action
+    pub fn mentor_only(self) -> bool {
+        matches!(self, SessionAction::Start | SessionAction::Complete)
+    }
+}
+
+/// Which sessions of a recurring series a cancel or reschedule applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OccurrenceScope {
+    /// Only the selected session
+    This,
+    /// The selected session and every later occurrence of its series
+    Following,
+}
+
+impl OccurrenceScope {
+    /// `this` (or empty) or `following`
+    pub fn parse(value: &str) -> Result<Self, Status> {
+        match value {
+            "" | "this" => Ok(OccurrenceScope::This),
+            "following" => Ok(OccurrenceScope::Following),
+            other => Err(Status::invalid_argument(format!(
+                "Invalid scope {:?}, expected \"this\" or \"following\"",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legal_transitions() {
+        assert_eq!(SessionAction::Start.next_status("scheduled").unwrap(), "ongoing");
+        assert_eq!(SessionAction::Complete.next_status("ongoing").unwrap(), "completed");
+        assert_eq!(SessionAction::Cancel.next_status("scheduled").unwrap(), "cancelled");
+        assert_eq!(SessionAction::Reschedule.next_status("scheduled").unwrap(), "scheduled");
+    }
+
+    #[test]
+    fn test_illegal_transitions() {
+        let illegal = [
+            (SessionAction::Start, "ongoing"),
+            (SessionAction::Start, "cancelled"),
+            (SessionAction::Complete, "scheduled"),
+            (SessionAction::Complete, "completed"),
+            (SessionAction::Cancel, "ongoing"),
+            (SessionAction::Cancel, "completed"),
+            (SessionAction::Reschedule, "cancelled"),
+        ];
+        for (action, current) in illegal {
+            let status = action.next_status(current).unwrap_err();
+            assert_eq!(status.code(), tonic::Code::FailedPrecondition, "{:?} from {}", action, current);
+        }
+    }
+
+    #[test]
+    fn test_parse_scope() {
+        assert_eq!(OccurrenceScope::parse("").unwrap(), OccurrenceScope::This);
+        assert_eq!(OccurrenceScope::parse("this").unwrap(), OccurrenceScope::This);
+        assert_eq!(OccurrenceScope::parse("following").unwrap(), OccurrenceScope::Following);
+        assert!(OccurrenceScope::parse("all").is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Wire format for instants. Times are stored and compared as UTC
+//! `NaiveDateTime`s and exchanged as RFC 3339 strings.
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, TimeZone};
+use chrono_tz::Tz;
+use tonic::Status;
+
+/// Format used before RFC 3339, read as UTC. Deprecated and still accepted for one release
+pub const LEGACY_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Parse an RFC 3339 instant with any offset into UTC, falling back to [`LEGACY_FORMAT`]
+pub fn parse(value: &str) -> Result<NaiveDateTime, Status> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Ok(at.naive_utc());
+    }
+
+    match NaiveDateTime::parse_from_str(value, LEGACY_FORMAT) {
+        Ok(at) => {
+            tracing::warn!("Deprecated timestamp format {:?}, clients should send RFC 3339", value);
+            Ok(at)
+        }
+        Err(_) => Err(Status::invalid_argument(format!(
+            "Invalid timestamp {:?}, expected RFC 3339 such as 2025-12-01T10:00:00Z",
+            value
+        ))),
+    }
+}
+
+/// RFC 3339 in UTC, e.g. `2025-12-01T10:00:00Z`
+pub fn format_utc(at: NaiveDateTime) -> String {
+    at.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// RFC 3339 with the offset in effect in `time_zone`, e.g. `2025-12-01T11:00:00+01:00`
+pub fn format_local(at: NaiveDateTime, time_zone: Tz) -> String {
+    time_zone
+        .from_utc_datetime(&at)
+        .to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// Serde adapter for UTC `NaiveDateTime` fields, as in `#[serde(with = "crate::timestamp::utc")]`
+pub mod utc {
+    use chrono::NaiveDateTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(at: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_utc(*at))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDateTime, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        super::parse(&value).map_err(|status| serde::de::Error::custom(status.message()))
+    }
+}
+
+/// Like [`utc`] for `Option<NaiveDateTime>` fields
+pub mod utc_option {
+    use chrono::NaiveDateTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(at: &Option<NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        match at {
+            Some(at) => serializer.serialize_some(&super::format_utc(*at)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDateTime>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| super::parse(&value).map_err(|status| serde::de::Error::custom(status.message())))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_normalizes_to_utc() {
+        let expected = NaiveDateTime::parse_from_str("2025-12-01 10:00:00", LEGACY_FORMAT).unwrap();
+
+        assert_eq!(parse("2025-12-01T10:00:00Z").unwrap(), expected);
+        assert_eq!(parse("2025-12-01T11:00:00+01:00").unwrap(), expected);
+        assert_eq!(parse("2025-12-01 10:00:00").unwrap(), expected);
+        assert_eq!(parse("01/12/2025").unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_format() {
+        let at = parse("2025-07-01T10:00:00Z").unwrap();
+
+        assert_eq!(format_utc(at), "2025-07-01T10:00:00Z");
+        assert_eq!(format_local(at, "Europe/Berlin".parse().unwrap()), "2025-07-01T12:00:00+02:00");
+        assert_eq!(format_local(at, "America/New_York".parse().unwrap()), "2025-07-01T06:00:00-04:00");
+    }
+}
@@ -0,0 +1,551 @@
+//! Native Web Push for browser `PushSubscription`s: payloads are encrypted with
+//! `aes128gcm` (RFC 8291) and requests carry a VAPID JWT (RFC 8292), so any
+//! RFC 8030 push service delivers them without going through FCM.
+
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes128Gcm, KeyInit, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hkdf::Hkdf;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::firebase::NotificationType;
+
+/// Record size written in the `aes128gcm` header; the whole payload is one record
+const RECORD_SIZE: u32 = 4096;
+
+/// salt (16) + record size (4) + key ID length (1) + uncompressed P-256 key (65)
+const HEADER_LEN: usize = 86;
+
+/// Push services must accept bodies of 4096 bytes; this leaves room for the header,
+/// the padding delimiter and the GCM tag
+pub const MAX_PLAINTEXT_LEN: usize = 4096 - HEADER_LEN - 1 - 16;
+
+/// How long push services keep a message for an offline browser
+const DEFAULT_TTL_SECONDS: u32 = 24 * 60 * 60;
+
+/// VAPID JWTs are valid this long; RFC 8292 allows at most 24 hours
+const VAPID_TOKEN_LIFETIME_SECONDS: i64 = 12 * 60 * 60;
+
+/// Push services of Chrome, Firefox, Edge and Safari, unless `WEB_PUSH_ALLOWED_HOSTS`
+/// replaces them. `*.` matches any subdomain.
+pub const DEFAULT_ALLOWED_HOSTS: &[&str] = &[
+    "fcm.googleapis.com",
+    "updates.push.services.mozilla.com",
+    "*.notify.windows.com",
+    "*.push.apple.com",
+];
+
+/// Why a Web Push send failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebPushError {
+    /// The browser unsubscribed or the subscription expired (404 or 410)
+    Gone,
+    /// The stored endpoint or keys can't be used
+    InvalidSubscription(String),
+    /// The encrypted payload would exceed what push services must accept
+    PayloadTooLarge,
+    /// The push service is rate limiting us (429)
+    TooManyRequests { retry_after: Option<std::time::Duration> },
+    /// The push service failed (5xx)
+    Unavailable { retry_after: Option<std::time::Duration> },
+    /// The push service refused the request, e.g. a VAPID key it doesn't accept
+    Rejected { status: u16, message: String },
+    /// No VAPID key is configured
+    NotConfigured,
+    /// Any other failure, including the network
+    Other(String),
+}
+
+impl WebPushError {
+    fn from_response(status: u16, body: &str, retry_after: Option<std::time::Duration>) -> Self {
+        match status {
+            404 | 410 => WebPushError::Gone,
+            413 => WebPushError::PayloadTooLarge,
+            429 => WebPushError::TooManyRequests { retry_after },
+            500.. => WebPushError::Unavailable { retry_after },
+            _ => WebPushError::Rejected { status, message: body.chars().take(200).collect() },
+        }
+    }
+
+    /// Whether the subscription will never work again and should be removed
+    pub fn is_token_invalid(&self) -> bool {
+        matches!(self, WebPushError::Gone | WebPushError::InvalidSubscription(_))
+    }
+
+    /// Whether the same message may succeed if sent again later
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            WebPushError::TooManyRequests { .. } | WebPushError::Unavailable { .. } | WebPushError::Other(_)
+        )
+    }
+
+    /// How long the push service asked us to wait before retrying, from `Retry-After`
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            WebPushError::TooManyRequests { retry_after } | WebPushError::Unavailable { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for WebPushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebPushError::Gone => write!(f, "push subscription is gone"),
+            WebPushError::InvalidSubscription(message) => write!(f, "invalid push subscription: {}", message),
+            WebPushError::PayloadTooLarge => write!(f, "payload too large"),
+            WebPushError::TooManyRequests { .. } => write!(f, "too many requests"),
+            WebPushError::Unavailable { .. } => write!(f, "push service unavailable"),
+            WebPushError::Rejected { status, message } => write!(f, "push service rejected the request with {}: {}", status, message),
+            WebPushError::NotConfigured => write!(f, "Web Push is not configured"),
+            WebPushError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for WebPushError {}
+
+/// A browser's push subscription as stored: the endpoint and its base64url keys
+#[derive(Debug, Clone, Copy)]
+pub struct Subscription<'a> {
+    pub endpoint: &'a str,
+    pub p256dh: &'a str,
+    pub auth: &'a str,
+}
+
+/// Decode base64url with or without padding, as browsers and libraries differ
+fn decode_key(value: &str) -> Result<Vec<u8>, String> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|e| format!("not base64url: {}", e))
+}
+
+/// Whether `url` is on one of the `allowed` push service hosts. IP literals only match when
+/// listed exactly, so endpoints can't point requests at internal addresses.
+fn is_allowed_host(allowed: &[String], url: &reqwest::Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    allowed.iter().any(|entry| match entry.strip_prefix("*.") {
+        // `domain` is only set for domain names, never IP addresses
+        Some(suffix) => url
+            .domain()
+            .and_then(|domain| domain.strip_suffix(suffix))
+            .is_some_and(|prefix| prefix.ends_with('.')),
+        None => entry.eq_ignore_ascii_case(host),
+    })
+}
+
+/// Encrypt `plaintext` for a user agent as a single `aes128gcm` record (RFC 8291), using
+/// `sender` as the ephemeral key and `salt` as the record salt
+fn encrypt(
+    plaintext: &[u8],
+    ua_public: &[u8],
+    auth_secret: &[u8],
+    sender: &SecretKey,
+    salt: &[u8; 16],
+) -> Result<Vec<u8>, WebPushError> {
+    if plaintext.len() > MAX_PLAINTEXT_LEN {
+        return Err(WebPushError::PayloadTooLarge);
+    }
+    let invalid = |message: &str| WebPushError::InvalidSubscription(message.to_string());
+
+    let ua_key = PublicKey::from_sec1_bytes(ua_public).map_err(|_| invalid("p256dh is not a P-256 public key"))?;
+    let sender_public = sender.public_key().to_encoded_point(false);
+    let shared = p256::ecdh::diffie_hellman(sender.to_nonzero_scalar(), ua_key.as_affine());
+
+    // IKM mixes the ECDH secret with the subscription's auth secret and both public keys
+    let key_info = [b"WebPush: info\0".as_slice(), ua_public, sender_public.as_bytes()].concat();
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| invalid("key derivation failed"))?;
+
+    let prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .and_then(|()| prk.expand(b"Content-Encoding: nonce\0", &mut nonce))
+        .map_err(|_| invalid("key derivation failed"))?;
+
+    // 0x02 marks the last (and only) record, with no further padding
+    let record = [plaintext, &[2]].concat();
+    let cipher = Aes128Gcm::new_from_slice(&cek).map_err(|_| WebPushError::Other("encryption failed".to_string()))?;
+    let ciphertext = cipher
+        .encrypt(&Nonce::from(nonce), record.as_slice())
+        .map_err(|_| WebPushError::Other("encryption failed".to_string()))?;
+
+    let mut body = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    body.extend_from_slice(salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(sender_public.len() as u8);
+    body.extend_from_slice(sender_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// The JSON a service worker receives, shaped like an FCM message so the same handler
+/// can serve both
+fn push_payload(title: &str, body: &str, payload: &NotificationType) -> Vec<u8> {
+    json!({
+        "notification": { "title": title, "body": body },
+        "data": payload.fcm_data(),
+    })
+    .to_string()
+    .into_bytes()
+}
+
+pub struct WebPushClient {
+    client: reqwest::Client,
+    signing_key: SigningKey,
+    /// Uncompressed P-256 point, as sent in the `k` parameter and used as `applicationServerKey`
+    public_key: Vec<u8>,
+    /// `mailto:` or `https:` contact for push services, per RFC 8292
+    subject: String,
+    /// Hosts endpoints may point at, see `DEFAULT_ALLOWED_HOSTS`
+    allowed_hosts: Vec<String>,
+}
+
+impl WebPushClient {
+    /// `private_key` is the base64url P-256 scalar of the VAPID key pair
+    pub fn new(private_key: &str, subject: &str, allowed_hosts: Vec<String>) -> Result<Self, Box<dyn std::error::Error>> {
+        let key = decode_key(private_key).map_err(|e| format!("VAPID_PRIVATE_KEY is {}", e))?;
+        let secret = SecretKey::from_slice(&key).map_err(|_| "VAPID_PRIVATE_KEY is not a P-256 private key")?;
+        if !subject.starts_with("mailto:") && !subject.starts_with("https://") {
+            return Err("VAPID_SUBJECT must be a mailto: or https: URL".into());
+        }
+
+        Ok(Self {
+            // A redirect could send the signed request to a host that isn't allowed
+            client: reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build()?,
+            public_key: secret.public_key().to_encoded_point(false).as_bytes().to_vec(),
+            signing_key: SigningKey::from(&secret),
+            subject: subject.to_string(),
+            allowed_hosts,
+        })
+    }
+
+    /// The VAPID public key, base64url encoded, for `pushManager.subscribe`
+    pub fn public_key(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.public_key)
+    }
+
+    /// Check a subscription from a browser before it is stored. Returns the keys re-encoded
+    /// as unpadded base64url.
+    pub fn normalize_subscription(&self, endpoint: &str, p256dh: &str, auth: &str) -> Result<(String, String), String> {
+        let url = reqwest::Url::parse(endpoint).map_err(|e| format!("endpoint is not a URL: {}", e))?;
+        if url.scheme() != "https" {
+            return Err("endpoint must be an HTTPS URL".to_string());
+        }
+        if !is_allowed_host(&self.allowed_hosts, &url) {
+            return Err("endpoint is not on a known push service".to_string());
+        }
+
+        let p256dh = decode_key(p256dh).map_err(|e| format!("p256dh is {}", e))?;
+        // RFC 8291 derives keys from the uncompressed point, so store it in that form
+        let p256dh = PublicKey::from_sec1_bytes(&p256dh)
+            .map_err(|_| "p256dh is not a P-256 public key".to_string())?
+            .to_encoded_point(false);
+        let auth = decode_key(auth).map_err(|e| format!("auth is {}", e))?;
+        if auth.len() != 16 {
+            return Err("auth must be 16 bytes".to_string());
+        }
+
+        Ok((URL_SAFE_NO_PAD.encode(p256dh.as_bytes()), URL_SAFE_NO_PAD.encode(auth)))
+    }
+
+    /// `Authorization` header value for a push service, signed for its origin
+    fn vapid_authorization(&self, endpoint: &reqwest::Url) -> String {
+        let claims = json!({
+            "aud": endpoint.origin().ascii_serialization(),
+            "exp": chrono::Utc::now().timestamp() + VAPID_TOKEN_LIFETIME_SECONDS,
+            "sub": self.subject,
+        });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature: Signature = self.signing_key.sign(signing_input.as_bytes());
+
+        format!(
+            "vapid t={}.{}, k={}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.public_key()
+        )
+    }
+
+    pub async fn send_notification(
+        &self,
+        subscription: Subscription<'_>,
+        title: &str,
+        body: &str,
+        payload: &NotificationType,
+    ) -> Result<(), WebPushError> {
+        let invalid = |message: String| WebPushError::InvalidSubscription(message);
+        let endpoint = reqwest::Url::parse(subscription.endpoint).map_err(|e| invalid(format!("endpoint is not a URL: {}", e)))?;
+        // Also checked at send time, for subscriptions stored before a host was removed from the list
+        if !is_allowed_host(&self.allowed_hosts, &endpoint) {
+            return Err(invalid("endpoint is not on a known push service".to_string()));
+        }
+        let ua_public = decode_key(subscription.p256dh).map_err(|e| invalid(format!("p256dh is {}", e)))?;
+        let auth_secret = decode_key(subscription.auth).map_err(|e| invalid(format!("auth is {}", e)))?;
+
+        let sender = SecretKey::random(&mut p256::elliptic_curve::rand_core::OsRng);
+        let salt: [u8; 16] = rand::random();
+        let encrypted = encrypt(&push_payload(title, body, payload), &ua_public, &auth_secret, &sender, &salt)?;
+
+        let (ttl, urgency) = match payload {
+            // Nobody wants to hear about a call long after it ended
+            NotificationType::Call { .. } => (crate::firebase::CALL_TTL_SECONDS, "high"),
+            _ => (DEFAULT_TTL_SECONDS, "normal"),
+        };
+
+        let res = self.client.post(endpoint.clone())
+            .header(reqwest::header::AUTHORIZATION, self.vapid_authorization(&endpoint))
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header(reqwest::header::CONTENT_ENCODING, "aes128gcm")
+            .header("TTL", ttl.to_string())
+            .header("Urgency", urgency)
+            .body(encrypted)
+            .send()
+            .await
+            .map_err(|e| WebPushError::Other(format!("Failed to send Web Push request: {}", e)))?;
+
+        let status = res.status();
+        if !status.is_success() {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(std::time::Duration::from_secs);
+            let text = res.text().await.unwrap_or_default();
+            tracing::debug!("Web Push error response {}: {}", status, text);
+            return Err(WebPushError::from_response(status.as_u16(), &text, retry_after));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn decode(value: &str) -> Vec<u8> {
+        decode_key(value).unwrap()
+    }
+
+    /// Reverse `encrypt` as a user agent would
+    fn decrypt(body: &[u8], ua_private: &SecretKey, auth_secret: &[u8]) -> Vec<u8> {
+        let (salt, rest) = body.split_at(16);
+        let id_len = rest[4] as usize;
+        let (sender_public, ciphertext) = rest[5..].split_at(id_len);
+
+        let sender = PublicKey::from_sec1_bytes(sender_public).unwrap();
+        let shared = p256::ecdh::diffie_hellman(ua_private.to_nonzero_scalar(), sender.as_affine());
+        let ua_public = ua_private.public_key().to_encoded_point(false);
+        let key_info = [b"WebPush: info\0".as_slice(), ua_public.as_bytes(), sender_public].concat();
+        let mut ikm = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(auth_secret), shared.raw_secret_bytes()).expand(&key_info, &mut ikm).unwrap();
+
+        let prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+        let (mut cek, mut nonce) = ([0u8; 16], [0u8; 12]);
+        prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek).unwrap();
+        prk.expand(b"Content-Encoding: nonce\0", &mut nonce).unwrap();
+
+        let mut record = Aes128Gcm::new_from_slice(&cek).unwrap().decrypt(&Nonce::from(nonce), ciphertext).unwrap();
+        assert_eq!(record.pop(), Some(2));
+        record
+    }
+
+    #[test]
+    fn test_encrypt_matches_rfc_8291_example() {
+        // RFC 8291, Appendix A
+        let sender = SecretKey::from_slice(&decode("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw")).unwrap();
+        let ua_public = decode("BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4");
+        let auth_secret = decode("BTBZMqHH6r4Tts7J_aSIgg");
+        let salt: [u8; 16] = decode("DGv6ra1nlYgDCS1FRnbzlw").try_into().unwrap();
+
+        let body = encrypt(b"When I grow up, I want to be a watermelon", &ua_public, &auth_secret, &sender, &salt).unwrap();
+
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(body),
+            "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN"
+        );
+    }
+
+    #[test]
+    fn test_encrypt_rejects_oversized_payload() {
+        let sender = SecretKey::from_slice(&[7; 32]).unwrap();
+        let ua_public = sender.public_key().to_encoded_point(false);
+        let plaintext = vec![b'x'; MAX_PLAINTEXT_LEN + 1];
+        assert_eq!(
+            encrypt(&plaintext, ua_public.as_bytes(), &[0; 16], &sender, &[0; 16]),
+            Err(WebPushError::PayloadTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_normalize_subscription() {
+        let client = client();
+        let normalize = |endpoint: &str, p256dh: &str, auth: &str| client.normalize_subscription(endpoint, p256dh, auth);
+        let p256dh = "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+        let (key, auth) = normalize("https://push.example.net/p/1", p256dh, "BTBZMqHH6r4Tts7J_aSIgg==").unwrap();
+        assert_eq!(key, p256dh);
+        assert_eq!(auth, "BTBZMqHH6r4Tts7J_aSIgg");
+
+        let point = URL_SAFE_NO_PAD.decode(p256dh).unwrap();
+        let compressed = PublicKey::from_sec1_bytes(&point).unwrap().to_encoded_point(true);
+        let (key, _) = normalize("https://push.example.net/p/1", &URL_SAFE_NO_PAD.encode(compressed.as_bytes()), "BTBZMqHH6r4Tts7J_aSIgg").unwrap();
+        assert_eq!(key, p256dh);
+
+        assert!(normalize("http://push.example.net/p/1", p256dh, "BTBZMqHH6r4Tts7J_aSIgg").is_err());
+        assert!(normalize("https://push.example.net/p/1", "BCVx", "BTBZMqHH6r4Tts7J_aSIgg").is_err());
+        assert!(normalize("https://push.example.net/p/1", p256dh, "AAAA").is_err());
+        assert!(normalize("https://169.254.169.254/latest", p256dh, "BTBZMqHH6r4Tts7J_aSIgg").is_err());
+        assert!(normalize("https://internal.example.com/p/1", p256dh, "BTBZMqHH6r4Tts7J_aSIgg").is_err());
+    }
+
+    #[test]
+    fn test_is_allowed_host() {
+        let defaults: Vec<String> = DEFAULT_ALLOWED_HOSTS.iter().map(|host| host.to_string()).collect();
+        let allowed = |url: &str| is_allowed_host(&defaults, &reqwest::Url::parse(url).unwrap());
+
+        assert!(allowed("https://fcm.googleapis.com/fcm/send/abc"));
+        assert!(allowed("https://web.push.apple.com/QG..."));
+        assert!(allowed("https://wns2-par02p.notify.windows.com/w/?token=abc"));
+        assert!(!allowed("https://push.apple.com/abc"));
+        assert!(!allowed("https://evilpush.apple.com/abc"));
+        assert!(!allowed("https://fcm.googleapis.com.evil.net/abc"));
+        assert!(!allowed("https://10.0.0.5/abc"));
+        assert!(!allowed("https://[::1]/abc"));
+        assert!(!allowed("https://localhost/abc"));
+
+        let stub = ["127.0.0.1".to_string()];
+        assert!(is_allowed_host(&stub, &reqwest::Url::parse("http://127.0.0.1:8080/push").unwrap()));
+        assert!(!is_allowed_host(&["*.0.0.1".to_string()], &reqwest::Url::parse("http://127.0.0.1/push").unwrap()));
+    }
+
+    /// Answer one request with `status`, handing back its head and body
+    async fn mock_push_service(status: u16) -> (String, tokio::sync::oneshot::Receiver<(String, Vec<u8>)>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/push/abc", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 8192];
+            let (head, body_start, content_length) = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                    // hyper sends header names in lowercase
+                    let head = String::from_utf8_lossy(&request[..end]).to_string();
+                    let length = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .and_then(|value| value.trim().parse::<usize>().ok())
+                        .unwrap_or_default();
+                    break (head, end + 4, length);
+                }
+            };
+            while request.len() < body_start + content_length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let response = format!("HTTP/1.1 {} Mock\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+            stream.write_all(response.as_bytes()).await.unwrap();
+            let _ = tx.send((head, request[body_start..].to_vec()));
+        });
+
+        (url, rx)
+    }
+
+    /// Allows the mock push service on 127.0.0.1, as `WEB_PUSH_ALLOWED_HOSTS` would in a test setup
+    fn client() -> WebPushClient {
+        let allowed_hosts = vec!["*.example.net".to_string(), "127.0.0.1".to_string()];
+        WebPushClient::new(&URL_SAFE_NO_PAD.encode([3; 32]), "mailto:ops@example.com", allowed_hosts).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_send_notification_to_push_service() {
+        let ua_private = SecretKey::from_slice(&[9; 32]).unwrap();
+        let p256dh = URL_SAFE_NO_PAD.encode(ua_private.public_key().to_encoded_point(false).as_bytes());
+        let auth = URL_SAFE_NO_PAD.encode([5; 16]);
+        let (endpoint, request) = mock_push_service(201).await;
+        let subscription = Subscription { endpoint: &endpoint, p256dh: &p256dh, auth: &auth };
+
+        let client = client();
+        let payload = NotificationType::Call { caller_id: "2".to_string(), call_id: "c".to_string(), is_video: false };
+        client.send_notification(subscription, "Incoming call", "Ada is calling", &payload).await.unwrap();
+
+        let (head, body) = request.await.unwrap();
+        assert!(head.contains("content-encoding: aes128gcm"));
+        assert!(head.contains("ttl: 30"));
+        assert!(head.contains("urgency: high"));
+        assert!(head.contains(&format!("k={}", client.public_key())));
+
+        let message: serde_json::Value = serde_json::from_slice(&decrypt(&body, &ua_private, &[5; 16])).unwrap();
+        assert_eq!(message["notification"]["title"], "Incoming call");
+        assert_eq!(message["data"]["call_id"], "c");
+    }
+
+    #[test]
+    fn test_vapid_token_verifies() {
+        let client = client();
+        let endpoint = reqwest::Url::parse("https://push.example.net:8443/p/1").unwrap();
+        let authorization = client.vapid_authorization(&endpoint);
+
+        let token = authorization.strip_prefix("vapid t=").unwrap().split(',').next().unwrap();
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let signature = Signature::from_slice(&decode(signature)).unwrap();
+        use p256::ecdsa::signature::Verifier;
+        client.signing_key.verifying_key().verify(signing_input.as_bytes(), &signature).unwrap();
+
+        let claims: serde_json::Value = serde_json::from_slice(&decode(signing_input.split('.').nth(1).unwrap())).unwrap();
+        assert_eq!(claims["aud"], "https://push.example.net:8443");
+        assert_eq!(claims["sub"], "mailto:ops@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_gone_subscription_is_invalid() {
+        let ua_private = SecretKey::from_slice(&[9; 32]).unwrap();
+        let p256dh = URL_SAFE_NO_PAD.encode(ua_private.public_key().to_encoded_point(false).as_bytes());
+        let auth = URL_SAFE_NO_PAD.encode([5; 16]);
+        let (endpoint, _request) = mock_push_service(410).await;
+        let subscription = Subscription { endpoint: &endpoint, p256dh: &p256dh, auth: &auth };
+
+        let error = client()
+            .send_notification(subscription, "Title", "Body", &NotificationType::Standard)
+            .await
+            .unwrap_err();
+        assert_eq!(error, WebPushError::Gone);
+        assert!(error.is_token_invalid());
+        assert!(!error.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_endpoint_is_not_sent_to() {
+        let subscription = Subscription { endpoint: "http://10.0.0.5/push", p256dh: "", auth: "" };
+
+        let error = client()
+            .send_notification(subscription, "Title", "Body", &NotificationType::Standard)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WebPushError::InvalidSubscription(_)));
+        assert!(error.is_token_invalid());
+    }
+}